@@ -1,8 +1,19 @@
 import threading
-from typing import Any, Dict, List, Optional, Tuple
+from typing import Any, Dict, List, Optional, Tuple, Union
 
 from ._types import WebsocketMessage
-from .http import HTTP1Settings, HTTP2Settings
+from .http import (
+    ACMESettings,
+    AdaptiveConcurrencySettings,
+    ConcurrencyLimitSettings,
+    ConnectionSettings,
+    HTTP1Settings,
+    HTTP2Settings,
+    LoadSheddingSettings,
+    RateLimitSettings,
+    TLSSettings,
+    WebsocketSettings,
+)
 
 __version__: str
 
@@ -16,20 +27,46 @@ class RSGIHeaders:
 class RSGIHTTPStreamTransport:
     async def send_bytes(self, data: bytes): ...
     async def send_str(self, data: str): ...
+    async def send_trailers(self, headers: List[Tuple[str, str]]): ...
+
+class RSGISSETransport:
+    last_event_id: Optional[str]
+
+    async def send(
+        self, data: str, event: Optional[str] = None, id: Optional[str] = None, retry: Optional[int] = None
+    ): ...
+
+class RSGIGRPCTransport:
+    time_remaining: Optional[float]
+
+    async def recv(self) -> Optional[bytes]: ...
+    def __aiter__(self) -> Any: ...
+    async def send(self, data: bytes): ...
+    async def finish(self, status: int = 0, message: Optional[str] = None, trailers: List[Tuple[str, str]] = []): ...
 
 class RSGIHTTPProtocol:
     async def __call__(self) -> bytes: ...
     def __aiter__(self) -> Any: ...
+    def trailers(self) -> Optional[RSGIHeaders]: ...
+    async def client_disconnect(self): ...
+    def send_early_hints(self, headers: List[Tuple[str, str]]): ...
     def response_empty(self, status: int, headers: List[Tuple[str, str]]): ...
     def response_str(self, status: int, headers: List[Tuple[str, str]], body: str): ...
     def response_bytes(self, status: int, headers: List[Tuple[str, str]], body: bytes): ...
     def response_file(self, status: int, headers: List[Tuple[str, str]], file: str): ...
     def response_stream(self, status: int, headers: List[Tuple[str, str]]) -> RSGIHTTPStreamTransport: ...
+    def response_sse(
+        self, status: int = 200, headers: List[Tuple[str, str]] = [], heartbeat: Optional[float] = 15.0
+    ) -> RSGISSETransport: ...
+    def grpc(self, headers: List[Tuple[str, str]] = []) -> RSGIGRPCTransport: ...
 
 class RSGIWebsocketTransport:
     async def receive(self) -> WebsocketMessage: ...
+    async def receive_fragment(self) -> WebsocketMessage: ...
     async def send_bytes(self, data: bytes): ...
     async def send_str(self, data: str): ...
+    async def send_bytes_fragment(self, data: bytes, fin: bool = False): ...
+    async def send_str_fragment(self, data: str, fin: bool = False): ...
 
 class RSGIWebsocketProtocol:
     async def accept(self) -> RSGIWebsocketTransport: ...
@@ -62,11 +99,25 @@ class ASGIWorker:
         http_mode: str,
         http1_opts: Optional[HTTP1Settings],
         http2_opts: Optional[HTTP2Settings],
+        connection_opts: Optional[ConnectionSettings],
+        rate_limit_opts: Optional[RateLimitSettings],
+        rate_limit_shm: Optional[str],
+        rate_limit_seed: int,
+        load_shedding_opts: Optional[LoadSheddingSettings],
+        concurrency_opts: Optional[ConcurrencyLimitSettings],
+        adaptive_concurrency_opts: Optional[AdaptiveConcurrencySettings],
+        strict_requests: bool,
         websockets_enabled: bool,
+        websockets_opts: Optional[WebsocketSettings],
+        cancel_on_disconnect: bool,
+        stream_buffer_size: int,
         opt_enabled: bool,
         ssl_enabled: bool,
-        ssl_cert: Optional[str],
-        ssl_key: Optional[str],
+        ssl_cert: Optional[Union[str, bytes]],
+        ssl_key: Optional[Union[str, bytes]],
+        ssl_key_password: Optional[str],
+        ssl_opts: Optional[TLSSettings],
+        ssl_acme: Optional[str],
     ) -> ASGIWorker: ...
 
 class WSGIWorker:
@@ -80,9 +131,20 @@ class WSGIWorker:
         http_mode: str,
         http1_opts: Optional[HTTP1Settings],
         http2_opts: Optional[HTTP2Settings],
+        connection_opts: Optional[ConnectionSettings],
+        rate_limit_opts: Optional[RateLimitSettings],
+        rate_limit_shm: Optional[str],
+        rate_limit_seed: int,
+        load_shedding_opts: Optional[LoadSheddingSettings],
+        concurrency_opts: Optional[ConcurrencyLimitSettings],
+        adaptive_concurrency_opts: Optional[AdaptiveConcurrencySettings],
+        strict_requests: bool,
         ssl_enabled: bool,
-        ssl_cert: Optional[str],
-        ssl_key: Optional[str],
+        ssl_cert: Optional[Union[str, bytes]],
+        ssl_key: Optional[Union[str, bytes]],
+        ssl_key_password: Optional[str],
+        ssl_opts: Optional[TLSSettings],
+        ssl_acme: Optional[str],
     ) -> WSGIWorker: ...
 
 class RSGIWorker:
@@ -96,11 +158,25 @@ class RSGIWorker:
         http_mode: str,
         http1_opts: Optional[HTTP1Settings],
         http2_opts: Optional[HTTP2Settings],
+        connection_opts: Optional[ConnectionSettings],
+        rate_limit_opts: Optional[RateLimitSettings],
+        rate_limit_shm: Optional[str],
+        rate_limit_seed: int,
+        load_shedding_opts: Optional[LoadSheddingSettings],
+        concurrency_opts: Optional[ConcurrencyLimitSettings],
+        adaptive_concurrency_opts: Optional[AdaptiveConcurrencySettings],
+        strict_requests: bool,
         websockets_enabled: bool,
+        websockets_opts: Optional[WebsocketSettings],
+        cancel_on_disconnect: bool,
+        stream_buffer_size: int,
         opt_enabled: bool,
         ssl_enabled: bool,
-        ssl_cert: Optional[str],
-        ssl_key: Optional[str],
+        ssl_cert: Optional[Union[str, bytes]],
+        ssl_key: Optional[Union[str, bytes]],
+        ssl_key_password: Optional[str],
+        ssl_opts: Optional[TLSSettings],
+        ssl_acme: Optional[str],
     ) -> RSGIWorker: ...
 
 class ListenerHolder:
@@ -108,6 +184,20 @@ class ListenerHolder:
     def from_address(cls, address: str, port: int, backlog: int) -> ListenerHolder: ...
     def get_fd(self) -> Any: ...
 
+def validate_tls_config(
+    cert: Union[str, bytes],
+    key: Optional[Union[str, bytes]] = None,
+    key_password: Optional[str] = None,
+    http_mode: str = 'auto',
+    settings: Optional[TLSSettings] = None,
+    acme_storage: Optional[str] = None,
+) -> List[str]: ...
+
+class ACMEManager:
+    def __new__(cls, settings: ACMESettings, address: str) -> ACMEManager: ...
+    def start(self): ...
+    def stop(self): ...
+
 class CallbackScheduler:
     _loop: Any
     _ctx: Any
@@ -4,3 +4,4 @@ from typing import Union
 class WebsocketMessage:
     kind: int
     data: Union[bytes, str]
+    fin: bool
@@ -7,7 +7,18 @@ import click
 
 from .constants import HTTPModes, Interfaces, Loops, TaskImpl, ThreadModes
 from .errors import FatalError
-from .http import HTTP1Settings, HTTP2Settings
+from .http import (
+    ACMESettings,
+    AdaptiveConcurrencySettings,
+    ConcurrencyLimitSettings,
+    ConnectionSettings,
+    HTTP1Settings,
+    HTTP2Settings,
+    LoadSheddingSettings,
+    RateLimitSettings,
+    TLSSettings,
+    WebsocketSettings,
+)
 from .log import LogLevels
 from .server import Granian
 
@@ -83,6 +94,16 @@ def option(*param_decls: str, cls: Optional[Type[click.Option]] = None, **attrs:
     default=TaskImpl.auto,
     help='Async task implementation to use',
 )
+@option(
+    '--cancel-on-disconnect/--no-cancel-on-disconnect',
+    default=False,
+    help='Cancel the application task when the client disconnects (requires asyncio task implementation)',
+)
+@option(
+    '--strict-requests/--no-strict-requests',
+    default=False,
+    help='Reject ambiguous or malformed requests with a 400 response before invoking the application',
+)
 @option(
     '--backlog',
     type=click.IntRange(128),
@@ -95,6 +116,122 @@ def option(*param_decls: str, cls: Optional[Type[click.Option]] = None, **attrs:
     show_default='backlog/workers',
     help='Maximum number of requests to process concurrently (per worker)',
 )
+@option(
+    '--stream-buffer-size',
+    type=click.IntRange(1),
+    default=65536,
+    help='Maximum amount of bytes to buffer for streamed responses (per request)',
+)
+@option(
+    '--connections-max-per-ip',
+    type=click.IntRange(1),
+    help='Maximum number of concurrent connections from a single IP address (per worker)',
+)
+@option(
+    '--connections-allow',
+    help='IP address or network (CIDR notation) allowed to connect, all others get rejected',
+    multiple=True,
+)
+@option(
+    '--connections-deny',
+    help='IP address or network (CIDR notation) to reject connections from',
+    multiple=True,
+)
+@option(
+    '--rate-limit',
+    type=click.FloatRange(0, min_open=True),
+    help='Maximum number of requests per second allowed for every rate limit key (enables rate limiting)',
+)
+@option(
+    '--rate-limit-burst',
+    type=click.IntRange(1),
+    show_default='rate',
+    help='Maximum number of requests allowed in a burst for every rate limit key',
+)
+@option(
+    '--rate-limit-key',
+    type=click.Choice(['ip', 'header', 'path']),
+    default='ip',
+    help='Key to apply rate limits on: client IP address, value of the rate limit header or path prefix',
+)
+@option(
+    '--rate-limit-header',
+    help='Header to use as rate limit key (falls back to client IP address when missing)',
+)
+@option(
+    '--rate-limit-path',
+    'rate_limit_paths',
+    help='Path prefix to apply rate limits on, requests not matching any prefix are not limited',
+    multiple=True,
+)
+@option(
+    '--rate-limit-shared/--no-rate-limit-shared',
+    default=False,
+    help='Share rate limits across workers (not available on Windows)',
+)
+@option(
+    '--load-shedding/--no-load-shedding',
+    default=False,
+    help='Reply with 503 to connections exceeding the backpressure, instead of holding them in the backlog',
+)
+@option(
+    '--load-shedding-queue-timeout',
+    type=click.FloatRange(0),
+    default=0,
+    help='Maximum number of seconds new connections can wait for the backpressure to free up before being shed',
+)
+@option(
+    '--load-shedding-retry-after',
+    type=click.IntRange(0),
+    default=1,
+    help='Value of the Retry-After header sent with shed responses (in seconds)',
+)
+@option(
+    '--concurrency-limit',
+    type=click.IntRange(1),
+    help='Maximum number of requests to process concurrently, regardless of the connections (per worker)',
+)
+@option(
+    '--concurrency-limit-queue-size',
+    type=click.IntRange(0),
+    default=0,
+    help='Maximum number of requests waiting for the concurrency limit to free up, others get a 503 response',
+)
+@option(
+    '--concurrency-limit-queue-timeout',
+    type=click.FloatRange(0),
+    show_default='unlimited',
+    help='Maximum number of seconds requests can wait for the concurrency limit to free up',
+)
+@option(
+    '--adaptive-concurrency/--no-adaptive-concurrency',
+    default=False,
+    help='Adjust the backpressure of every worker based on the application latency',
+)
+@option(
+    '--adaptive-concurrency-initial-limit',
+    type=click.IntRange(1),
+    default=AdaptiveConcurrencySettings.initial_limit,
+    help='Initial value of the adaptive backpressure (per worker)',
+)
+@option(
+    '--adaptive-concurrency-min-limit',
+    type=click.IntRange(1),
+    default=AdaptiveConcurrencySettings.min_limit,
+    help='Minimum value of the adaptive backpressure (per worker)',
+)
+@option(
+    '--adaptive-concurrency-max-limit',
+    type=click.IntRange(1),
+    show_default='backpressure',
+    help='Maximum value of the adaptive backpressure (per worker)',
+)
+@option(
+    '--adaptive-concurrency-smoothing',
+    type=click.FloatRange(0, 1, min_open=True),
+    default=AdaptiveConcurrencySettings.smoothing,
+    help='Smoothing factor applied to adaptive backpressure changes',
+)
 @option(
     '--http1-buffer-size',
     type=click.IntRange(8192),
@@ -111,6 +248,30 @@ def option(*param_decls: str, cls: Optional[Type[click.Option]] = None, **attrs:
     default=HTTP1Settings.pipeline_flush,
     help='Aggregates HTTP/1 flushes to better support pipelined responses (experimental)',
 )
+@option(
+    '--http1-max-headers',
+    type=click.IntRange(1),
+    default=HTTP1Settings.max_headers,
+    help='Sets the maximum number of headers accepted in HTTP/1 requests',
+)
+@option(
+    '--http1-header-read-timeout',
+    type=click.IntRange(0),
+    default=HTTP1Settings.header_read_timeout,
+    help='Sets a timeout (in seconds) for reading HTTP/1 request headers (0 disables it)',
+)
+@option(
+    '--http1-keep-alive-timeout',
+    type=click.IntRange(1),
+    default=HTTP1Settings.keep_alive_timeout,
+    help='Sets a timeout (in seconds) to close idle HTTP/1 keep-alive connections',
+)
+@option(
+    '--http1-max-requests',
+    type=click.IntRange(1),
+    default=HTTP1Settings.max_requests,
+    help='Sets the maximum number of requests served by every HTTP/1 keep-alive connection',
+)
 @option(
     '--http2-adaptive-window/--no-http2-adaptive-window',
     default=HTTP2Settings.adaptive_window,
@@ -164,6 +325,45 @@ def option(*param_decls: str, cls: Optional[Type[click.Option]] = None, **attrs:
     default=HTTP2Settings.max_send_buffer_size,
     help='Set the maximum write buffer size for each HTTP/2 stream',
 )
+@option(
+    '--http2-max-pending-accept-reset-streams',
+    type=click.IntRange(1),
+    default=HTTP2Settings.max_pending_accept_reset_streams,
+    help='Sets the max number of HTTP2 streams reset by the client before being accepted',
+)
+@option(
+    '--http2-max-local-error-reset-streams',
+    type=click.IntRange(0),
+    default=HTTP2Settings.max_local_error_reset_streams,
+    help=(
+        'Sets the max number of HTTP2 streams reset by the server due to client errors '
+        '(0 disables the limit, only applies to HTTP mode 2)'
+    ),
+)
+@option(
+    '--http2-max-resets-per-second',
+    type=click.IntRange(0),
+    default=HTTP2Settings.max_resets_per_second,
+    help='Sets the max number of HTTP2 RST_STREAM frames a client can send per second (0 disables the limit)',
+)
+@option(
+    '--http2-max-control-frames-per-second',
+    type=click.IntRange(0),
+    default=HTTP2Settings.max_control_frames_per_second,
+    help='Sets the max number of HTTP2 SETTINGS and PING frames a client can send per second (0 disables the limit)',
+)
+@option(
+    '--ws-max-message-size',
+    type=click.IntRange(1),
+    default=WebsocketSettings.max_message_size,
+    help='Sets the maximum size of incoming websocket messages',
+)
+@option(
+    '--ws-max-frame-size',
+    type=click.IntRange(1),
+    default=WebsocketSettings.max_frame_size,
+    help='Sets the maximum size of incoming websocket frames',
+)
 @option('--log/--no-log', 'log_enabled', default=True, help='Enable logging')
 @option('--log-level', type=EnumType(LogLevels), default=LogLevels.info, help='Log level')
 @option(
@@ -176,14 +376,79 @@ def option(*param_decls: str, cls: Optional[Type[click.Option]] = None, **attrs:
 @option(
     '--ssl-certificate',
     type=click.Path(exists=True, file_okay=True, dir_okay=False, readable=True, path_type=pathlib.Path),
-    help='SSL certificate file',
+    help='SSL certificate file (PEM, DER or PKCS#12 bundle)',
 )
 @option(
     '--ssl-keyfile',
     type=click.Path(exists=True, file_okay=True, dir_okay=False, readable=True, path_type=pathlib.Path),
-    help='SSL key file',
+    help='SSL key file (PEM or DER), can be omitted when included in the certificate file',
 )
 @option('--ssl-keyfile-password', help='SSL key password')
+@option(
+    '--ssl-min-version',
+    type=click.Choice(['1.2', '1.3']),
+    default=TLSSettings.min_version,
+    help='Minimum TLS protocol version to accept',
+)
+@option(
+    '--ssl-ciphers',
+    help='TLS cipher suites to allow (IANA names, eg: TLS_AES_256_GCM_SHA384)',
+    show_default='all supported',
+    multiple=True,
+)
+@option(
+    '--ssl-session-tickets/--no-ssl-session-tickets',
+    default=TLSSettings.session_tickets,
+    help='Enables or disables stateless TLS session tickets (keys get rotated every 6 hours)',
+)
+@option(
+    '--ssl-session-cache-size',
+    type=click.IntRange(0),
+    default=TLSSettings.session_cache_size,
+    help='Sets the size of the stateful TLS session cache (0 disables it)',
+)
+@option(
+    '--ssl-keylog/--no-ssl-keylog',
+    default=TLSSettings.keylog,
+    help='Export TLS session keys to the file in SSLKEYLOGFILE env var (for debugging purposes only)',
+)
+@option(
+    '--ssl-ocsp-response',
+    type=click.Path(exists=True, file_okay=True, dir_okay=False, readable=True, path_type=pathlib.Path),
+    help='DER encoded OCSP response file to staple to TLS handshakes (reloaded when changed)',
+)
+@option(
+    '--ssl-acme-domain',
+    'ssl_acme_domains',
+    help='Domain to obtain the TLS certificate for via ACME (enables automatic certificate management)',
+    multiple=True,
+)
+@option(
+    '--ssl-acme-storage',
+    type=click.Path(file_okay=False, dir_okay=True, writable=True, path_type=pathlib.Path),
+    default=pathlib.Path('.granian-acme'),
+    help='Directory to store ACME account and certificates into',
+)
+@option('--ssl-acme-server', default=ACMESettings.server, help='ACME directory URL')
+@option('--ssl-acme-contact', 'ssl_acme_contacts', help='ACME account contact email', multiple=True)
+@option(
+    '--ssl-acme-challenge',
+    type=click.Choice(['tls-alpn-01', 'http-01']),
+    default=ACMESettings.challenge,
+    help='ACME challenge type to use',
+)
+@option(
+    '--ssl-acme-http-port',
+    type=click.IntRange(1, 65535),
+    default=ACMESettings.http_port,
+    help='Port to answer ACME HTTP-01 challenges on',
+)
+@option(
+    '--ssl-acme-renew-before',
+    type=click.IntRange(1),
+    default=ACMESettings.renew_before,
+    help='Days before expiration to renew ACME certificates',
+)
 @option('--url-path-prefix', help='URL path prefix the app is mounted on')
 @option(
     '--respawn-failed-workers/--no-respawn-failed-workers',
@@ -268,11 +533,38 @@ def cli(
     threading_mode: ThreadModes,
     loop: Loops,
     task_impl: TaskImpl,
+    cancel_on_disconnect: bool,
+    strict_requests: bool,
     backlog: int,
     backpressure: Optional[int],
+    stream_buffer_size: int,
+    connections_max_per_ip: Optional[int],
+    connections_allow: Optional[List[str]],
+    connections_deny: Optional[List[str]],
+    rate_limit: Optional[float],
+    rate_limit_burst: Optional[int],
+    rate_limit_key: str,
+    rate_limit_header: Optional[str],
+    rate_limit_paths: Optional[List[str]],
+    rate_limit_shared: bool,
+    load_shedding: bool,
+    load_shedding_queue_timeout: float,
+    load_shedding_retry_after: int,
+    concurrency_limit: Optional[int],
+    concurrency_limit_queue_size: int,
+    concurrency_limit_queue_timeout: Optional[float],
+    adaptive_concurrency: bool,
+    adaptive_concurrency_initial_limit: int,
+    adaptive_concurrency_min_limit: int,
+    adaptive_concurrency_max_limit: Optional[int],
+    adaptive_concurrency_smoothing: float,
     http1_buffer_size: int,
     http1_keep_alive: bool,
     http1_pipeline_flush: bool,
+    http1_max_headers: int,
+    http1_header_read_timeout: int,
+    http1_keep_alive_timeout: Optional[int],
+    http1_max_requests: Optional[int],
     http2_adaptive_window: bool,
     http2_initial_connection_window_size: int,
     http2_initial_stream_window_size: int,
@@ -282,6 +574,12 @@ def cli(
     http2_max_frame_size: int,
     http2_max_headers_size: int,
     http2_max_send_buffer_size: int,
+    http2_max_pending_accept_reset_streams: int,
+    http2_max_local_error_reset_streams: int,
+    http2_max_resets_per_second: int,
+    http2_max_control_frames_per_second: int,
+    ws_max_message_size: int,
+    ws_max_frame_size: int,
     log_enabled: bool,
     log_access_enabled: bool,
     log_access_fmt: Optional[str],
@@ -290,6 +588,19 @@ def cli(
     ssl_certificate: Optional[pathlib.Path],
     ssl_keyfile: Optional[pathlib.Path],
     ssl_keyfile_password: Optional[str],
+    ssl_min_version: str,
+    ssl_ciphers: Optional[List[str]],
+    ssl_session_tickets: bool,
+    ssl_session_cache_size: int,
+    ssl_keylog: bool,
+    ssl_ocsp_response: Optional[pathlib.Path],
+    ssl_acme_domains: Optional[List[str]],
+    ssl_acme_storage: pathlib.Path,
+    ssl_acme_server: str,
+    ssl_acme_contacts: Optional[List[str]],
+    ssl_acme_challenge: str,
+    ssl_acme_http_port: int,
+    ssl_acme_renew_before: int,
     url_path_prefix: Optional[str],
     respawn_failed_workers: bool,
     respawn_interval: float,
@@ -324,12 +635,22 @@ def cli(
         threading_mode=threading_mode,
         loop=loop,
         task_impl=task_impl,
+        cancel_on_disconnect=cancel_on_disconnect,
+        strict_requests=strict_requests,
         http=http,
         websockets=websockets,
+        websockets_settings=WebsocketSettings(max_message_size=ws_max_message_size, max_frame_size=ws_max_frame_size),
         backlog=backlog,
         backpressure=backpressure,
+        stream_buffer_size=stream_buffer_size,
         http1_settings=HTTP1Settings(
-            keep_alive=http1_keep_alive, max_buffer_size=http1_buffer_size, pipeline_flush=http1_pipeline_flush
+            keep_alive=http1_keep_alive,
+            max_buffer_size=http1_buffer_size,
+            pipeline_flush=http1_pipeline_flush,
+            max_headers=http1_max_headers,
+            header_read_timeout=http1_header_read_timeout or None,
+            keep_alive_timeout=http1_keep_alive_timeout,
+            max_requests=http1_max_requests,
         ),
         http2_settings=HTTP2Settings(
             adaptive_window=http2_adaptive_window,
@@ -341,7 +662,46 @@ def cli(
             max_frame_size=http2_max_frame_size,
             max_headers_size=http2_max_headers_size,
             max_send_buffer_size=http2_max_send_buffer_size,
+            max_pending_accept_reset_streams=http2_max_pending_accept_reset_streams,
+            max_local_error_reset_streams=http2_max_local_error_reset_streams or None,
+            max_resets_per_second=http2_max_resets_per_second or None,
+            max_control_frames_per_second=http2_max_control_frames_per_second or None,
+        ),
+        connection_settings=ConnectionSettings(
+            max_per_ip=connections_max_per_ip,
+            allow=list(connections_allow) or None,
+            deny=list(connections_deny) or None,
         ),
+        rate_limit_settings=RateLimitSettings(
+            rate=rate_limit,
+            burst=rate_limit_burst,
+            key=rate_limit_key,
+            header=rate_limit_header,
+            paths=list(rate_limit_paths) or None,
+            shared=rate_limit_shared,
+        )
+        if rate_limit
+        else None,
+        load_shedding_settings=LoadSheddingSettings(
+            queue_timeout=load_shedding_queue_timeout, retry_after=load_shedding_retry_after
+        )
+        if load_shedding
+        else None,
+        concurrency_settings=ConcurrencyLimitSettings(
+            max_requests=concurrency_limit,
+            queue_size=concurrency_limit_queue_size,
+            queue_timeout=concurrency_limit_queue_timeout,
+        )
+        if concurrency_limit
+        else None,
+        adaptive_concurrency_settings=AdaptiveConcurrencySettings(
+            initial_limit=adaptive_concurrency_initial_limit,
+            min_limit=adaptive_concurrency_min_limit,
+            max_limit=adaptive_concurrency_max_limit,
+            smoothing=adaptive_concurrency_smoothing,
+        )
+        if adaptive_concurrency
+        else None,
         log_enabled=log_enabled,
         log_level=log_level,
         log_dictconfig=log_dictconfig,
@@ -350,6 +710,25 @@ def cli(
         ssl_cert=ssl_certificate,
         ssl_key=ssl_keyfile,
         ssl_key_password=ssl_keyfile_password,
+        ssl_settings=TLSSettings(
+            min_version=ssl_min_version,
+            ciphers=list(ssl_ciphers) or None,
+            session_tickets=ssl_session_tickets,
+            session_cache_size=ssl_session_cache_size,
+            keylog=ssl_keylog,
+            ocsp_response=ssl_ocsp_response,
+        ),
+        ssl_acme=ACMESettings(
+            domains=list(ssl_acme_domains),
+            storage=ssl_acme_storage,
+            server=ssl_acme_server,
+            contact=list(ssl_acme_contacts) or None,
+            challenge=ssl_acme_challenge,
+            http_port=ssl_acme_http_port,
+            renew_before=ssl_acme_renew_before,
+        )
+        if ssl_acme_domains
+        else None,
         url_path_prefix=url_path_prefix,
         respawn_failed_workers=respawn_failed_workers,
         respawn_interval=respawn_interval,
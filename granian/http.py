@@ -1,5 +1,6 @@
 from dataclasses import dataclass
-from typing import Optional
+from pathlib import Path
+from typing import List, Optional
 
 
 @dataclass
@@ -7,6 +8,10 @@ class HTTP1Settings:
     keep_alive: bool = True
     max_buffer_size: int = 8192 + 4096 * 100
     pipeline_flush: bool = False
+    max_headers: int = 100
+    header_read_timeout: Optional[int] = 30
+    keep_alive_timeout: Optional[int] = None
+    max_requests: Optional[int] = None
 
 
 @dataclass
@@ -20,3 +25,72 @@ class HTTP2Settings:
     max_frame_size: int = 1024 * 16
     max_headers_size: int = 16 * 1024 * 1024
     max_send_buffer_size: int = 1024 * 400
+    max_pending_accept_reset_streams: int = 20
+    max_local_error_reset_streams: Optional[int] = 1024
+    max_resets_per_second: Optional[int] = 200
+    max_control_frames_per_second: Optional[int] = 100
+
+
+@dataclass
+class ConnectionSettings:
+    max_per_ip: Optional[int] = None
+    allow: Optional[List[str]] = None
+    deny: Optional[List[str]] = None
+
+
+@dataclass
+class RateLimitSettings:
+    rate: float
+    burst: Optional[int] = None
+    key: str = 'ip'
+    header: Optional[str] = None
+    paths: Optional[List[str]] = None
+    shared: bool = False
+
+
+@dataclass
+class LoadSheddingSettings:
+    queue_timeout: float = 0
+    retry_after: int = 1
+
+
+@dataclass
+class ConcurrencyLimitSettings:
+    max_requests: int
+    queue_size: int = 0
+    queue_timeout: Optional[float] = None
+
+
+@dataclass
+class AdaptiveConcurrencySettings:
+    initial_limit: int = 20
+    min_limit: int = 1
+    max_limit: Optional[int] = None
+    smoothing: float = 0.2
+
+
+@dataclass
+class WebsocketSettings:
+    max_message_size: Optional[int] = 64 * 1024 * 1024
+    max_frame_size: Optional[int] = 16 * 1024 * 1024
+
+
+@dataclass
+class TLSSettings:
+    min_version: str = '1.3'
+    ciphers: Optional[List[str]] = None
+    session_tickets: bool = False
+    session_cache_size: int = 256
+    keylog: bool = False
+    ocsp_response: Optional[Path] = None
+
+
+@dataclass
+class ACMESettings:
+    domains: List[str]
+    storage: Path
+    server: str = 'https://acme-v02.api.letsencrypt.org/directory'
+    contact: Optional[List[str]] = None
+    challenge: str = 'tls-alpn-01'
+    http_port: int = 80
+    renew_before: int = 30
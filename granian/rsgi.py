@@ -1,7 +1,7 @@
 import time
 from enum import Enum
 from functools import wraps
-from typing import Optional, Union
+from typing import AsyncIterable, Optional, Union
 
 from ._granian import (
     RSGIHeaders as Headers,
@@ -24,6 +24,10 @@ class Scope:
     path: str
     query_string: str
     authority: Optional[str]
+    tls_protocol: Optional[str]
+    tls_cipher: Optional[str]
+    tls_alpn_protocol: Optional[str]
+    tls_sni: Optional[str]
 
     @property
     def headers(self) -> Headers: ...
@@ -38,6 +42,23 @@ class WebsocketMessageType(int, Enum):
 class WebsocketMessage:
     kind: WebsocketMessageType
     data: Union[bytes, str]
+    fin: bool
+
+
+async def send_fragments(transport, chunks: AsyncIterable[Union[bytes, str]]):
+    """Sends the chunks produced by the given async iterator as fragments of a single websocket message.
+
+    The type of the message is defined by the first chunk, and the message gets completed
+    once the iterator is exhausted; when the iterator raises, the message is left incomplete.
+    """
+    text = False
+    async for chunk in chunks:
+        text = isinstance(chunk, str)
+        await (transport.send_str_fragment if text else transport.send_bytes_fragment)(chunk)
+    if text:
+        await transport.send_str_fragment('', fin=True)
+    else:
+        await transport.send_bytes_fragment(b'', fin=True)
 
 
 class _LoggingProto:
@@ -53,6 +74,15 @@ class _LoggingProto:
     def __aiter__(self):
         return self.inner.__aiter__()
 
+    def trailers(self):
+        return self.inner.trailers()
+
+    def client_disconnect(self):
+        return self.inner.client_disconnect()
+
+    def send_early_hints(self, headers):
+        return self.inner.send_early_hints(headers)
+
     def response_empty(self, status, headers):
         self.status = status
         return self.inner.response_empty(status, headers)
@@ -73,6 +103,14 @@ class _LoggingProto:
         self.status = status
         return self.inner.response_stream(status, headers)
 
+    def response_sse(self, status=200, headers=[], heartbeat=15.0):
+        self.status = status
+        return self.inner.response_sse(status, headers, heartbeat)
+
+    def grpc(self, headers=[]):
+        self.status = 200
+        return self.inner.grpc(headers)
+
 
 def _callback_wrapper(callback, access_log_fmt=False):
     async def _http_logger(scope, proto):
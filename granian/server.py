@@ -1,26 +1,42 @@
 from __future__ import annotations
 
+import dataclasses
 import errno
+import ipaddress
+import math
 import multiprocessing
 import os
+import re
+import secrets
 import socket
-import ssl
 import sys
 import threading
 import time
 from functools import partial
+from multiprocessing.shared_memory import SharedMemory
 from pathlib import Path
-from typing import Any, Callable, Dict, List, Optional, Sequence, Tuple, Type
+from typing import Any, Callable, Dict, List, Optional, Sequence, Tuple, Type, Union
 
 from ._futures import _future_watcher_wrapper, _new_cbscheduler
-from ._granian import ASGIWorker, RSGIWorker, WSGIWorker
+from ._granian import ACMEManager, ASGIWorker, RSGIWorker, WSGIWorker, validate_tls_config
 from ._imports import anyio, setproctitle, watchfiles
 from ._internal import load_target
 from ._signals import set_main_signals
 from .asgi import LifespanProtocol, _callback_wrapper as _asgi_call_wrap
 from .constants import HTTPModes, Interfaces, Loops, TaskImpl, ThreadModes
 from .errors import ConfigurationError, PidFileError
-from .http import HTTP1Settings, HTTP2Settings
+from .http import (
+    ACMESettings,
+    AdaptiveConcurrencySettings,
+    ConcurrencyLimitSettings,
+    ConnectionSettings,
+    HTTP1Settings,
+    HTTP2Settings,
+    LoadSheddingSettings,
+    RateLimitSettings,
+    TLSSettings,
+    WebsocketSettings,
+)
 from .log import DEFAULT_ACCESSLOG_FMT, LogLevels, configure_logging, logger
 from .net import SocketHolder
 from .rsgi import _callback_wrapper as _rsgi_call_wrap
@@ -84,18 +100,29 @@ class Granian:
         task_impl: TaskImpl = TaskImpl.auto,
         http: HTTPModes = HTTPModes.auto,
         websockets: bool = True,
+        websockets_settings: Optional[WebsocketSettings] = None,
         backlog: int = 1024,
         backpressure: Optional[int] = None,
         http1_settings: Optional[HTTP1Settings] = None,
         http2_settings: Optional[HTTP2Settings] = None,
+        connection_settings: Optional[ConnectionSettings] = None,
+        rate_limit_settings: Optional[RateLimitSettings] = None,
+        load_shedding_settings: Optional[LoadSheddingSettings] = None,
+        concurrency_settings: Optional[ConcurrencyLimitSettings] = None,
+        adaptive_concurrency_settings: Optional[AdaptiveConcurrencySettings] = None,
+        strict_requests: bool = False,
+        cancel_on_disconnect: bool = False,
+        stream_buffer_size: int = 65536,
         log_enabled: bool = True,
         log_level: LogLevels = LogLevels.info,
         log_dictconfig: Optional[Dict[str, Any]] = None,
         log_access: bool = False,
         log_access_format: Optional[str] = None,
-        ssl_cert: Optional[Path] = None,
-        ssl_key: Optional[Path] = None,
+        ssl_cert: Optional[Union[Path, bytes]] = None,
+        ssl_key: Optional[Union[Path, bytes]] = None,
         ssl_key_password: Optional[str] = None,
+        ssl_settings: Optional[TLSSettings] = None,
+        ssl_acme: Optional[ACMESettings] = None,
         url_path_prefix: Optional[str] = None,
         respawn_failed_workers: bool = False,
         respawn_interval: float = 3.5,
@@ -122,6 +149,7 @@ class Granian:
         self.task_impl = task_impl
         self.http = http
         self.websockets = websockets
+        self.websockets_settings = websockets_settings
         self.backlog = max(128, backlog)
         self.backpressure = max(1, backpressure or self.backlog // self.workers)
         self.blocking_threads = (
@@ -133,6 +161,14 @@ class Granian:
         )
         self.http1_settings = http1_settings
         self.http2_settings = http2_settings
+        self.connection_settings = connection_settings
+        self.rate_limit_settings = rate_limit_settings
+        self.load_shedding_settings = load_shedding_settings
+        self.concurrency_settings = concurrency_settings
+        self.adaptive_concurrency_settings = adaptive_concurrency_settings
+        self.strict_requests = strict_requests
+        self.cancel_on_disconnect = cancel_on_disconnect
+        self.stream_buffer_size = max(1, stream_buffer_size)
         self.log_enabled = log_enabled
         self.log_level = log_level
         self.log_config = log_dictconfig
@@ -155,7 +191,12 @@ class Granian:
 
         configure_logging(self.log_level, self.log_config, self.log_enabled)
 
-        self.build_ssl_context(ssl_cert, ssl_key, ssl_key_password)
+        self.ssl_acme = ssl_acme
+        self.build_ssl_context(ssl_cert, ssl_key, ssl_key_password, ssl_settings, ssl_acme)
+        self._acme = None
+        self._rate_limit_shm = None
+        # NOTE: keeps rate limiting buckets unpredictable, shared by all the workers (and respawns)
+        self._rate_limit_seed = secrets.randbits(64)
         self._shd = None
         self._sfd = None
         self.procs: List[Worker] = []
@@ -167,17 +208,161 @@ class Granian:
         self.lifetime_signal = False
         self.pid = None
 
-    def build_ssl_context(self, cert: Optional[Path], key: Optional[Path], password: Optional[str]):
-        if not (cert and key):
-            self.ssl_ctx = (False, None, None)
+    def build_ssl_context(
+        self,
+        cert: Optional[Union[Path, bytes]],
+        key: Optional[Union[Path, bytes]],
+        password: Optional[str],
+        settings: Optional[TLSSettings] = None,
+        acme: Optional[ACMESettings] = None,
+    ):
+        # certificates managed by ACME get stored (and reloaded by workers) within its storage directory
+        if acme:
+            storage = Path(acme.storage).resolve()
+            self.ssl_ctx = (True, str(storage / 'cert.pem'), str(storage / 'key.pem'), None, settings, str(storage))
+            return
+        if not cert:
+            self.ssl_ctx = (False, None, None, None, None, None)
             return
-        ctx = ssl.SSLContext(ssl.PROTOCOL_TLS_SERVER)
-        ctx.load_cert_chain(cert, key, password)
-        # with cert.open("rb") as f:
-        #     cert_contents = f.read()
-        # with key.open("rb") as f:
-        #     key_contents = f.read()
-        self.ssl_ctx = (True, str(cert.resolve()), str(key.resolve()), password)
+        # key can be omitted when bundled with the certificate (combined PEM or PKCS#12)
+        self.ssl_ctx = (
+            True,
+            self._ssl_material(cert),
+            self._ssl_material(key) if key else None,
+            password,
+            settings,
+            None,
+        )
+
+    @staticmethod
+    def _ssl_material(value: Union[Path, bytes]) -> Union[str, bytes]:
+        if isinstance(value, (bytes, bytearray)):
+            return bytes(value)
+        return str(Path(value).resolve())
+
+    def validate_ssl_context(self):
+        if not self.ssl_ctx[0]:
+            return
+        try:
+            warnings = validate_tls_config(
+                *self.ssl_ctx[1:4], http_mode=str(self.http), settings=self.ssl_ctx[4], acme_storage=self.ssl_ctx[5]
+            )
+        except ValueError as exc:
+            logger.error(f'Invalid TLS configuration: {exc}')
+            raise ConfigurationError('ssl') from exc
+        for message in warnings:
+            logger.warning(message)
+
+    def validate_connection_settings(self):
+        settings = self.connection_settings
+        if not settings:
+            return
+        try:
+            if settings.max_per_ip is not None and settings.max_per_ip < 1:
+                raise ValueError('the maximum number of connections per IP should be positive')
+            # normalize networks, so that workers only need to deal with the `address/prefix` notation
+            networks = {
+                key: [str(ipaddress.ip_network(value, strict=False)) for value in getattr(settings, key) or []]
+                for key in ('allow', 'deny')
+            }
+        except ValueError as exc:
+            logger.error(f'Invalid connection settings: {exc}')
+            raise ConfigurationError('connection_settings') from exc
+        self.connection_settings = dataclasses.replace(settings, **networks)
+
+    def validate_rate_limit_settings(self):
+        settings = self.rate_limit_settings
+        if not settings:
+            return
+        try:
+            if settings.rate <= 0:
+                raise ValueError('the rate should be positive')
+            burst = settings.burst if settings.burst is not None else max(1, math.ceil(settings.rate))
+            if burst < 1:
+                raise ValueError('the burst size should be positive')
+            if settings.key not in ('ip', 'header', 'path'):
+                raise ValueError(f'unsupported key {settings.key!r}')
+            if settings.key == 'header' and not re.fullmatch(r"[!#$%&'*+\-.^_`|~0-9A-Za-z]+", settings.header or ''):
+                raise ValueError('the header key requires a valid header name')
+            if settings.key == 'path' and not settings.paths:
+                raise ValueError('the path key requires at least one path prefix')
+            if any(not path.startswith('/') for path in settings.paths or []):
+                raise ValueError('path prefixes should start with a slash')
+            if settings.shared and sys.platform == 'win32':
+                raise ValueError('shared rate limits are not supported on Windows')
+        except ValueError as exc:
+            logger.error(f'Invalid rate limit settings: {exc}')
+            raise ConfigurationError('rate_limit_settings') from exc
+        self.rate_limit_settings = dataclasses.replace(settings, burst=burst)
+
+    def validate_load_shedding_settings(self):
+        settings = self.load_shedding_settings
+        if not settings:
+            return
+        if settings.queue_timeout < 0:
+            logger.error('Invalid load shedding settings: the queue timeout cannot be negative')
+            raise ConfigurationError('load_shedding_settings')
+        if settings.retry_after < 0:
+            logger.error('Invalid load shedding settings: the retry after value cannot be negative')
+            raise ConfigurationError('load_shedding_settings')
+
+    def validate_concurrency_settings(self):
+        settings = self.concurrency_settings
+        if not settings:
+            return
+        try:
+            if settings.max_requests < 1:
+                raise ValueError('the maximum number of concurrent requests should be positive')
+            if settings.queue_size < 0:
+                raise ValueError('the queue size cannot be negative')
+            if settings.queue_timeout is not None and settings.queue_timeout < 0:
+                raise ValueError('the queue timeout cannot be negative')
+        except ValueError as exc:
+            logger.error(f'Invalid concurrency settings: {exc}')
+            raise ConfigurationError('concurrency_settings') from exc
+
+    def validate_adaptive_concurrency_settings(self):
+        settings = self.adaptive_concurrency_settings
+        if not settings:
+            return
+        # the adaptive limit replaces the backpressure, which thus becomes its upper bound by default
+        max_limit = settings.max_limit if settings.max_limit is not None else self.backpressure
+        try:
+            if settings.min_limit < 1:
+                raise ValueError('the minimum limit should be positive')
+            if settings.min_limit > max_limit:
+                raise ValueError('the minimum limit cannot exceed the maximum one')
+            if not 0 < settings.smoothing <= 1:
+                raise ValueError('the smoothing factor should be between 0 and 1')
+        except ValueError as exc:
+            logger.error(f'Invalid adaptive concurrency settings: {exc}')
+            raise ConfigurationError('adaptive_concurrency_settings') from exc
+        initial_limit = min(max(settings.initial_limit, settings.min_limit), max_limit)
+        self.adaptive_concurrency_settings = dataclasses.replace(
+            settings, initial_limit=initial_limit, max_limit=max_limit
+        )
+
+    def _init_rate_limit_shm(self):
+        if not self.rate_limit_settings or not self.rate_limit_settings.shared:
+            return
+        # one 64bit slot per bucket, workers map the segment on their own
+        self._rate_limit_shm = SharedMemory(create=True, size=8 * 65536)
+
+    def _release_rate_limit_shm(self):
+        if not self._rate_limit_shm:
+            return
+        self._rate_limit_shm.close()
+        self._rate_limit_shm.unlink()
+        self._rate_limit_shm = None
+
+    def _init_acme(self):
+        if not self.ssl_acme:
+            return
+        try:
+            self._acme = ACMEManager(self.ssl_acme, self.bind_addr)
+        except (OSError, ValueError) as exc:
+            logger.error(f'Invalid ACME configuration: {exc}')
+            raise ConfigurationError('ssl_acme') from exc
 
     @staticmethod
     def _spawn_asgi_worker(
@@ -194,12 +379,28 @@ class Granian:
         http_mode: HTTPModes,
         http1_settings: Optional[HTTP1Settings],
         http2_settings: Optional[HTTP2Settings],
+        connection_settings: Optional[ConnectionSettings],
+        rate_limit_ctx: Tuple[Optional[RateLimitSettings], Optional[str], int],
+        load_shedding_settings: Optional[LoadSheddingSettings],
+        concurrency_settings: Optional[ConcurrencyLimitSettings],
+        adaptive_concurrency_settings: Optional[AdaptiveConcurrencySettings],
+        strict_requests: bool,
         websockets: bool,
+        websockets_settings: Optional[WebsocketSettings],
+        cancel_on_disconnect: bool,
+        stream_buffer_size: int,
         log_enabled: bool,
         log_level: LogLevels,
         log_config: Dict[str, Any],
         log_access_fmt: Optional[str],
-        ssl_ctx: Tuple[bool, Optional[str], Optional[str], Optional[str]],
+        ssl_ctx: Tuple[
+            bool,
+            Optional[Union[str, bytes]],
+            Optional[Union[str, bytes]],
+            Optional[str],
+            Optional[TLSSettings],
+            Optional[str],
+        ],
         scope_opts: Dict[str, Any],
     ):
         from granian._loops import loops
@@ -224,7 +425,16 @@ class Granian:
             http_mode,
             http1_settings,
             http2_settings,
+            connection_settings,
+            *rate_limit_ctx,
+            load_shedding_settings,
+            concurrency_settings,
+            adaptive_concurrency_settings,
+            strict_requests,
             websockets,
+            websockets_settings,
+            cancel_on_disconnect,
+            stream_buffer_size,
             *ssl_ctx,
         )
         serve = getattr(worker, {ThreadModes.runtime: 'serve_rth', ThreadModes.workers: 'serve_wth'}[threading_mode])
@@ -248,12 +458,28 @@ class Granian:
         http_mode: HTTPModes,
         http1_settings: Optional[HTTP1Settings],
         http2_settings: Optional[HTTP2Settings],
+        connection_settings: Optional[ConnectionSettings],
+        rate_limit_ctx: Tuple[Optional[RateLimitSettings], Optional[str], int],
+        load_shedding_settings: Optional[LoadSheddingSettings],
+        concurrency_settings: Optional[ConcurrencyLimitSettings],
+        adaptive_concurrency_settings: Optional[AdaptiveConcurrencySettings],
+        strict_requests: bool,
         websockets: bool,
+        websockets_settings: Optional[WebsocketSettings],
+        cancel_on_disconnect: bool,
+        stream_buffer_size: int,
         log_enabled: bool,
         log_level: LogLevels,
         log_config: Dict[str, Any],
         log_access_fmt: Optional[str],
-        ssl_ctx: Tuple[bool, Optional[str], Optional[str], Optional[str]],
+        ssl_ctx: Tuple[
+            bool,
+            Optional[Union[str, bytes]],
+            Optional[Union[str, bytes]],
+            Optional[str],
+            Optional[TLSSettings],
+            Optional[str],
+        ],
         scope_opts: Dict[str, Any],
     ):
         from granian._loops import loops
@@ -285,7 +511,16 @@ class Granian:
             http_mode,
             http1_settings,
             http2_settings,
+            connection_settings,
+            *rate_limit_ctx,
+            load_shedding_settings,
+            concurrency_settings,
+            adaptive_concurrency_settings,
+            strict_requests,
             websockets,
+            websockets_settings,
+            cancel_on_disconnect,
+            stream_buffer_size,
             *ssl_ctx,
         )
         serve = getattr(worker, {ThreadModes.runtime: 'serve_rth', ThreadModes.workers: 'serve_wth'}[threading_mode])
@@ -310,12 +545,28 @@ class Granian:
         http_mode: HTTPModes,
         http1_settings: Optional[HTTP1Settings],
         http2_settings: Optional[HTTP2Settings],
+        connection_settings: Optional[ConnectionSettings],
+        rate_limit_ctx: Tuple[Optional[RateLimitSettings], Optional[str], int],
+        load_shedding_settings: Optional[LoadSheddingSettings],
+        concurrency_settings: Optional[ConcurrencyLimitSettings],
+        adaptive_concurrency_settings: Optional[AdaptiveConcurrencySettings],
+        strict_requests: bool,
         websockets: bool,
+        websockets_settings: Optional[WebsocketSettings],
+        cancel_on_disconnect: bool,
+        stream_buffer_size: int,
         log_enabled: bool,
         log_level: LogLevels,
         log_config: Dict[str, Any],
         log_access_fmt: Optional[str],
-        ssl_ctx: Tuple[bool, Optional[str], Optional[str], Optional[str]],
+        ssl_ctx: Tuple[
+            bool,
+            Optional[Union[str, bytes]],
+            Optional[Union[str, bytes]],
+            Optional[str],
+            Optional[TLSSettings],
+            Optional[str],
+        ],
         scope_opts: Dict[str, Any],
     ):
         from granian._loops import loops
@@ -348,7 +599,16 @@ class Granian:
             http_mode,
             http1_settings,
             http2_settings,
+            connection_settings,
+            *rate_limit_ctx,
+            load_shedding_settings,
+            concurrency_settings,
+            adaptive_concurrency_settings,
+            strict_requests,
             websockets,
+            websockets_settings,
+            cancel_on_disconnect,
+            stream_buffer_size,
             *ssl_ctx,
         )
         serve = getattr(worker, {ThreadModes.runtime: 'serve_rth', ThreadModes.workers: 'serve_wth'}[threading_mode])
@@ -373,12 +633,28 @@ class Granian:
         http_mode: HTTPModes,
         http1_settings: Optional[HTTP1Settings],
         http2_settings: Optional[HTTP2Settings],
+        connection_settings: Optional[ConnectionSettings],
+        rate_limit_ctx: Tuple[Optional[RateLimitSettings], Optional[str], int],
+        load_shedding_settings: Optional[LoadSheddingSettings],
+        concurrency_settings: Optional[ConcurrencyLimitSettings],
+        adaptive_concurrency_settings: Optional[AdaptiveConcurrencySettings],
+        strict_requests: bool,
         websockets: bool,
+        websockets_settings: Optional[WebsocketSettings],
+        cancel_on_disconnect: bool,
+        stream_buffer_size: int,
         log_enabled: bool,
         log_level: LogLevels,
         log_config: Dict[str, Any],
         log_access_fmt: Optional[str],
-        ssl_ctx: Tuple[bool, Optional[str], Optional[str], Optional[str]],
+        ssl_ctx: Tuple[
+            bool,
+            Optional[Union[str, bytes]],
+            Optional[Union[str, bytes]],
+            Optional[str],
+            Optional[TLSSettings],
+            Optional[str],
+        ],
         scope_opts: Dict[str, Any],
     ):
         from granian._loops import loops
@@ -394,7 +670,21 @@ class Granian:
         shutdown_event = set_sync_signals()
 
         worker = WSGIWorker(
-            worker_id, sfd, threads, blocking_threads, backpressure, http_mode, http1_settings, http2_settings, *ssl_ctx
+            worker_id,
+            sfd,
+            threads,
+            blocking_threads,
+            backpressure,
+            http_mode,
+            http1_settings,
+            http2_settings,
+            connection_settings,
+            *rate_limit_ctx,
+            load_shedding_settings,
+            concurrency_settings,
+            adaptive_concurrency_settings,
+            strict_requests,
+            *ssl_ctx,
         )
         serve = getattr(worker, {ThreadModes.runtime: 'serve_rth', ThreadModes.workers: 'serve_wth'}[threading_mode])
         scheduler = _new_cbscheduler(
@@ -404,7 +694,11 @@ class Granian:
         shutdown_event.qs.wait()
 
     def _init_shared_socket(self):
-        self._shd = SocketHolder.from_address(self.bind_addr, self.bind_port, self.backlog)
+        try:
+            self._shd = SocketHolder.from_address(self.bind_addr, self.bind_port, self.backlog)
+        except (OSError, ValueError) as exc:
+            logger.error(f'Unable to bind listener on {self.bind_addr}:{self.bind_port}: {exc}')
+            raise ConfigurationError('bind') from exc
         self._sfd = self._shd.get_fd()
 
     def signal_handler_interrupt(self, *args, **kwargs):
@@ -434,7 +728,20 @@ class Granian:
                 self.http,
                 self.http1_settings,
                 self.http2_settings,
+                self.connection_settings,
+                (
+                    self.rate_limit_settings,
+                    self._rate_limit_shm.name if self._rate_limit_shm else None,
+                    self._rate_limit_seed,
+                ),
+                self.load_shedding_settings,
+                self.concurrency_settings,
+                self.adaptive_concurrency_settings,
+                self.strict_requests,
                 self.websockets,
+                self.websockets_settings,
+                self.cancel_on_disconnect,
+                self.stream_buffer_size,
                 self.log_enabled,
                 self.log_level,
                 self.log_config,
@@ -559,6 +866,7 @@ class Granian:
         self._write_pidfile()
         set_main_signals(self.signal_handler_interrupt, self.signal_handler_reload)
         self._init_shared_socket()
+        self._init_rate_limit_shm()
         sock = socket.socket(fileno=self._sfd)
         sock.set_inheritable(True)
         proto = 'https' if self.ssl_ctx[0] else 'http'
@@ -566,6 +874,10 @@ class Granian:
 
         self._spawn_workers(sock, spawn_target, target_loader)
 
+        # NOTE: TLS-ALPN-01 challenges are answered by workers, so we start the ACME client afterwards
+        if self._acme:
+            self._acme.start()
+
         if self.workers_lifetime is not None:
             self._watch_workers_lifetime(self.workers_lifetime)
 
@@ -573,7 +885,10 @@ class Granian:
 
     def shutdown(self, exit_code=0):
         logger.info('Shutting down granian')
+        if self._acme:
+            self._acme.stop()
         self._stop_workers()
+        self._release_rate_limit_shm()
         self._unlink_pidfile()
         if not exit_code and self.interrupt_children:
             exit_code = 1
@@ -712,6 +1027,14 @@ class Granian:
             if self.http == HTTPModes.http2:
                 logger.info('Websockets are not supported on HTTP/2 only, ignoring')
 
+        if (
+            self.http == HTTPModes.auto
+            and self.http2_settings
+            and self.http2_settings.max_local_error_reset_streams != HTTP2Settings.max_local_error_reset_streams
+        ):
+            # NOTE: hyper's auto connection builder doesn't expose this limit
+            logger.warning('HTTP/2 max local error reset streams is only supported with HTTP mode 2, ignoring')
+
         if setproctitle is not None:
             self.process_name = self.process_name or (
                 f'granian {self.interface} {self.bind_addr}:{self.bind_port} {self.target}'
@@ -728,8 +1051,20 @@ class Granian:
                 logger.error('Workers lifetime cannot be less than 60 seconds')
                 raise ConfigurationError('workers_lifetime')
 
+        self.validate_ssl_context()
+        self.validate_connection_settings()
+        self.validate_rate_limit_settings()
+        self.validate_load_shedding_settings()
+        self.validate_concurrency_settings()
+        self.validate_adaptive_concurrency_settings()
+        self._init_acme()
+
         if self.task_impl == TaskImpl.auto:
             self.task_impl = TaskImpl.asyncio if anyio is not None else TaskImpl.rust
 
+        if self.cancel_on_disconnect and self.task_impl != TaskImpl.asyncio:
+            logger.info('Cancelling tasks on client disconnection requires the asyncio task implementation, ignoring')
+            self.cancel_on_disconnect = False
+
         serve_method = self._serve_with_reloader if self.reload_on_changes else self._serve
         serve_method(spawn_target, target_loader)
@@ -0,0 +1,506 @@
+use anyhow::{anyhow, bail, Result};
+use http_body_util::BodyExt;
+use hyper::{header::SERVER as HK_SERVER, Response};
+use instant_acme::{
+    Account, AccountCredentials, AuthorizationStatus, ChallengeType, Identifier, NewAccount, NewOrder, Order,
+    OrderStatus,
+};
+use pyo3::{exceptions::PyValueError, prelude::*};
+use rcgen::{CertificateParams, CustomExtension, DistinguishedName, KeyPair};
+use std::{
+    collections::HashMap,
+    convert::Infallible,
+    fs,
+    io::{self, Write},
+    net::{IpAddr, SocketAddr, TcpListener},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
+use tls_listener::rustls::{
+    rustls::{
+        crypto::CryptoProvider,
+        server::{ClientHello, ResolvesServerCert},
+        sign::CertifiedKey,
+    },
+    server::TlsStream,
+};
+use tokio::sync::watch;
+
+use crate::{
+    conversion::acme_config_from_py,
+    http::{response_404, HTTPRequest, HV_SERVER},
+    tls::{cert_not_after, load_identity, unix_now, TLSMaterial},
+};
+
+pub(crate) const ACME_TLS_ALPN_PROTOCOL: &[u8] = b"acme-tls/1";
+const ACME_ACCOUNT_FILE: &str = "account.json";
+const ACME_CERT_FILE: &str = "cert.pem";
+const ACME_KEY_FILE: &str = "key.pem";
+const ACME_CHALLENGES_DIR: &str = "challenges";
+const ACME_HTTP01_PATH: &str = "/.well-known/acme-challenge/";
+const ACME_HTTP01_ACCEPT_BACKOFF: Duration = Duration::from_millis(500);
+const ACME_CHECK_INTERVAL: Duration = Duration::from_secs(12 * 3600);
+const ACME_RETRY_INTERVAL: Duration = Duration::from_secs(600);
+const ACME_POLL_DELAY: Duration = Duration::from_millis(250);
+const ACME_POLL_MAX_DELAY: Duration = Duration::from_secs(10);
+const ACME_POLL_ATTEMPTS: usize = 30;
+const CERT_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(crate) struct ACMEConfig {
+    pub domains: Vec<String>,
+    pub storage: PathBuf,
+    pub server: String,
+    pub contact: Vec<String>,
+    pub challenge: String,
+    pub http_port: u16,
+    pub renew_before: i64,
+}
+
+impl ACMEConfig {
+    fn validate(&mut self) -> Result<()> {
+        if self.domains.is_empty() {
+            bail!("At least one domain is required");
+        }
+        for domain in &mut self.domains {
+            *domain = domain.to_ascii_lowercase();
+            if challenge_path(&self.storage, domain).is_none() {
+                bail!("Unsupported domain name '{domain}'");
+            }
+        }
+        if !matches!(self.challenge.as_str(), "http-01" | "tls-alpn-01") {
+            bail!("Unsupported challenge type '{}'", self.challenge);
+        }
+        Ok(())
+    }
+
+    fn challenge_type(&self) -> ChallengeType {
+        match self.challenge.as_str() {
+            "http-01" => ChallengeType::Http01,
+            _ => ChallengeType::TlsAlpn01,
+        }
+    }
+}
+
+// NOTE: we only accept plain DNS names, as the domain also names the challenge file in the storage.
+fn challenge_path(storage: &Path, domain: &str) -> Option<PathBuf> {
+    if domain.is_empty()
+        || domain.starts_with('.')
+        || !domain
+            .bytes()
+            .all(|c| c.is_ascii_alphanumeric() || c == b'-' || c == b'.')
+    {
+        return None;
+    }
+    Some(storage.join(ACME_CHALLENGES_DIR).join(format!("{domain}.pem")))
+}
+
+fn write_file(path: &Path, data: &[u8]) -> io::Result<()> {
+    let tmp_path = path.with_extension("tmp");
+    let mut opts = fs::OpenOptions::new();
+    opts.write(true).create(true).truncate(true);
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut opts, 0o600);
+    opts.open(&tmp_path)?.write_all(data)?;
+    fs::rename(tmp_path, path)
+}
+
+fn load_certified_key(provider: &CryptoProvider, cert: &Path, key: Option<&Path>) -> Result<CertifiedKey> {
+    let material = |path: &Path| TLSMaterial::Path(path.to_string_lossy().into());
+    let (certs, pkey) = load_identity(&material(cert), key.map(material).as_ref(), None)?;
+    let signing_key = provider
+        .key_provider
+        .load_private_key(pkey)
+        .map_err(|err| anyhow!("Unable to load private key: {err}"))?;
+    Ok(CertifiedKey::new(certs, signing_key))
+}
+
+/// Certificate resolver used by workers for ACME managed certificates.
+///
+/// Answers TLS-ALPN-01 challenges with the certificates prepared by the main process,
+/// and reloads the certificate from the storage once it gets renewed.
+#[derive(Debug)]
+pub(crate) struct ACMECertResolver {
+    storage: PathBuf,
+    provider: Arc<CryptoProvider>,
+    key: RwLock<Option<Arc<CertifiedKey>>>,
+    state: Mutex<ACMECertState>,
+}
+
+#[derive(Debug)]
+struct ACMECertState {
+    checked_at: Instant,
+    modified: Option<SystemTime>,
+}
+
+impl ACMECertResolver {
+    pub fn new(storage: &Path, provider: Arc<CryptoProvider>) -> Self {
+        let resolver = Self {
+            storage: storage.into(),
+            provider,
+            key: RwLock::new(None),
+            state: Mutex::new(ACMECertState {
+                checked_at: Instant::now(),
+                modified: None,
+            }),
+        };
+        resolver.refresh();
+        resolver
+    }
+
+    // NOTE: until a certificate gets loaded we check the storage on every handshake,
+    //       afterwards at most once per `CERT_REFRESH_INTERVAL`.
+    fn refresh(&self) {
+        let Ok(mut state) = self.state.try_lock() else {
+            return;
+        };
+        let loaded = self.key.read().unwrap().is_some();
+        if loaded && state.checked_at.elapsed() < CERT_REFRESH_INTERVAL {
+            return;
+        }
+        state.checked_at = Instant::now();
+
+        let cert_path = self.storage.join(ACME_CERT_FILE);
+        let Ok(modified) = fs::metadata(&cert_path).and_then(|v| v.modified()) else {
+            return;
+        };
+        if state.modified == Some(modified) {
+            return;
+        }
+        match load_certified_key(&self.provider, &cert_path, Some(&self.storage.join(ACME_KEY_FILE))) {
+            Ok(key) => {
+                state.modified = Some(modified);
+                *self.key.write().unwrap() = Some(Arc::new(key));
+                if loaded {
+                    log::info!("Reloaded ACME certificate from {}", cert_path.display());
+                }
+            }
+            Err(err) => log::warn!("Unable to load ACME certificate from {}: {err}", cert_path.display()),
+        }
+    }
+
+    fn challenge_key(&self, server_name: &str) -> Option<Arc<CertifiedKey>> {
+        let path = challenge_path(&self.storage, &server_name.to_ascii_lowercase())?;
+        load_certified_key(&self.provider, &path, None).ok().map(Arc::new)
+    }
+}
+
+impl ResolvesServerCert for ACMECertResolver {
+    fn resolve(&self, client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        if client_hello
+            .alpn()
+            .is_some_and(|mut protocols| protocols.any(|v| v == ACME_TLS_ALPN_PROTOCOL))
+        {
+            return client_hello.server_name().and_then(|v| self.challenge_key(v));
+        }
+        self.refresh();
+        self.key.read().unwrap().clone()
+    }
+}
+
+/// TLS-ALPN-01 validation connections are done once the handshake completes.
+pub(crate) fn is_challenge_connection(stream: &TlsStream<tokio::net::TcpStream>) -> bool {
+    stream.get_ref().1.alpn_protocol() == Some(ACME_TLS_ALPN_PROTOCOL)
+}
+
+type HTTP01Tokens = Arc<RwLock<HashMap<String, String>>>;
+
+async fn serve_http01(listener: TcpListener, tokens: HTTP01Tokens) {
+    let listener = match tokio::net::TcpListener::from_std(listener) {
+        Ok(listener) => listener,
+        Err(err) => {
+            log::error!("Unable to serve ACME HTTP-01 challenges: {err}");
+            return;
+        }
+    };
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(err) => {
+                // NOTE: errors like running out of file descriptors won't go away right away
+                log::warn!("Unable to accept ACME HTTP-01 connection: {err}");
+                tokio::time::sleep(ACME_HTTP01_ACCEPT_BACKOFF).await;
+                continue;
+            }
+        };
+        let tokens = tokens.clone();
+        tokio::spawn(async move {
+            let svc = hyper::service::service_fn(move |request: HTTPRequest| {
+                let key_authorization = request
+                    .uri()
+                    .path()
+                    .strip_prefix(ACME_HTTP01_PATH)
+                    .and_then(|token| tokens.read().unwrap().get(token).cloned());
+                async move {
+                    Ok::<_, Infallible>(match key_authorization {
+                        Some(value) => {
+                            let mut response =
+                                Response::new(http_body_util::Full::new(value.into()).map_err(|e| match e {}).boxed());
+                            response.headers_mut().insert(HK_SERVER, HV_SERVER);
+                            response
+                        }
+                        None => response_404(),
+                    })
+                }
+            });
+            let _ = hyper::server::conn::http1::Builder::new()
+                .serve_connection(hyper_util::rt::TokioIo::new(stream), svc)
+                .await;
+        });
+    }
+}
+
+async fn load_account(config: &ACMEConfig) -> Result<Account> {
+    let path = config.storage.join(ACME_ACCOUNT_FILE);
+    // NOTE: accounts are bound to the ACME server they were registered on
+    if let Ok(data) = fs::read(&path) {
+        let credentials: serde_json::Value = serde_json::from_slice(&data)
+            .map_err(|err| anyhow!("Unable to load ACME account from {}: {err}", path.display()))?;
+        if credentials.get("directory").and_then(serde_json::Value::as_str) == Some(config.server.as_str()) {
+            let credentials: AccountCredentials = serde_json::from_value(credentials)?;
+            return Ok(Account::from_credentials(credentials).await?);
+        }
+    }
+
+    let contact: Vec<String> = config
+        .contact
+        .iter()
+        .map(|v| {
+            if v.starts_with("mailto:") {
+                v.clone()
+            } else {
+                format!("mailto:{v}")
+            }
+        })
+        .collect();
+    let contact: Vec<&str> = contact.iter().map(String::as_str).collect();
+    let (account, credentials) = Account::create(
+        &NewAccount {
+            contact: &contact,
+            terms_of_service_agreed: true,
+            only_return_existing: false,
+        },
+        &config.server,
+        None,
+    )
+    .await?;
+    write_file(&path, &serde_json::to_vec(&credentials)?)?;
+    log::info!("Registered ACME account on {}", config.server);
+    Ok(account)
+}
+
+async fn wait_order_ready(order: &mut Order) -> Result<()> {
+    let mut delay = ACME_POLL_DELAY;
+    for _ in 0..ACME_POLL_ATTEMPTS {
+        tokio::time::sleep(delay).await;
+        match order.refresh().await?.status {
+            OrderStatus::Ready => return Ok(()),
+            OrderStatus::Invalid => {
+                let reason = order
+                    .authorizations()
+                    .await?
+                    .into_iter()
+                    .flat_map(|authz| authz.challenges)
+                    .find_map(|challenge| challenge.error.and_then(|err| err.detail))
+                    .unwrap_or_else(|| "unknown reason".into());
+                bail!("ACME order is invalid: {reason}");
+            }
+            _ => delay = (delay * 2).min(ACME_POLL_MAX_DELAY),
+        }
+    }
+    bail!("Timed out waiting for ACME order validation")
+}
+
+async fn complete_order(config: &ACMEConfig, tokens: &HTTP01Tokens, order: &mut Order) -> Result<(String, String)> {
+    let challenge_type = config.challenge_type();
+    for authz in order.authorizations().await? {
+        match authz.status {
+            AuthorizationStatus::Pending => {}
+            AuthorizationStatus::Valid => continue,
+            status => bail!("Unexpected ACME authorization status {status:?}"),
+        }
+        let Identifier::Dns(domain) = &authz.identifier;
+        let challenge = authz
+            .challenges
+            .iter()
+            .find(|v| v.r#type == challenge_type)
+            .ok_or_else(|| anyhow!("ACME server didn't offer {} challenge for {domain}", config.challenge))?;
+        let key_authorization = order.key_authorization(challenge);
+        match challenge_type {
+            ChallengeType::Http01 => {
+                tokens
+                    .write()
+                    .unwrap()
+                    .insert(challenge.token.clone(), key_authorization.as_str().into());
+            }
+            _ => {
+                let key = KeyPair::generate()?;
+                let mut params = CertificateParams::new(vec![domain.clone()])?;
+                params.custom_extensions = vec![CustomExtension::new_acme_identifier(
+                    key_authorization.digest().as_ref(),
+                )];
+                let cert = params.self_signed(&key)?;
+                let path = challenge_path(&config.storage, domain).ok_or_else(|| anyhow!("Invalid domain {domain}"))?;
+                write_file(&path, format!("{}{}", cert.pem(), key.serialize_pem()).as_bytes())?;
+            }
+        }
+        order.set_challenge_ready(&challenge.url).await?;
+    }
+    wait_order_ready(order).await?;
+
+    let key = KeyPair::generate()?;
+    let mut params = CertificateParams::new(config.domains.clone())?;
+    params.distinguished_name = DistinguishedName::new();
+    let csr = params.serialize_request(&key)?;
+    order.finalize(csr.der()).await?;
+
+    let mut delay = ACME_POLL_DELAY;
+    for _ in 0..ACME_POLL_ATTEMPTS {
+        if let Some(chain) = order.certificate().await? {
+            return Ok((chain, key.serialize_pem()));
+        }
+        tokio::time::sleep(delay).await;
+        delay = (delay * 2).min(ACME_POLL_MAX_DELAY);
+    }
+    bail!("Timed out waiting for ACME certificate")
+}
+
+async fn issue_certificate(config: &ACMEConfig, tokens: &HTTP01Tokens) -> Result<()> {
+    let account = load_account(config).await?;
+    let identifiers: Vec<Identifier> = config.domains.iter().map(|v| Identifier::Dns(v.clone())).collect();
+    let mut order = account
+        .new_order(&NewOrder {
+            identifiers: &identifiers,
+        })
+        .await?;
+    let ret = complete_order(config, tokens, &mut order).await;
+
+    tokens.write().unwrap().clear();
+    for domain in &config.domains {
+        if let Some(path) = challenge_path(&config.storage, domain) {
+            let _ = fs::remove_file(path);
+        }
+    }
+
+    // NOTE: workers watch the certificate file, so we write the key first.
+    let (chain, key) = ret?;
+    write_file(&config.storage.join(ACME_KEY_FILE), key.as_bytes())?;
+    write_file(&config.storage.join(ACME_CERT_FILE), chain.as_bytes())?;
+    Ok(())
+}
+
+fn stored_cert_expiry(storage: &Path) -> Option<i64> {
+    let material = |name| TLSMaterial::Path(storage.join(name).to_string_lossy().into());
+    let (certs, _) = load_identity(&material(ACME_CERT_FILE), Some(&material(ACME_KEY_FILE)), None).ok()?;
+    certs.first().and_then(cert_not_after)
+}
+
+async fn run(config: Arc<ACMEConfig>, http_listener: Option<TcpListener>) {
+    let tokens = HTTP01Tokens::default();
+    if let Some(listener) = http_listener {
+        tokio::spawn(serve_http01(listener, tokens.clone()));
+    }
+
+    loop {
+        let now = unix_now();
+        let wait = match stored_cert_expiry(&config.storage).map(|v| v - config.renew_before * 86400) {
+            Some(renew_at) if renew_at > now => {
+                ACME_CHECK_INTERVAL.min(Duration::from_secs((renew_at - now).unsigned_abs()))
+            }
+            _ => {
+                log::info!("Requesting ACME certificate for {}", config.domains.join(", "));
+                match issue_certificate(&config, &tokens).await {
+                    Ok(()) => {
+                        log::info!("ACME certificate for {} issued", config.domains.join(", "));
+                        ACME_CHECK_INTERVAL
+                    }
+                    Err(err) => {
+                        log::error!("ACME certificate request failed: {err}");
+                        ACME_RETRY_INTERVAL
+                    }
+                }
+            }
+        };
+        tokio::time::sleep(wait).await;
+    }
+}
+
+/// ACME client running in the main process, issuing and renewing certificates into the storage directory.
+#[pyclass(frozen, module = "granian._granian")]
+pub(crate) struct ACMEManager {
+    config: Arc<ACMEConfig>,
+    http_listener: Mutex<Option<TcpListener>>,
+    stop_tx: watch::Sender<bool>,
+    handle: Mutex<Option<thread::JoinHandle<()>>>,
+}
+
+#[pymethods]
+impl ACMEManager {
+    #[new]
+    fn new(py: Python, settings: PyObject, address: &str) -> PyResult<Self> {
+        let mut config = acme_config_from_py(py, settings)?;
+        config
+            .validate()
+            .map_err(|err| PyValueError::new_err(err.to_string()))?;
+        fs::create_dir_all(config.storage.join(ACME_CHALLENGES_DIR)).map_err(|err| {
+            PyValueError::new_err(format!(
+                "Unable to create ACME storage in {}: {err}",
+                config.storage.display()
+            ))
+        })?;
+
+        let http_listener = match config.challenge_type() {
+            ChallengeType::Http01 => {
+                let addr: SocketAddr = (address.parse::<IpAddr>()?, config.http_port).into();
+                let listener = TcpListener::bind(addr).map_err(|err| {
+                    PyValueError::new_err(format!("Unable to bind ACME HTTP-01 listener on {addr}: {err}"))
+                })?;
+                listener.set_nonblocking(true)?;
+                Some(listener)
+            }
+            _ => None,
+        };
+
+        Ok(Self {
+            config: Arc::new(config),
+            http_listener: Mutex::new(http_listener),
+            stop_tx: watch::channel(false).0,
+            handle: Mutex::new(None),
+        })
+    }
+
+    fn start(&self) {
+        let config = self.config.clone();
+        let http_listener = self.http_listener.lock().unwrap().take();
+        let mut stop_rx = self.stop_tx.subscribe();
+        let handle = thread::spawn(move || {
+            let rt = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .unwrap();
+            rt.block_on(async move {
+                tokio::select! {
+                    () = run(config, http_listener) => {},
+                    _ = stop_rx.changed() => {},
+                }
+            });
+        });
+        *self.handle.lock().unwrap() = Some(handle);
+    }
+
+    fn stop(&self, py: Python) {
+        let _ = self.stop_tx.send(true);
+        if let Some(handle) = self.handle.lock().unwrap().take() {
+            py.allow_threads(|| {
+                let _ = handle.join();
+            });
+        }
+    }
+}
+
+pub(crate) fn init_pymodule(module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_class::<ACMEManager>()?;
+
+    Ok(())
+}
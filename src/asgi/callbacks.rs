@@ -5,24 +5,31 @@ use std::{
     sync::{Arc, OnceLock},
 };
 use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
 
 use super::{
     io::{ASGIHTTPProtocol as HTTPProtocol, ASGIWebsocketProtocol as WebsocketProtocol, WebsocketDetachedTransport},
     utils::{build_scope_http, build_scope_ws, scope_native_parts},
 };
 use crate::{
-    callbacks::ArcCBScheduler,
-    http::{response_500, HTTPResponse},
+    callbacks::{ArcCBScheduler, DisconnectWatcher},
+    http::{response_500, HTTPResponse, InterimSender},
     runtime::RuntimeRef,
     utils::log_application_callable_exception,
+    workers::ServiceConfig,
     ws::{HyperWebsocket, UpgradeData},
 };
 
 macro_rules! callback_impl_done_http {
     ($self:expr) => {
-        if let Some(tx) = $self.proto.get().tx() {
+        if let Some(watcher) = &$self.disconnect_watcher {
+            watcher.finish();
+        }
+        let proto = $self.proto.get();
+        if let Some(tx) = proto.tx() {
             let _ = tx.send(response_500());
         }
+        proto.release();
     };
 }
 
@@ -54,14 +61,21 @@ pub(crate) struct CallbackWatcherHTTP {
     #[pyo3(get)]
     scope: Py<PyDict>,
     aio_taskref: OnceLock<PyObject>,
+    disconnect_watcher: Option<DisconnectWatcher>,
 }
 
 impl CallbackWatcherHTTP {
-    pub fn new(py: Python, proto: HTTPProtocol, scope: Bound<PyDict>) -> Self {
+    pub fn new(
+        py: Python,
+        proto: HTTPProtocol,
+        scope: Bound<PyDict>,
+        disconnect_watcher: Option<DisconnectWatcher>,
+    ) -> Self {
         Self {
             proto: Py::new(py, proto).unwrap(),
             scope: scope.unbind(),
             aio_taskref: OnceLock::new(),
+            disconnect_watcher,
         }
     }
 }
@@ -72,11 +86,19 @@ impl CallbackWatcherHTTP {
         callback_impl_done_http!(self);
     }
 
-    fn err(&self, err: Bound<PyAny>) {
-        callback_impl_done_err!(self, &PyErr::from_value(err));
+    fn err(&self, py: Python, err: Bound<PyAny>) {
+        let err = PyErr::from_value(err);
+        if self.disconnect_watcher.as_ref().is_some_and(|v| v.caused(py, &err)) {
+            self.done();
+            return;
+        }
+        callback_impl_done_err!(self, &err);
     }
 
     fn taskref(&self, py: Python, task: PyObject) {
+        if let Some(watcher) = &self.disconnect_watcher {
+            watcher.watch(task.clone_ref(py));
+        }
         callback_impl_taskref!(self, py, task);
     }
 }
@@ -147,10 +169,16 @@ pub(crate) fn call_http(
     scheme: &str,
     req: hyper::http::request::Parts,
     body: hyper::body::Incoming,
+    disconnect: CancellationToken,
+    svc_cfg: &ServiceConfig,
 ) -> oneshot::Receiver<HTTPResponse> {
     let brt = rt.innerb.clone();
     let (tx, rx) = oneshot::channel();
-    let protocol = HTTPProtocol::new(rt, body, tx);
+    let disconnect_watcher = svc_cfg
+        .cancel_on_disconnect
+        .then(|| DisconnectWatcher::new(rt.clone(), disconnect.clone()));
+    let interim = req.extensions.get::<InterimSender>().cloned();
+    let protocol = HTTPProtocol::new(rt, body, tx, interim, disconnect, svc_cfg.stream_buffer_size);
     let scheme: Arc<str> = scheme.into();
 
     let _ = brt.run(move || {
@@ -166,7 +194,7 @@ pub(crate) fn call_http(
         );
         Python::with_gil(|py| {
             let scope = build_scope_http(py, &req, version, server, client, &scheme, &path, query_string).unwrap();
-            let watcher = Py::new(py, CallbackWatcherHTTP::new(py, protocol, scope)).unwrap();
+            let watcher = Py::new(py, CallbackWatcherHTTP::new(py, protocol, scope, disconnect_watcher)).unwrap();
             cb.get().schedule(py, watcher.as_any());
         });
     });
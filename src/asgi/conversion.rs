@@ -1,6 +1,6 @@
 use pyo3::{
     prelude::*,
-    types::{PyBytes, PyDict},
+    types::{PyBytes, PyDict, PyList},
     IntoPyObjectExt,
 };
 use tokio_tungstenite::tungstenite::Message;
@@ -16,10 +16,20 @@ pub(crate) fn message_into_py(py: Python, message: ASGIMessageType) -> PyResult<
         ASGIMessageType::HTTPDisconnect => {
             dict.set_item(pyo3::intern!(py, "type"), pyo3::intern!(py, "http.disconnect"))?;
         }
-        ASGIMessageType::HTTPRequestBody((bytes, more)) => {
+        ASGIMessageType::HTTPRequestBody((bytes, more, trailers)) => {
             dict.set_item(pyo3::intern!(py, "type"), pyo3::intern!(py, "http.request"))?;
             dict.set_item(pyo3::intern!(py, "body"), bytes.into_py_any(py)?)?;
             dict.set_item(pyo3::intern!(py, "more_body"), more)?;
+            if let Some(trailers) = trailers {
+                let items = PyList::empty(py);
+                for (key, value) in &trailers {
+                    items.append((
+                        PyBytes::new(py, key.as_str().as_bytes()),
+                        PyBytes::new(py, value.as_bytes()),
+                    ))?;
+                }
+                dict.set_item(pyo3::intern!(py, "trailers"), items)?;
+            }
         }
         ASGIMessageType::WSConnect => {
             dict.set_item(pyo3::intern!(py, "type"), pyo3::intern!(py, "websocket.connect"))?;
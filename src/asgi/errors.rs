@@ -81,5 +81,12 @@ macro_rules! error_message {
     };
 }
 
+macro_rules! error_transport {
+    () => {
+        Err(super::errors::ASGITransportError.into())
+    };
+}
+
 pub(crate) use error_flow;
 pub(crate) use error_message;
+pub(crate) use error_transport;
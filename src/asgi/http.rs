@@ -1,13 +1,15 @@
 use http_body_util::BodyExt;
 use hyper::{header::SERVER as HK_SERVER, http::response::Builder as ResponseBuilder, StatusCode};
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 use super::callbacks::{call_http, call_ws};
 use crate::{
     callbacks::ArcCBScheduler,
-    http::{empty_body, response_500, HTTPRequest, HTTPResponse, HV_SERVER},
+    http::{empty_body, response_500, validate_strict, HTTPRequest, HTTPResponse, HV_SERVER},
     runtime::RuntimeRef,
+    workers::ServiceConfig,
     ws::{is_upgrade_request as is_ws_upgrade, upgrade_intent as ws_upgrade, UpgradeData},
 };
 
@@ -16,15 +18,33 @@ const SCHEME_WS: &str = "ws";
 const SCHEME_WSS: &str = "wss";
 
 macro_rules! handle_http_response {
-    ($handler:expr, $rt:expr, $callback:expr, $server_addr:expr, $client_addr:expr, $scheme:expr, $req:expr, $body:expr) => {
-        match $handler($callback, $rt, $server_addr, $client_addr, $req, $scheme, $body).await {
+    ($handler:expr, $rt:expr, $callback:expr, $svc_cfg:expr, $server_addr:expr, $client_addr:expr, $scheme:expr, $req:expr, $body:expr) => {{
+        // NOTE: hyper drops the service future when the connection goes away,
+        //       so the guard signals the disconnection to the application.
+        let disconnect = CancellationToken::new();
+        let disconnect_guard = disconnect.clone().drop_guard();
+        let res = $handler(
+            $callback,
+            $rt,
+            $server_addr,
+            $client_addr,
+            $req,
+            $scheme,
+            $body,
+            disconnect,
+            &$svc_cfg,
+        )
+        .await;
+        disconnect_guard.disarm();
+
+        match res {
             Ok(res) => res,
             _ => {
                 log::error!("ASGI protocol failure");
                 response_500()
             }
         }
-    };
+    }};
 }
 
 macro_rules! handle_request {
@@ -33,16 +53,23 @@ macro_rules! handle_request {
         pub(crate) async fn $func_name(
             rt: RuntimeRef,
             callback: ArcCBScheduler,
+            svc_cfg: Arc<ServiceConfig>,
             server_addr: SocketAddr,
             client_addr: SocketAddr,
             req: HTTPRequest,
             scheme: &str,
         ) -> HTTPResponse {
+            if svc_cfg.strict_requests {
+                if let Some(res) = validate_strict(&req, client_addr) {
+                    return res;
+                }
+            }
             let (parts, body) = req.into_parts();
             handle_http_response!(
                 $handler,
                 rt,
                 callback,
+                svc_cfg,
                 server_addr,
                 client_addr,
                 parts,
@@ -59,13 +86,19 @@ macro_rules! handle_request_with_ws {
         pub(crate) async fn $func_name(
             rt: RuntimeRef,
             callback: ArcCBScheduler,
+            svc_cfg: Arc<ServiceConfig>,
             server_addr: SocketAddr,
             client_addr: SocketAddr,
             mut req: HTTPRequest,
             scheme: &str,
         ) -> HTTPResponse {
+            if svc_cfg.strict_requests {
+                if let Some(res) = validate_strict(&req, client_addr) {
+                    return res;
+                }
+            }
             if is_ws_upgrade(&req) {
-                return match ws_upgrade(&mut req, None) {
+                return match ws_upgrade(&mut req, Some(svc_cfg.ws_config)) {
                     Ok((res, ws)) => {
                         let (restx, mut resrx) = mpsc::channel(1);
                         let (parts, _) = req.into_parts();
@@ -143,6 +176,7 @@ macro_rules! handle_request_with_ws {
                 $handler_req,
                 rt,
                 callback,
+                svc_cfg,
                 server_addr,
                 client_addr,
                 parts,
@@ -1,37 +1,47 @@
 use anyhow::Result;
-use futures::{sink::SinkExt, StreamExt, TryStreamExt};
+use futures::{StreamExt, TryStreamExt};
 use http_body_util::BodyExt;
 use hyper::{
     body,
-    header::{HeaderMap, HeaderName, HeaderValue, SERVER as HK_SERVER},
+    header::{HeaderMap, HeaderValue, LINK, SERVER as HK_SERVER},
     Response, StatusCode,
 };
-use pyo3::{prelude::*, pybacked::PyBackedBytes, types::PyDict};
+use pyo3::{
+    exceptions::PyValueError,
+    prelude::*,
+    pybacked::PyBackedBytes,
+    types::{PyDict, PyString},
+};
 use std::{
     borrow::Cow,
     sync::{atomic, Arc, Mutex},
 };
 use tokio::{
     fs::File,
-    sync::{mpsc, oneshot, Mutex as AsyncMutex},
+    sync::{oneshot, Mutex as AsyncMutex},
 };
 use tokio_tungstenite::tungstenite::Message;
 use tokio_util::io::ReaderStream;
+use tokio_util::sync::CancellationToken;
 
 use super::{
-    errors::{error_flow, error_message, UnsupportedASGIMessage},
+    errors::{error_flow, error_message, error_transport, UnsupportedASGIMessage},
     types::ASGIMessageType,
 };
 use crate::{
-    conversion::FutureResultToPy,
-    http::{response_404, HTTPResponse, HTTPResponseBody, HV_SERVER},
+    conversion::{header_from_py_bytes, status_code_from_py, FutureResultToPy},
+    http::{
+        body_channel, response_404, DisconnectAwareBody, HTTPBodySender, HTTPResponse, HTTPResponseBody, InterimSender,
+        HEAD_EARLY_HINTS, HV_SERVER,
+    },
     runtime::{empty_future_into_py, future_into_py_futlike, future_into_py_iter, Runtime, RuntimeRef},
-    ws::{HyperWebsocket, UpgradeData, WSRxStream, WSTxStream},
+    ws::{HyperWebsocket, UpgradeData, WSFragments, WSRxStream, WSTxStream},
 };
 
 const EMPTY_BYTES: Cow<[u8]> = Cow::Borrowed(b"");
 const EMPTY_STRING: String = String::new();
 static WS_SUBPROTO_HNAME: &str = "Sec-WebSocket-Protocol";
+const ZEROCOPY_CHUNK_SIZE: usize = 64 * 1024;
 
 #[pyclass(frozen, module = "granian._granian")]
 pub(crate) struct ASGIHTTPProtocol {
@@ -40,69 +50,176 @@ pub(crate) struct ASGIHTTPProtocol {
     request_body: Arc<AsyncMutex<http_body_util::BodyStream<body::Incoming>>>,
     response_started: atomic::AtomicBool,
     response_chunked: atomic::AtomicBool,
-    response_intent: Mutex<Option<(u16, HeaderMap)>>,
-    body_tx: Mutex<Option<mpsc::Sender<Result<body::Bytes, anyhow::Error>>>>,
+    response_trailers: atomic::AtomicBool,
+    response_trailers_acc: Mutex<HeaderMap>,
+    response_intent: Mutex<Option<(StatusCode, HeaderMap)>>,
+    body_tx: Mutex<Option<HTTPBodySender>>,
     flow_rx_exhausted: Arc<atomic::AtomicBool>,
     flow_tx_waiter: Arc<tokio::sync::Notify>,
     sent_response_code: Arc<atomic::AtomicU16>,
+    disconnect: CancellationToken,
+    stream_buffer_size: usize,
+    interim: Option<InterimSender>,
 }
 
 impl ASGIHTTPProtocol {
-    pub fn new(rt: RuntimeRef, body: hyper::body::Incoming, tx: oneshot::Sender<HTTPResponse>) -> Self {
+    pub fn new(
+        rt: RuntimeRef,
+        body: hyper::body::Incoming,
+        tx: oneshot::Sender<HTTPResponse>,
+        interim: Option<InterimSender>,
+        disconnect: CancellationToken,
+        stream_buffer_size: usize,
+    ) -> Self {
         Self {
             rt,
             tx: Mutex::new(Some(tx)),
             request_body: Arc::new(AsyncMutex::new(http_body_util::BodyStream::new(body))),
             response_started: false.into(),
             response_chunked: false.into(),
+            response_trailers: false.into(),
+            response_trailers_acc: Mutex::new(HeaderMap::new()),
             response_intent: Mutex::new(None),
             body_tx: Mutex::new(None),
             flow_rx_exhausted: Arc::new(atomic::AtomicBool::new(false)),
             flow_tx_waiter: Arc::new(tokio::sync::Notify::new()),
             sent_response_code: Arc::new(atomic::AtomicU16::new(500)),
+            disconnect,
+            stream_buffer_size,
+            interim,
         }
     }
 
     #[inline(always)]
-    fn send_response(&self, status: u16, headers: HeaderMap<HeaderValue>, body: HTTPResponseBody) {
+    fn send_response(&self, status: StatusCode, headers: HeaderMap<HeaderValue>, body: HTTPResponseBody) {
         if let Some(tx) = self.tx.lock().unwrap().take() {
             let mut res = Response::new(body);
-            *res.status_mut() = hyper::StatusCode::from_u16(status).unwrap();
+            *res.status_mut() = status;
             *res.headers_mut() = headers;
             let _ = tx.send(res);
-            self.sent_response_code.store(status, atomic::Ordering::Relaxed);
+            self.sent_response_code
+                .store(status.as_u16(), atomic::Ordering::Relaxed);
         }
     }
 
+    #[inline]
+    fn start_stream_response(&self) -> HTTPBodySender {
+        self.response_chunked.store(true, atomic::Ordering::Relaxed);
+        let (status, headers) = self.response_intent.lock().unwrap().take().unwrap();
+        let (body_tx, body_rx) = body_channel(self.stream_buffer_size);
+        *self.body_tx.lock().unwrap() = Some(body_tx.clone());
+        self.send_response(
+            status,
+            headers,
+            DisconnectAwareBody::boxed(BodyExt::boxed(body_rx), self.disconnect.clone()),
+        );
+        body_tx
+    }
+
     #[inline]
     fn send_body<'p>(
         &self,
         py: Python<'p>,
-        tx: mpsc::Sender<Result<body::Bytes, anyhow::Error>>,
+        tx: HTTPBodySender,
         body: Box<[u8]>,
         close: bool,
     ) -> PyResult<Bound<'p, PyAny>> {
         let flow_hld = self.flow_tx_waiter.clone();
 
         future_into_py_futlike(self.rt.clone(), py, async move {
-            match tx.send(Ok(body.into())).await {
+            match tx.send(body.into()).await {
                 Ok(()) => {
                     if close {
                         flow_hld.notify_one();
                     }
+                    FutureResultToPy::None
                 }
                 Err(err) => {
                     log::warn!("ASGI transport error: {:?}", err);
                     flow_hld.notify_one();
+                    FutureResultToPy::Err(error_transport!())
+                }
+            }
+        })
+    }
+
+    #[inline]
+    fn send_file<'p>(
+        &self,
+        py: Python<'p>,
+        tx: HTTPBodySender,
+        file: std::fs::File,
+        offset: Option<u64>,
+        count: Option<u64>,
+        close: bool,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let flow_hld = self.flow_tx_waiter.clone();
+
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            let file = Arc::new(file);
+            let mut position = offset;
+            let mut remaining = count.unwrap_or(u64::MAX);
+            while remaining > 0 {
+                let size = usize::try_from(remaining)
+                    .unwrap_or(usize::MAX)
+                    .min(ZEROCOPY_CHUNK_SIZE);
+                let fref = file.clone();
+                let chunk = tokio::task::spawn_blocking(move || {
+                    let mut buf = vec![0; size];
+                    read_file_chunk(&fref, &mut buf, position).map(|read| {
+                        buf.truncate(read);
+                        buf
+                    })
+                })
+                .await;
+                let data = match chunk {
+                    Ok(Ok(data)) if data.is_empty() => break,
+                    Ok(Ok(data)) => data,
+                    _ => {
+                        log::info!("ASGI zerocopy failed to read the file");
+                        flow_hld.notify_one();
+                        return FutureResultToPy::Err(error_transport!());
+                    }
+                };
+                remaining -= data.len() as u64;
+                position = position.map(|v| v + data.len() as u64);
+                if let Err(err) = tx.send(data.into()).await {
+                    log::warn!("ASGI transport error: {:?}", err);
+                    flow_hld.notify_one();
+                    return FutureResultToPy::Err(error_transport!());
                 }
             }
+            if close {
+                flow_hld.notify_one();
+            }
             FutureResultToPy::None
         })
     }
 
+    #[inline]
+    fn send_trailers<'p>(&self, py: Python<'p>, tx: HTTPBodySender, trailers: HeaderMap) -> PyResult<Bound<'p, PyAny>> {
+        let flow_hld = self.flow_tx_waiter.clone();
+
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            let ret = match tx.send_trailers(trailers).await {
+                Ok(()) => FutureResultToPy::None,
+                Err(err) => {
+                    log::warn!("ASGI transport error: {:?}", err);
+                    FutureResultToPy::Err(error_transport!())
+                }
+            };
+            flow_hld.notify_one();
+            ret
+        })
+    }
+
     pub fn tx(&self) -> Option<oneshot::Sender<HTTPResponse>> {
         self.tx.lock().unwrap().take()
     }
+
+    pub fn release(&self) {
+        self.disconnect.cancel();
+    }
 }
 
 #[pymethods]
@@ -110,8 +227,12 @@ impl ASGIHTTPProtocol {
     fn receive<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
         if self.flow_rx_exhausted.load(atomic::Ordering::Relaxed) {
             let flow_hld = self.flow_tx_waiter.clone();
+            let disconnect = self.disconnect.clone();
             return future_into_py_futlike(self.rt.clone(), py, async move {
-                let () = flow_hld.notified().await;
+                tokio::select! {
+                    () = flow_hld.notified() => {},
+                    () = disconnect.cancelled() => {}
+                }
                 FutureResultToPy::ASGIMessage(ASGIMessageType::HTTPDisconnect)
             });
         }
@@ -123,20 +244,29 @@ impl ASGIHTTPProtocol {
             let mut bodym = body_ref.lock().await;
             let body = &mut *bodym;
             let mut more_body = false;
-            let chunk = match body.next().await {
-                Some(Ok(buf)) => {
-                    more_body = true;
-                    Ok(buf.into_data().unwrap_or_default())
+            let mut trailers = None;
+            let chunk = loop {
+                match body.next().await {
+                    Some(Ok(buf)) => match buf.into_data() {
+                        Ok(data) => {
+                            more_body = true;
+                            break Ok(data);
+                        }
+                        // trailers are the last frame, we deliver them with the final message
+                        Err(frame) => trailers = frame.into_trailers().ok(),
+                    },
+                    Some(Err(err)) => break Err(err),
+                    _ => break Ok(body::Bytes::new()),
                 }
-                Some(Err(err)) => Err(err),
-                _ => Ok(body::Bytes::new()),
             };
             if !more_body {
                 flow_ref.store(true, atomic::Ordering::Relaxed);
             }
 
             match chunk {
-                Ok(data) => FutureResultToPy::ASGIMessage(ASGIMessageType::HTTPRequestBody((data, more_body))),
+                Ok(data) => {
+                    FutureResultToPy::ASGIMessage(ASGIMessageType::HTTPRequestBody((data, more_body, trailers)))
+                }
                 _ => {
                     flow_hld.notify_one();
                     FutureResultToPy::ASGIMessage(ASGIMessageType::HTTPDisconnect)
@@ -147,11 +277,12 @@ impl ASGIHTTPProtocol {
 
     fn send<'p>(&self, py: Python<'p>, data: &Bound<'p, PyDict>) -> PyResult<Bound<'p, PyAny>> {
         match adapt_message_type(py, data) {
-            Ok(ASGIMessageType::HTTPResponseStart(intent)) => {
+            Ok(ASGIMessageType::HTTPResponseStart((status, headers, trailers))) => {
                 match self.response_started.load(atomic::Ordering::Relaxed) {
                     false => {
                         let mut response_intent = self.response_intent.lock().unwrap();
-                        *response_intent = Some(intent);
+                        *response_intent = Some((status, headers));
+                        self.response_trailers.store(trailers, atomic::Ordering::Relaxed);
                         self.response_started.store(true, atomic::Ordering::Relaxed);
                         empty_future_into_py(py)
                     }
@@ -163,8 +294,9 @@ impl ASGIHTTPProtocol {
                     self.response_started.load(atomic::Ordering::Relaxed),
                     more,
                     self.response_chunked.load(atomic::Ordering::Relaxed),
+                    self.response_trailers.load(atomic::Ordering::Relaxed),
                 ) {
-                    (true, false, false) => {
+                    (true, false, false, false) => {
                         let (status, headers) = self.response_intent.lock().unwrap().take().unwrap();
                         self.send_response(
                             status,
@@ -176,25 +308,27 @@ impl ASGIHTTPProtocol {
                         self.flow_tx_waiter.notify_one();
                         empty_future_into_py(py)
                     }
-                    (true, true, false) => {
-                        self.response_chunked.store(true, atomic::Ordering::Relaxed);
-                        let (status, headers) = self.response_intent.lock().unwrap().take().unwrap();
-                        let (body_tx, body_rx) = mpsc::channel::<Result<body::Bytes, anyhow::Error>>(1);
-                        let body_stream = http_body_util::StreamBody::new(
-                            tokio_stream::wrappers::ReceiverStream::new(body_rx).map_ok(body::Frame::data),
-                        );
-                        *self.body_tx.lock().unwrap() = Some(body_tx.clone());
-                        self.send_response(status, headers, BodyExt::boxed(body_stream));
+                    // NOTE: when trailers are expected, we always need a streamed body
+                    (true, _, false, _) => {
+                        let body_tx = self.start_stream_response();
                         self.send_body(py, body_tx, body, false)
                     }
-                    (true, true, true) => match &*self.body_tx.lock().unwrap() {
+                    (true, true, true, _) => match &*self.body_tx.lock().unwrap() {
                         Some(tx) => {
                             let tx = tx.clone();
                             self.send_body(py, tx, body, false)
                         }
                         _ => error_flow!(),
                     },
-                    (true, false, true) => match self.body_tx.lock().unwrap().take() {
+                    (true, false, true, true) => match (&*self.body_tx.lock().unwrap(), body.is_empty()) {
+                        (Some(tx), false) => {
+                            let tx = tx.clone();
+                            self.send_body(py, tx, body, false)
+                        }
+                        (Some(_), true) => empty_future_into_py(py),
+                        _ => error_flow!(),
+                    },
+                    (true, false, true, false) => match self.body_tx.lock().unwrap().take() {
                         Some(tx) => match body.is_empty() {
                             false => self.send_body(py, tx, body, true),
                             true => {
@@ -207,6 +341,51 @@ impl ASGIHTTPProtocol {
                     _ => error_flow!(),
                 }
             }
+            Ok(ASGIMessageType::HTTPResponseZeroCopy((file, offset, count, more))) => {
+                if !self.response_started.load(atomic::Ordering::Relaxed) {
+                    return error_flow!();
+                }
+                if !self.response_chunked.load(atomic::Ordering::Relaxed) {
+                    self.start_stream_response();
+                }
+                let close = !more && !self.response_trailers.load(atomic::Ordering::Relaxed);
+                let tx = match close {
+                    true => self.body_tx.lock().unwrap().take(),
+                    false => self.body_tx.lock().unwrap().clone(),
+                };
+                match tx {
+                    Some(tx) => self.send_file(py, tx, file, offset, count, close),
+                    _ => error_flow!(),
+                }
+            }
+            Ok(ASGIMessageType::HTTPResponseEarlyHint(headers)) => {
+                if self.tx.lock().unwrap().is_none() {
+                    return error_flow!();
+                }
+                // NOTE: informational responses are only supported on HTTP/1.1 connections,
+                //       elsewhere hints are dropped so applications don't need to care about the protocol.
+                match &self.interim {
+                    Some(interim) => interim.send(HEAD_EARLY_HINTS, &headers),
+                    None => log::debug!("Dropping early hints: unsupported on the current connection"),
+                }
+                empty_future_into_py(py)
+            }
+            Ok(ASGIMessageType::HTTPResponseTrailers((headers, more))) => {
+                if !self.response_trailers.load(atomic::Ordering::Relaxed) {
+                    return error_flow!();
+                }
+                let mut trailers = self.response_trailers_acc.lock().unwrap();
+                for (key, value) in &headers {
+                    trailers.append(key, value.clone());
+                }
+                if more {
+                    return empty_future_into_py(py);
+                }
+                match self.body_tx.lock().unwrap().take() {
+                    Some(tx) => self.send_trailers(py, tx, std::mem::take(&mut *trailers)),
+                    _ => error_flow!(),
+                }
+            }
             Ok(ASGIMessageType::HTTPResponseFile(file_path)) => match (
                 self.response_started.load(atomic::Ordering::Relaxed),
                 self.tx.lock().unwrap().take(),
@@ -215,7 +394,8 @@ impl ASGIHTTPProtocol {
                     let (status, headers) = self.response_intent.lock().unwrap().take().unwrap();
                     // FIXME: to store the actual status in case of 404 this should be re-implemented taking
                     //        into account the following async flow (we return empty future to avoid waiting)
-                    self.sent_response_code.store(status, atomic::Ordering::Relaxed);
+                    self.sent_response_code
+                        .store(status.as_u16(), atomic::Ordering::Relaxed);
                     self.rt.spawn(async move {
                         let res = match File::open(&file_path).await {
                             Ok(file) => {
@@ -223,7 +403,7 @@ impl ASGIHTTPProtocol {
                                 let stream_body = http_body_util::StreamBody::new(stream.map_ok(body::Frame::data));
                                 let mut res =
                                     Response::new(BodyExt::map_err(stream_body, std::convert::Into::into).boxed());
-                                *res.status_mut() = StatusCode::from_u16(status).unwrap();
+                                *res.status_mut() = status;
                                 *res.headers_mut() = headers;
                                 res
                             }
@@ -238,7 +418,7 @@ impl ASGIHTTPProtocol {
                 }
                 _ => error_flow!(),
             },
-            Err(err) => Err(err.into()),
+            Err(err) => Err(err),
             _ => error_message!(),
         }
     }
@@ -261,7 +441,7 @@ impl WebsocketDetachedTransport {
     }
 
     pub async fn close(&mut self) {
-        if let Some(mut tx) = self.tx.take() {
+        if let Some(tx) = self.tx.take() {
             if let Err(err) = tx.close().await {
                 log::info!("Failed to close websocket with error {:?}", err);
             }
@@ -280,6 +460,8 @@ pub(crate) struct ASGIWebsocketProtocol {
     ws_tx: Arc<AsyncMutex<Option<WSTxStream>>>,
     accepted: Arc<atomic::AtomicBool>,
     closed: Arc<atomic::AtomicBool>,
+    fragments: Arc<WSFragments>,
+    denial: Mutex<Option<(StatusCode, HeaderMap, Vec<u8>)>>,
 }
 
 impl ASGIWebsocketProtocol {
@@ -298,6 +480,8 @@ impl ASGIWebsocketProtocol {
             ws_tx: Arc::new(AsyncMutex::new(None)),
             accepted: Arc::new(false.into()),
             closed: Arc::new(false.into()),
+            fragments: Arc::new(WSFragments::new()),
+            denial: Mutex::new(None),
         }
     }
 
@@ -334,14 +518,27 @@ impl ASGIWebsocketProtocol {
     }
 
     #[inline(always)]
-    fn send_message<'p>(&self, py: Python<'p>, data: Message) -> PyResult<Bound<'p, PyAny>> {
+    fn send_message<'p>(&self, py: Python<'p>, data: Message, more: bool) -> PyResult<Bound<'p, PyAny>> {
         let transport = self.ws_tx.clone();
         let closed = self.closed.clone();
+        let fragments = self.fragments.clone();
 
         future_into_py_futlike(self.rt.clone(), py, async move {
             if let Some(ws) = &mut *(transport.lock().await) {
+                let (data, state) = match (more, fragments.pending()) {
+                    (false, false) => (data, None),
+                    _ => match fragments.frame(data, !more) {
+                        Some((frame, state)) => (Message::Frame(frame), Some(state)),
+                        None => return FutureResultToPy::Err(error_message!()),
+                    },
+                };
                 match ws.send(data).await {
-                    Ok(()) => return FutureResultToPy::None,
+                    Ok(()) => {
+                        if let Some(state) = state {
+                            fragments.commit(state);
+                        }
+                        return FutureResultToPy::None;
+                    }
                     _ => {
                         if closed.load(atomic::Ordering::Relaxed) {
                             log::info!("Attempted to write to a closed websocket");
@@ -371,6 +568,46 @@ impl ASGIWebsocketProtocol {
         })
     }
 
+    #[inline(always)]
+    fn deny_start<'p>(&self, py: Python<'p>, status: StatusCode, headers: HeaderMap) -> PyResult<Bound<'p, PyAny>> {
+        let mut denial = self.denial.lock().unwrap();
+        if denial.is_some() || self.consumed() {
+            return error_flow!();
+        }
+        *denial = Some((status, headers, Vec::new()));
+        empty_future_into_py(py)
+    }
+
+    #[inline(always)]
+    fn deny_body<'p>(&self, py: Python<'p>, body: Box<[u8]>, more: bool) -> PyResult<Bound<'p, PyAny>> {
+        let mut denial = self.denial.lock().unwrap();
+        let Some((_, _, acc)) = denial.as_mut() else {
+            return error_flow!();
+        };
+        acc.extend_from_slice(&body);
+        if more {
+            return empty_future_into_py(py);
+        }
+
+        let (status, headers, body) = denial.take().unwrap();
+        let upgrade = self.upgrade.lock().unwrap().take();
+        future_into_py_iter(self.rt.clone(), py, async move {
+            if let Some(mut upgrade) = upgrade {
+                let mut res = Response::new(
+                    http_body_util::Full::new(body::Bytes::from(body))
+                        .map_err(|e| match e {})
+                        .boxed(),
+                );
+                *res.status_mut() = status;
+                *res.headers_mut() = headers;
+                if upgrade.deny(res).await.is_ok() {
+                    return FutureResultToPy::None;
+                }
+            }
+            FutureResultToPy::Err(error_flow!())
+        })
+    }
+
     fn consumed(&self) -> bool {
         self.upgrade.lock().unwrap().is_none()
     }
@@ -404,16 +641,13 @@ impl ASGIWebsocketProtocol {
             }
 
             if let Some(ws) = &mut *(transport.lock().await) {
-                while let Some(recv) = ws.next().await {
-                    match recv {
-                        Ok(Message::Ping(_) | Message::Pong(_)) => continue,
-                        Ok(message @ Message::Close(_)) => {
-                            closed.store(true, atomic::Ordering::Relaxed);
-                            return FutureResultToPy::ASGIWSMessage(message);
-                        }
-                        Ok(message) => return FutureResultToPy::ASGIWSMessage(message),
-                        _ => break,
+                match ws.next().await {
+                    Some(Ok(message @ Message::Close(_))) => {
+                        closed.store(true, atomic::Ordering::Relaxed);
+                        return FutureResultToPy::ASGIWSMessage(message);
                     }
+                    Some(Ok(message)) => return FutureResultToPy::ASGIWSMessage(message),
+                    _ => {}
                 }
             }
             FutureResultToPy::Err(error_flow!())
@@ -424,24 +658,36 @@ impl ASGIWebsocketProtocol {
         match adapt_message_type(py, data) {
             Ok(ASGIMessageType::WSAccept(subproto)) => self.accept(py, subproto),
             Ok(ASGIMessageType::WSClose) => self.close(py),
-            Ok(ASGIMessageType::WSMessage(message)) => self.send_message(py, message),
+            Ok(ASGIMessageType::WSMessage((message, more))) => self.send_message(py, message, more),
+            Ok(ASGIMessageType::WSDenialStart((status, headers))) => self.deny_start(py, status, headers),
+            Ok(ASGIMessageType::WSDenialBody((body, more))) => self.deny_body(py, body, more),
+            Err(err) => Err(err),
             _ => future_into_py_iter::<_, _>(self.rt.clone(), py, async { FutureResultToPy::Err(error_message!()) }),
         }
     }
 }
 
 #[inline(never)]
-fn adapt_message_type(py: Python, message: &Bound<PyDict>) -> Result<ASGIMessageType, UnsupportedASGIMessage> {
+fn adapt_message_type(py: Python, message: &Bound<PyDict>) -> PyResult<ASGIMessageType> {
     match message.get_item(pyo3::intern!(py, "type")) {
         Ok(Some(item)) => {
-            let message_type: &str = item.extract()?;
+            let message_type: &str = item.extract().map_err(|_| UnsupportedASGIMessage)?;
             match message_type {
                 "http.response.start" => Ok(ASGIMessageType::HTTPResponseStart((
                     adapt_status_code(py, message)?,
-                    adapt_headers(py, message),
+                    adapt_headers(py, message)?,
+                    adapt_flag(message, pyo3::intern!(py, "trailers")),
                 ))),
                 "http.response.body" => Ok(ASGIMessageType::HTTPResponseBody(adapt_body(py, message))),
+                "http.response.trailers" => Ok(ASGIMessageType::HTTPResponseTrailers((
+                    adapt_trailers(py, message)?,
+                    adapt_flag(message, pyo3::intern!(py, "more_trailers")),
+                ))),
                 "http.response.pathsend" => Ok(ASGIMessageType::HTTPResponseFile(adapt_file(py, message)?)),
+                "http.response.early_hint" => {
+                    Ok(ASGIMessageType::HTTPResponseEarlyHint(adapt_early_hint(py, message)?))
+                }
+                "http.response.zerocopy" => Ok(ASGIMessageType::HTTPResponseZeroCopy(adapt_zerocopy(py, message)?)),
                 "websocket.accept" => {
                     let subproto: Option<String> = match message.get_item(pyo3::intern!(py, "subprotocol")) {
                         Ok(Some(item)) => item.extract::<String>().map(Some).unwrap_or(None),
@@ -450,7 +696,15 @@ fn adapt_message_type(py: Python, message: &Bound<PyDict>) -> Result<ASGIMessage
                     Ok(ASGIMessageType::WSAccept(subproto))
                 }
                 "websocket.close" => Ok(ASGIMessageType::WSClose),
-                "websocket.send" => Ok(ASGIMessageType::WSMessage(ws_message_into_rs(py, message)?)),
+                "websocket.send" => Ok(ASGIMessageType::WSMessage((
+                    ws_message_into_rs(py, message)?,
+                    adapt_flag(message, pyo3::intern!(py, "more_body")),
+                ))),
+                "websocket.http.response.start" => Ok(ASGIMessageType::WSDenialStart((
+                    adapt_status_code(py, message)?,
+                    adapt_headers(py, message)?,
+                ))),
+                "websocket.http.response.body" => Ok(ASGIMessageType::WSDenialBody(adapt_body(py, message))),
                 _ => error_message!(),
             }
         }
@@ -459,28 +713,66 @@ fn adapt_message_type(py: Python, message: &Bound<PyDict>) -> Result<ASGIMessage
 }
 
 #[inline(always)]
-fn adapt_status_code(py: Python, message: &Bound<PyDict>) -> Result<u16, UnsupportedASGIMessage> {
+fn adapt_status_code(py: Python, message: &Bound<PyDict>) -> PyResult<StatusCode> {
     match message.get_item(pyo3::intern!(py, "status"))? {
-        Some(item) => Ok(item.extract()?),
+        Some(item) => status_code_from_py(item.extract().map_err(|_| UnsupportedASGIMessage)?),
         _ => error_message!(),
     }
 }
 
 #[inline(always)]
-fn adapt_headers(py: Python, message: &Bound<PyDict>) -> HeaderMap {
+fn adapt_headers(py: Python, message: &Bound<PyDict>) -> PyResult<HeaderMap> {
     let mut ret = HeaderMap::new();
     ret.insert(HK_SERVER, HV_SERVER);
-    match message.get_item(pyo3::intern!(py, "headers")) {
-        Ok(Some(item)) => {
-            let accum: Vec<Vec<PyBackedBytes>> = item.extract().unwrap_or(Vec::new());
-            for tup in &accum {
-                if let (Ok(key), Ok(val)) = (HeaderName::from_bytes(&tup[0]), HeaderValue::from_bytes(&tup[1])) {
-                    ret.append(key, val);
-                }
-            }
-            ret
+    append_headers(py, message, &mut ret)?;
+    Ok(ret)
+}
+
+#[inline(always)]
+fn adapt_trailers(py: Python, message: &Bound<PyDict>) -> PyResult<HeaderMap> {
+    let mut ret = HeaderMap::new();
+    append_headers(py, message, &mut ret)?;
+    Ok(ret)
+}
+
+#[inline(always)]
+fn append_headers(py: Python, message: &Bound<PyDict>, headers: &mut HeaderMap) -> PyResult<()> {
+    if let Some(item) = message.get_item(pyo3::intern!(py, "headers"))? {
+        for pair in item.try_iter()? {
+            let pair: Vec<PyBackedBytes> = pair?.extract()?;
+            let [key, value] = &pair[..] else {
+                return Err(PyValueError::new_err("Headers should be pairs of name and value"));
+            };
+            let (key, value) = header_from_py_bytes(key, value)?;
+            headers.append(key, value);
         }
-        _ => ret,
+    }
+    Ok(())
+}
+
+#[inline(always)]
+fn adapt_early_hint(py: Python, message: &Bound<PyDict>) -> PyResult<HeaderMap> {
+    let mut ret = HeaderMap::new();
+    if let Some(item) = message.get_item(pyo3::intern!(py, "links"))? {
+        let links: Vec<PyBackedBytes> = item.extract()?;
+        for link in &links {
+            let Ok(value) = HeaderValue::from_bytes(link) else {
+                return Err(PyValueError::new_err(format!(
+                    "Invalid link: {:?}",
+                    String::from_utf8_lossy(link)
+                )));
+            };
+            ret.append(LINK, value);
+        }
+    }
+    Ok(ret)
+}
+
+#[inline(always)]
+fn adapt_flag(message: &Bound<PyDict>, key: &Bound<PyString>) -> bool {
+    match message.get_item(key) {
+        Ok(Some(item)) => item.extract().unwrap_or(false),
+        _ => false,
     }
 }
 
@@ -506,6 +798,72 @@ fn adapt_file(py: Python, message: &Bound<PyDict>) -> PyResult<String> {
     }
 }
 
+#[cfg(unix)]
+#[inline(always)]
+fn adapt_zerocopy(
+    py: Python,
+    message: &Bound<PyDict>,
+) -> Result<(std::fs::File, Option<u64>, Option<u64>, bool), UnsupportedASGIMessage> {
+    use std::os::fd::BorrowedFd;
+
+    let Some(file) = message.get_item(pyo3::intern!(py, "file"))? else {
+        return error_message!();
+    };
+    let fd: i32 = file.call_method0(pyo3::intern!(py, "fileno"))?.extract()?;
+    // NOTE: we need our own descriptor, as the application is free to close the file
+    //       once the message is sent.
+    let Ok(fd) = unsafe { BorrowedFd::borrow_raw(fd) }.try_clone_to_owned() else {
+        return error_message!();
+    };
+    let offset: Option<u64> = match message.get_item(pyo3::intern!(py, "offset"))? {
+        Some(item) => item.extract()?,
+        _ => None,
+    };
+    let count: Option<u64> = match message.get_item(pyo3::intern!(py, "count"))? {
+        Some(item) => item.extract()?,
+        _ => None,
+    };
+    Ok((
+        std::fs::File::from(fd),
+        offset,
+        count,
+        adapt_flag(message, pyo3::intern!(py, "more_body")),
+    ))
+}
+
+#[cfg(not(unix))]
+#[inline(always)]
+fn adapt_zerocopy(
+    _py: Python,
+    _message: &Bound<PyDict>,
+) -> Result<(std::fs::File, Option<u64>, Option<u64>, bool), UnsupportedASGIMessage> {
+    error_message!()
+}
+
+// NOTE: hyper owns the socket, so we can't rely on `sendfile`: we read the file in chunks instead.
+//       When an offset is given, the file position is left untouched.
+#[cfg(unix)]
+fn read_file_chunk(file: &std::fs::File, buf: &mut [u8], position: Option<u64>) -> std::io::Result<usize> {
+    use std::io::Read;
+    use std::os::unix::fs::FileExt;
+
+    match position {
+        Some(position) => file.read_at(buf, position),
+        None => (&*file).read(buf),
+    }
+}
+
+#[cfg(not(unix))]
+fn read_file_chunk(file: &std::fs::File, buf: &mut [u8], position: Option<u64>) -> std::io::Result<usize> {
+    use std::io::Read;
+    use std::os::windows::fs::FileExt;
+
+    match position {
+        Some(position) => file.seek_read(buf, position),
+        None => (&*file).read(buf),
+    }
+}
+
 #[inline(always)]
 fn ws_message_into_rs(py: Python, message: &Bound<PyDict>) -> PyResult<Message> {
     match (
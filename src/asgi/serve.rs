@@ -1,9 +1,15 @@
 use pyo3::prelude::*;
+use std::path::PathBuf;
 
 use super::http::{handle, handle_ws};
 
 use crate::callbacks::CallbackScheduler;
-use crate::conversion::{worker_http1_config_from_py, worker_http2_config_from_py};
+use crate::conversion::{
+    worker_adaptive_concurrency_config_from_py, worker_concurrency_config_from_py, worker_connection_config_from_py,
+    worker_http1_config_from_py, worker_http2_config_from_py, worker_load_shedding_config_from_py,
+    worker_rate_limit_config_from_py, worker_tls_config_from_py, worker_websocket_config_from_py,
+};
+use crate::tls::TLSMaterial;
 use crate::workers::{serve_rth, serve_rth_ssl, serve_wth, serve_wth_ssl, WorkerConfig, WorkerSignal, WorkerSignals};
 
 #[pyclass(frozen, module = "granian._granian")]
@@ -35,13 +41,27 @@ impl ASGIWorker {
             http_mode="1",
             http1_opts=None,
             http2_opts=None,
+            connection_opts=None,
+            rate_limit_opts=None,
+            rate_limit_shm=None,
+            rate_limit_seed=0,
+            load_shedding_opts=None,
+            concurrency_opts=None,
+            adaptive_concurrency_opts=None,
+            strict_requests=false,
             websockets_enabled=false,
+            websockets_opts=None,
+            cancel_on_disconnect=false,
+            stream_buffer_size=65536,
             ssl_enabled=false,
             ssl_cert=None,
             ssl_key=None,
-            ssl_key_password=None
+            ssl_key_password=None,
+            ssl_opts=None,
+            ssl_acme=None
         )
     )]
+    #[allow(clippy::fn_params_excessive_bools)]
     fn new(
         py: Python,
         worker_id: i32,
@@ -52,11 +72,24 @@ impl ASGIWorker {
         http_mode: &str,
         http1_opts: Option<PyObject>,
         http2_opts: Option<PyObject>,
+        connection_opts: Option<PyObject>,
+        rate_limit_opts: Option<PyObject>,
+        rate_limit_shm: Option<String>,
+        rate_limit_seed: u64,
+        load_shedding_opts: Option<PyObject>,
+        concurrency_opts: Option<PyObject>,
+        adaptive_concurrency_opts: Option<PyObject>,
+        strict_requests: bool,
         websockets_enabled: bool,
+        websockets_opts: Option<PyObject>,
+        cancel_on_disconnect: bool,
+        stream_buffer_size: usize,
         ssl_enabled: bool,
-        ssl_cert: Option<&str>,
-        ssl_key: Option<&str>,
+        ssl_cert: Option<TLSMaterial>,
+        ssl_key: Option<TLSMaterial>,
         ssl_key_password: Option<&str>,
+        ssl_opts: Option<PyObject>,
+        ssl_acme: Option<PathBuf>,
     ) -> PyResult<Self> {
         Ok(Self {
             config: WorkerConfig::new(
@@ -68,11 +101,22 @@ impl ASGIWorker {
                 http_mode,
                 worker_http1_config_from_py(py, http1_opts)?,
                 worker_http2_config_from_py(py, http2_opts)?,
+                worker_connection_config_from_py(py, connection_opts)?,
+                worker_rate_limit_config_from_py(py, rate_limit_opts, rate_limit_shm, rate_limit_seed)?,
+                worker_load_shedding_config_from_py(py, load_shedding_opts)?,
+                worker_concurrency_config_from_py(py, concurrency_opts)?,
+                worker_adaptive_concurrency_config_from_py(py, adaptive_concurrency_opts)?,
+                strict_requests,
                 websockets_enabled,
+                worker_websocket_config_from_py(py, websockets_opts)?,
+                cancel_on_disconnect,
+                stream_buffer_size,
                 ssl_enabled,
                 ssl_cert,
                 ssl_key,
                 ssl_key_password,
+                worker_tls_config_from_py(py, ssl_opts)?,
+                ssl_acme,
             ),
         })
     }
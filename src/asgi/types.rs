@@ -1,14 +1,19 @@
-use hyper::{body, HeaderMap};
+use hyper::{body, HeaderMap, StatusCode};
 use tokio_tungstenite::tungstenite::Message;
 
 pub(crate) enum ASGIMessageType {
-    HTTPResponseStart((u16, HeaderMap)),
+    HTTPResponseStart((StatusCode, HeaderMap, bool)),
     HTTPResponseBody((Box<[u8]>, bool)),
+    HTTPResponseTrailers((HeaderMap, bool)),
     HTTPResponseFile(String),
+    HTTPResponseEarlyHint(HeaderMap),
+    HTTPResponseZeroCopy((std::fs::File, Option<u64>, Option<u64>, bool)),
     HTTPDisconnect,
-    HTTPRequestBody((body::Bytes, bool)),
+    HTTPRequestBody((body::Bytes, bool, Option<HeaderMap>)),
     WSAccept(Option<String>),
     WSConnect,
     WSClose,
-    WSMessage(Message),
+    WSMessage((Message, bool)),
+    WSDenialStart((StatusCode, HeaderMap)),
+    WSDenialBody((Box<[u8]>, bool)),
 }
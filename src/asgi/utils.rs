@@ -7,6 +7,9 @@ use pyo3::{
     sync::GILOnceCell,
     types::{PyBytes, PyDict, PyList, PyString},
 };
+use std::sync::Arc;
+
+use crate::tls::{cert_to_pem, TLSConnectionInfo};
 
 static ASGI_VERSION: GILOnceCell<PyObject> = GILOnceCell::new();
 static ASGI_EXTENSIONS: GILOnceCell<PyObject> = GILOnceCell::new();
@@ -17,7 +20,7 @@ macro_rules! scope_native_parts {
             .uri
             .path_and_query()
             .map_or_else(|| ("", ""), |pq| (pq.path(), pq.query().unwrap_or("")));
-        let $path = percent_encoding::percent_decode_str(path_raw).decode_utf8().unwrap();
+        let $path = percent_encoding::percent_decode_str(path_raw).decode_utf8_lossy();
         let $version = match $req.version {
             hyper::Version::HTTP_10 => "1",
             hyper::Version::HTTP_11 => "1.1",
@@ -55,16 +58,27 @@ pub(super) fn build_scope<'p>(
             })?
             .bind(py),
     )?;
-    scope.set_item(
-        pyo3::intern!(py, "extensions"),
-        ASGI_EXTENSIONS
-            .get_or_try_init(py, || {
-                let rv = PyDict::new(py);
-                rv.set_item("http.response.pathsend", PyDict::new(py))?;
-                Ok::<PyObject, PyErr>(rv.into())
-            })?
-            .bind(py),
-    )?;
+    let extensions = ASGI_EXTENSIONS
+        .get_or_try_init(py, || {
+            let rv = PyDict::new(py);
+            rv.set_item("http.response.pathsend", PyDict::new(py))?;
+            rv.set_item("http.response.early_hint", PyDict::new(py))?;
+            rv.set_item("http.response.trailers", PyDict::new(py))?;
+            #[cfg(unix)]
+            rv.set_item("http.response.zerocopy", PyDict::new(py))?;
+            rv.set_item("websocket.fragments", PyDict::new(py))?;
+            rv.set_item("websocket.http.response", PyDict::new(py))?;
+            Ok::<PyObject, PyErr>(rv.into())
+        })?
+        .bind(py);
+    match req.extensions.get::<Arc<TLSConnectionInfo>>() {
+        Some(tls_info) => {
+            let extensions = extensions.downcast::<PyDict>()?.copy()?;
+            extensions.set_item(pyo3::intern!(py, "tls"), build_tls_extension(py, tls_info)?)?;
+            scope.set_item(pyo3::intern!(py, "extensions"), extensions)?;
+        }
+        None => scope.set_item(pyo3::intern!(py, "extensions"), extensions)?,
+    }
     scope.set_item(pyo3::intern!(py, "type"), proto)?;
     scope.set_item(pyo3::intern!(py, "http_version"), version)?;
     scope.set_item(pyo3::intern!(py, "server"), server)?;
@@ -93,6 +107,24 @@ pub(super) fn build_scope<'p>(
     Ok(scope)
 }
 
+fn build_tls_extension<'p>(py: Python<'p>, tls_info: &TLSConnectionInfo) -> PyResult<Bound<'p, PyDict>> {
+    let rv = PyDict::new(py);
+    rv.set_item("server_cert", tls_info.server_cert.as_deref())?;
+    rv.set_item(
+        "client_cert_chain",
+        tls_info
+            .peer_certificates
+            .iter()
+            .map(cert_to_pem)
+            .collect::<Vec<String>>(),
+    )?;
+    rv.set_item("client_cert_name", py.None())?;
+    rv.set_item("client_cert_error", py.None())?;
+    rv.set_item("tls_version", tls_info.protocol_version.map(u16::from))?;
+    rv.set_item("cipher_suite", tls_info.cipher_suite.map(u16::from))?;
+    Ok(rv)
+}
+
 #[inline]
 pub(super) fn build_scope_http<'p>(
     py: Python<'p>,
@@ -138,7 +170,8 @@ pub(super) fn build_scope_ws<'p>(
             req.headers
                 .get_all("Sec-WebSocket-Protocol")
                 .iter()
-                .map(|v| PyString::new(py, v.to_str().unwrap()))
+                .filter_map(|v| v.to_str().ok())
+                .map(|v| PyString::new(py, v))
                 .collect::<Vec<Bound<PyString>>>(),
         )?,
     )?;
@@ -4,8 +4,13 @@ use std::sync::Mutex;
 use pyo3::{exceptions::PyStopIteration, prelude::*, types::PyDict, IntoPyObjectExt};
 use std::sync::{atomic, Arc, OnceLock, RwLock};
 use tokio::sync::Notify;
+use tokio_util::sync::CancellationToken;
 
-use crate::{asyncio::copy_context, conversion::FutureResultToPy};
+use crate::{
+    asyncio::copy_context,
+    conversion::FutureResultToPy,
+    runtime::{ContextExt, Runtime, RuntimeRef},
+};
 
 pub(crate) type ArcCBScheduler = Arc<Py<CallbackScheduler>>;
 
@@ -764,3 +769,60 @@ impl PyFutureResultSetter {
         let _ = target.call1((value,));
     }
 }
+
+pub(crate) struct DisconnectWatcher {
+    rt: RuntimeRef,
+    disconnect: CancellationToken,
+    finished: CancellationToken,
+}
+
+impl DisconnectWatcher {
+    pub fn new(rt: RuntimeRef, disconnect: CancellationToken) -> Self {
+        Self {
+            rt,
+            disconnect,
+            finished: CancellationToken::new(),
+        }
+    }
+
+    /// Schedules the cancellation of the given `asyncio.Task` on client disconnection,
+    /// unless the application completes first.
+    pub fn watch(&self, task: PyObject) {
+        let rt = self.rt.clone();
+        let disconnect = self.disconnect.clone();
+        let finished = self.finished.clone();
+
+        self.rt.spawn(async move {
+            let rb = rt.blocking();
+            tokio::select! {
+                biased;
+                () = finished.cancelled() => {
+                    let _ = rb.run(move || Python::with_gil(|_| drop(task)));
+                },
+                () = disconnect.cancelled() => {
+                    let _ = rb.run(move || {
+                        Python::with_gil(|py| {
+                            if let Ok(cancel) = task.getattr(py, pyo3::intern!(py, "cancel")) {
+                                let _ = rt.py_event_loop(py).call_method1(
+                                    py,
+                                    pyo3::intern!(py, "call_soon_threadsafe"),
+                                    (cancel,),
+                                );
+                            }
+                            drop(task);
+                        });
+                    });
+                }
+            }
+        });
+    }
+
+    pub fn finish(&self) {
+        self.finished.cancel();
+    }
+
+    /// Checks whether the given application error is the cancellation we scheduled.
+    pub fn caused(&self, py: Python, err: &PyErr) -> bool {
+        self.disconnect.is_cancelled() && err.is_instance_of::<pyo3::exceptions::asyncio::CancelledError>(py)
+    }
+}
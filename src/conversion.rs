@@ -1,6 +1,17 @@
-use pyo3::{prelude::*, IntoPyObjectExt};
+use hyper::{
+    header::{HeaderName, HeaderValue},
+    StatusCode,
+};
+use pyo3::{exceptions::PyValueError, prelude::*, IntoPyObjectExt};
 
-use crate::workers::{HTTP1Config, HTTP2Config};
+use crate::{
+    acme::ACMEConfig,
+    limits::{IPNetwork, RateLimitKey},
+    workers::{
+        AdaptiveConcurrencyConfig, ConcurrencyLimitConfig, ConnectionConfig, HTTP1Config, HTTP2Config,
+        LoadSheddingConfig, RateLimitConfig, TLSConfig, WebsocketConfig,
+    },
+};
 
 pub(crate) struct BytesToPy(pub hyper::body::Bytes);
 pub(crate) struct Utf8BytesToPy(pub tokio_tungstenite::tungstenite::Utf8Bytes);
@@ -31,7 +42,7 @@ pub(crate) enum FutureResultToPy {
     Bytes(hyper::body::Bytes),
     ASGIMessage(crate::asgi::types::ASGIMessageType),
     ASGIWSMessage(tokio_tungstenite::tungstenite::Message),
-    RSGIWSMessage(tokio_tungstenite::tungstenite::Message),
+    RSGIWSMessage(tokio_tungstenite::tungstenite::Message, bool),
     Py(PyObject),
 }
 
@@ -47,23 +58,67 @@ impl<'p> IntoPyObject<'p> for FutureResultToPy {
             Self::Bytes(inner) => inner.into_pyobject(py),
             Self::ASGIMessage(message) => crate::asgi::conversion::message_into_py(py, message),
             Self::ASGIWSMessage(message) => crate::asgi::conversion::ws_message_into_py(py, message),
-            Self::RSGIWSMessage(message) => crate::rsgi::conversion::ws_message_into_py(py, message),
+            Self::RSGIWSMessage(message, fin) => crate::rsgi::conversion::ws_message_into_py(py, message, fin),
             Self::Py(obj) => Ok(obj.into_bound(py)),
         }
     }
 }
 
+pub(crate) fn status_code_from_py(status: u16) -> PyResult<StatusCode> {
+    StatusCode::from_u16(status).map_err(|_| PyValueError::new_err(format!("Invalid HTTP status code: {status}")))
+}
+
+pub(crate) fn header_from_py(key: &str, value: &str) -> PyResult<(HeaderName, HeaderValue)> {
+    let Ok(name) = HeaderName::from_bytes(key.as_bytes()) else {
+        return Err(PyValueError::new_err(format!("Invalid header name: {key:?}")));
+    };
+    let Ok(value) = HeaderValue::from_str(value) else {
+        return Err(PyValueError::new_err(format!("Invalid value for header {key:?}")));
+    };
+    Ok((name, value))
+}
+
+pub(crate) fn header_from_py_bytes(key: &[u8], value: &[u8]) -> PyResult<(HeaderName, HeaderValue)> {
+    let Ok(name) = HeaderName::from_bytes(key) else {
+        return Err(PyValueError::new_err(format!(
+            "Invalid header name: {:?}",
+            String::from_utf8_lossy(key)
+        )));
+    };
+    let Ok(value) = HeaderValue::from_bytes(value) else {
+        return Err(PyValueError::new_err(format!(
+            "Invalid value for header {:?}",
+            String::from_utf8_lossy(key)
+        )));
+    };
+    Ok((name, value))
+}
+
 pub(crate) fn worker_http1_config_from_py(py: Python, cfg: Option<PyObject>) -> PyResult<HTTP1Config> {
     let ret = match cfg {
         Some(cfg) => HTTP1Config {
             keep_alive: cfg.getattr(py, "keep_alive")?.extract(py)?,
             max_buffer_size: cfg.getattr(py, "max_buffer_size")?.extract(py)?,
             pipeline_flush: cfg.getattr(py, "pipeline_flush")?.extract(py)?,
+            max_headers: cfg.getattr(py, "max_headers")?.extract(py)?,
+            header_read_timeout: cfg
+                .getattr(py, "header_read_timeout")?
+                .extract::<Option<u64>>(py)?
+                .map(core::time::Duration::from_secs),
+            keep_alive_timeout: cfg
+                .getattr(py, "keep_alive_timeout")?
+                .extract::<Option<u64>>(py)?
+                .map(core::time::Duration::from_secs),
+            max_requests: cfg.getattr(py, "max_requests")?.extract(py)?,
         },
         None => HTTP1Config {
             keep_alive: true,
             max_buffer_size: 8192 + 4096 * 100,
             pipeline_flush: false,
+            max_headers: 100,
+            header_read_timeout: Some(core::time::Duration::from_secs(30)),
+            keep_alive_timeout: None,
+            max_requests: None,
         },
     };
     Ok(ret)
@@ -84,6 +139,10 @@ pub(crate) fn worker_http2_config_from_py(py: Python, cfg: Option<PyObject>) ->
             max_frame_size: cfg.getattr(py, "max_frame_size")?.extract(py)?,
             max_headers_size: cfg.getattr(py, "max_headers_size")?.extract(py)?,
             max_send_buffer_size: cfg.getattr(py, "max_send_buffer_size")?.extract(py)?,
+            max_pending_accept_reset_streams: cfg.getattr(py, "max_pending_accept_reset_streams")?.extract(py)?,
+            max_local_error_reset_streams: cfg.getattr(py, "max_local_error_reset_streams")?.extract(py)?,
+            max_resets_per_second: cfg.getattr(py, "max_resets_per_second")?.extract(py)?,
+            max_control_frames_per_second: cfg.getattr(py, "max_control_frames_per_second")?.extract(py)?,
         },
         None => HTTP2Config {
             adaptive_window: false,
@@ -95,7 +154,166 @@ pub(crate) fn worker_http2_config_from_py(py: Python, cfg: Option<PyObject>) ->
             max_frame_size: 1024 * 16,
             max_headers_size: 16 * 1024 * 1024,
             max_send_buffer_size: 1024 * 400,
+            max_pending_accept_reset_streams: 20,
+            max_local_error_reset_streams: Some(1024),
+            max_resets_per_second: Some(200),
+            max_control_frames_per_second: Some(100),
+        },
+    };
+    Ok(ret)
+}
+
+fn ip_networks_from_py(py: Python, value: PyObject) -> PyResult<Vec<IPNetwork>> {
+    value
+        .extract::<Option<Vec<String>>>(py)?
+        .unwrap_or_default()
+        .iter()
+        .map(|v| v.parse().map_err(PyValueError::new_err))
+        .collect()
+}
+
+pub(crate) fn worker_connection_config_from_py(py: Python, cfg: Option<PyObject>) -> PyResult<ConnectionConfig> {
+    let ret = match cfg {
+        Some(cfg) => ConnectionConfig {
+            max_per_ip: cfg.getattr(py, "max_per_ip")?.extract(py)?,
+            allow: ip_networks_from_py(py, cfg.getattr(py, "allow")?)?,
+            deny: ip_networks_from_py(py, cfg.getattr(py, "deny")?)?,
+        },
+        None => ConnectionConfig {
+            max_per_ip: None,
+            allow: Vec::new(),
+            deny: Vec::new(),
+        },
+    };
+    Ok(ret)
+}
+
+pub(crate) fn worker_rate_limit_config_from_py(
+    py: Python,
+    cfg: Option<PyObject>,
+    shared_memory: Option<String>,
+    seed: u64,
+) -> PyResult<Option<RateLimitConfig>> {
+    let Some(cfg) = cfg else {
+        return Ok(None);
+    };
+    let key = match cfg.getattr(py, "key")?.extract::<String>(py)?.as_str() {
+        "ip" => RateLimitKey::IP,
+        "path" => RateLimitKey::Path,
+        "header" => {
+            let header = cfg.getattr(py, "header")?.extract::<String>(py)?;
+            RateLimitKey::Header(
+                HeaderName::from_bytes(header.as_bytes())
+                    .map_err(|_| PyValueError::new_err(format!("Invalid rate limit header: {header:?}")))?,
+            )
+        }
+        key => return Err(PyValueError::new_err(format!("Unsupported rate limit key: {key:?}"))),
+    };
+    Ok(Some(RateLimitConfig {
+        rate: cfg.getattr(py, "rate")?.extract(py)?,
+        burst: cfg.getattr(py, "burst")?.extract(py)?,
+        key,
+        paths: cfg
+            .getattr(py, "paths")?
+            .extract::<Option<Vec<String>>>(py)?
+            .unwrap_or_default(),
+        shared_memory,
+        seed,
+    }))
+}
+
+pub(crate) fn worker_load_shedding_config_from_py(
+    py: Python,
+    cfg: Option<PyObject>,
+) -> PyResult<Option<LoadSheddingConfig>> {
+    let Some(cfg) = cfg else {
+        return Ok(None);
+    };
+    Ok(Some(LoadSheddingConfig {
+        queue_timeout: core::time::Duration::from_secs_f64(cfg.getattr(py, "queue_timeout")?.extract(py)?),
+        retry_after: cfg.getattr(py, "retry_after")?.extract(py)?,
+    }))
+}
+
+pub(crate) fn worker_concurrency_config_from_py(
+    py: Python,
+    cfg: Option<PyObject>,
+) -> PyResult<Option<ConcurrencyLimitConfig>> {
+    let Some(cfg) = cfg else {
+        return Ok(None);
+    };
+    Ok(Some(ConcurrencyLimitConfig {
+        max_requests: cfg.getattr(py, "max_requests")?.extract(py)?,
+        queue_size: cfg.getattr(py, "queue_size")?.extract(py)?,
+        queue_timeout: cfg
+            .getattr(py, "queue_timeout")?
+            .extract::<Option<f64>>(py)?
+            .map(core::time::Duration::from_secs_f64),
+    }))
+}
+
+pub(crate) fn worker_adaptive_concurrency_config_from_py(
+    py: Python,
+    cfg: Option<PyObject>,
+) -> PyResult<Option<AdaptiveConcurrencyConfig>> {
+    let Some(cfg) = cfg else {
+        return Ok(None);
+    };
+    Ok(Some(AdaptiveConcurrencyConfig {
+        initial_limit: cfg.getattr(py, "initial_limit")?.extract(py)?,
+        min_limit: cfg.getattr(py, "min_limit")?.extract(py)?,
+        max_limit: cfg.getattr(py, "max_limit")?.extract(py)?,
+        smoothing: cfg.getattr(py, "smoothing")?.extract(py)?,
+    }))
+}
+
+pub(crate) fn worker_websocket_config_from_py(py: Python, cfg: Option<PyObject>) -> PyResult<WebsocketConfig> {
+    let ret = match cfg {
+        Some(cfg) => WebsocketConfig {
+            max_message_size: cfg.getattr(py, "max_message_size")?.extract(py)?,
+            max_frame_size: cfg.getattr(py, "max_frame_size")?.extract(py)?,
+        },
+        None => WebsocketConfig {
+            max_message_size: Some(64 * 1024 * 1024),
+            max_frame_size: Some(16 * 1024 * 1024),
+        },
+    };
+    Ok(ret)
+}
+
+pub(crate) fn worker_tls_config_from_py(py: Python, cfg: Option<PyObject>) -> PyResult<TLSConfig> {
+    let ret = match cfg {
+        Some(cfg) => TLSConfig {
+            min_version: cfg.getattr(py, "min_version")?.extract(py)?,
+            ciphers: cfg.getattr(py, "ciphers")?.extract(py)?,
+            session_tickets: cfg.getattr(py, "session_tickets")?.extract(py)?,
+            session_cache_size: cfg.getattr(py, "session_cache_size")?.extract(py)?,
+            keylog: cfg.getattr(py, "keylog")?.extract(py)?,
+            ocsp_response: cfg.getattr(py, "ocsp_response")?.extract(py)?,
+        },
+        None => TLSConfig {
+            min_version: "1.3".into(),
+            ciphers: None,
+            session_tickets: false,
+            session_cache_size: 256,
+            keylog: false,
+            ocsp_response: None,
         },
     };
     Ok(ret)
 }
+
+pub(crate) fn acme_config_from_py(py: Python, cfg: PyObject) -> PyResult<ACMEConfig> {
+    Ok(ACMEConfig {
+        domains: cfg.getattr(py, "domains")?.extract(py)?,
+        storage: cfg.getattr(py, "storage")?.extract(py)?,
+        server: cfg.getattr(py, "server")?.extract(py)?,
+        contact: cfg
+            .getattr(py, "contact")?
+            .extract::<Option<Vec<String>>>(py)?
+            .unwrap_or_default(),
+        challenge: cfg.getattr(py, "challenge")?.extract(py)?,
+        http_port: cfg.getattr(py, "http_port")?.extract(py)?,
+        renew_before: cfg.getattr(py, "renew_before")?.extract(py)?,
+    })
+}
@@ -1,15 +1,47 @@
 use http_body_util::BodyExt;
 use hyper::{
-    body::Bytes,
-    header::{HeaderValue, SERVER as HK_SERVER},
+    body::{Body, Bytes, Frame, SizeHint},
+    header::{HeaderMap, HeaderValue, CONNECTION, CONTENT_LENGTH, HOST, SERVER as HK_SERVER, TRANSFER_ENCODING},
     Response,
 };
+use pin_project::pin_project;
+use std::{
+    future::Future,
+    io,
+    net::SocketAddr,
+    pin::Pin,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Arc, Mutex,
+    },
+    task::{ready, Context, Poll},
+    time::{Duration, Instant},
+};
+use tokio::{
+    io::{AsyncRead, AsyncWrite, ReadBuf},
+    sync::{mpsc, Notify, OwnedSemaphorePermit, Semaphore},
+};
+use tokio_util::sync::CancellationToken;
 
 pub(crate) type HTTPRequest = hyper::Request<hyper::body::Incoming>;
 pub(crate) type HTTPResponseBody = http_body_util::combinators::BoxBody<Bytes, anyhow::Error>;
 pub(crate) type HTTPResponse = hyper::Response<HTTPResponseBody>;
 
 pub(crate) const HV_SERVER: HeaderValue = HeaderValue::from_static("granian");
+pub(crate) const HEAD_EARLY_HINTS: &str = "103 Early Hints";
+
+pub(crate) fn response_400() -> HTTPResponse {
+    let mut builder = Response::builder().status(400);
+    let headers = builder.headers_mut().unwrap();
+    headers.insert(HK_SERVER, HV_SERVER);
+    builder
+        .body(
+            http_body_util::Full::new("Bad request".into())
+                .map_err(|e| match e {})
+                .boxed(),
+        )
+        .unwrap()
+}
 
 pub(crate) fn response_404() -> HTTPResponse {
     let mut builder = Response::builder().status(404);
@@ -24,6 +56,32 @@ pub(crate) fn response_404() -> HTTPResponse {
         .unwrap()
 }
 
+pub(crate) fn response_429() -> HTTPResponse {
+    let mut builder = Response::builder().status(429);
+    let headers = builder.headers_mut().unwrap();
+    headers.insert(HK_SERVER, HV_SERVER);
+    builder
+        .body(
+            http_body_util::Full::new("Too many requests".into())
+                .map_err(|e| match e {})
+                .boxed(),
+        )
+        .unwrap()
+}
+
+pub(crate) fn response_503() -> HTTPResponse {
+    let mut builder = Response::builder().status(503);
+    let headers = builder.headers_mut().unwrap();
+    headers.insert(HK_SERVER, HV_SERVER);
+    builder
+        .body(
+            http_body_util::Full::new("Service unavailable".into())
+                .map_err(|e| match e {})
+                .boxed(),
+        )
+        .unwrap()
+}
+
 pub(crate) fn response_500() -> HTTPResponse {
     let mut builder = Response::builder().status(500);
     let headers = builder.headers_mut().unwrap();
@@ -41,3 +99,778 @@ pub(crate) fn response_500() -> HTTPResponse {
 pub(crate) fn empty_body() -> HTTPResponseBody {
     http_body_util::Empty::<Bytes>::new().map_err(|e| match e {}).boxed()
 }
+
+fn strict_validation_error(request: &HTTPRequest) -> Option<&'static str> {
+    let headers = request.headers();
+    // NOTE: hyper rejects different `Content-Length` values and ignores the ones following a
+    //       `Transfer-Encoding`, thus here we only catch the ones preceding it.
+    if headers.contains_key(CONTENT_LENGTH) && headers.contains_key(TRANSFER_ENCODING) {
+        return Some("both Content-Length and Transfer-Encoding headers specified");
+    }
+    // NOTE: obsolete line folding never gets here, as the parser already rejects it.
+    for value in headers.values() {
+        if value.as_bytes().iter().any(|&c| (c < 0x20 && c != b'\t') || c >= 0x7f) {
+            return Some("invalid character in header value");
+        }
+    }
+    let mut hosts = headers.get_all(HOST).iter();
+    let host = hosts.next();
+    if hosts.next().is_some() {
+        return Some("multiple Host headers specified");
+    }
+    if let (Some(authority), Some(host)) = (request.uri().authority(), host) {
+        if !host.as_bytes().eq_ignore_ascii_case(authority.as_str().as_bytes()) {
+            return Some("request target authority doesn't match the Host header");
+        }
+    }
+    if percent_encoding::percent_decode_str(request.uri().path())
+        .decode_utf8()
+        .is_err()
+    {
+        return Some("request path is not valid UTF-8");
+    }
+    None
+}
+
+/// Checks requests for ambiguous or malformed input when running in strict mode,
+/// returning a `400 Bad Request` response for the ones to reject.
+pub(crate) fn validate_strict(request: &HTTPRequest, remote_addr: SocketAddr) -> Option<HTTPResponse> {
+    let reason = strict_validation_error(request)?;
+    log::info!("Rejected request from {remote_addr}: {reason}");
+    Some(response_400())
+}
+
+/// Response body wrapper cancelling the given token when dropped before reaching
+/// the end of the stream, which happens when the client goes away.
+pub(crate) struct DisconnectAwareBody {
+    inner: HTTPResponseBody,
+    disconnect: Option<CancellationToken>,
+}
+
+impl DisconnectAwareBody {
+    pub fn boxed(inner: HTTPResponseBody, disconnect: CancellationToken) -> HTTPResponseBody {
+        Self {
+            inner,
+            disconnect: Some(disconnect),
+        }
+        .boxed()
+    }
+}
+
+impl Body for DisconnectAwareBody {
+    type Data = Bytes;
+    type Error = anyhow::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        let ret = Pin::new(&mut self.inner).poll_frame(cx);
+        match &ret {
+            Poll::Ready(None) => {
+                self.disconnect.take();
+            }
+            // trailers always terminate the body, and the connection might drop it right away
+            Poll::Ready(Some(Ok(frame))) if frame.is_trailers() => {
+                self.disconnect.take();
+            }
+            _ => {}
+        }
+        ret
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+impl Drop for DisconnectAwareBody {
+    fn drop(&mut self) {
+        if let Some(disconnect) = self.disconnect.take() {
+            disconnect.cancel();
+        }
+    }
+}
+
+struct InterimState {
+    buffer: Mutex<Vec<u8>>,
+    pending: AtomicBool,
+    flushed: AtomicBool,
+    writable: AtomicBool,
+    waker: futures::task::AtomicWaker,
+}
+
+impl Default for InterimState {
+    fn default() -> Self {
+        Self {
+            buffer: Mutex::default(),
+            pending: AtomicBool::new(false),
+            flushed: AtomicBool::new(true),
+            writable: AtomicBool::new(false),
+            waker: futures::task::AtomicWaker::new(),
+        }
+    }
+}
+
+/// Handle to send HTTP/1.1 informational (1xx) responses on a connection,
+/// ahead of the final response of the current request.
+#[derive(Clone, Default)]
+pub(crate) struct InterimSender {
+    state: Arc<InterimState>,
+}
+
+impl InterimSender {
+    /// Marks the start of a new request on the connection: unless everything written before
+    /// was flushed, informational responses are held back until hyper flushes the connection.
+    pub fn request_started(&self) {
+        self.state
+            .writable
+            .store(self.state.flushed.load(Ordering::Acquire), Ordering::Release);
+    }
+
+    /// Marks the final response of the current request as handed over to hyper:
+    /// the informational responses still held back can't be sent anymore.
+    pub fn response_started(&self) {
+        if !self.state.writable.load(Ordering::Acquire) {
+            let mut buffer = self.state.buffer.lock().unwrap();
+            if !buffer.is_empty() {
+                log::debug!("Dropping informational responses queued behind a pending response");
+                buffer.clear();
+            }
+            self.state.pending.store(false, Ordering::Release);
+        }
+    }
+
+    pub fn send(&self, head: &str, headers: &HeaderMap) {
+        let mut buffer = self.state.buffer.lock().unwrap();
+        buffer.extend_from_slice(b"HTTP/1.1 ");
+        buffer.extend_from_slice(head.as_bytes());
+        buffer.extend_from_slice(b"\r\n");
+        for (key, value) in headers {
+            buffer.extend_from_slice(key.as_str().as_bytes());
+            buffer.extend_from_slice(b": ");
+            buffer.extend_from_slice(value.as_bytes());
+            buffer.extend_from_slice(b"\r\n");
+        }
+        buffer.extend_from_slice(b"\r\n");
+        self.state.pending.store(true, Ordering::Release);
+        self.state.waker.wake();
+    }
+}
+
+/// Connection IO wrapper writing the queued informational responses.
+/// hyper keeps polling the read side of the connection while waiting for the response,
+/// which gives us the chance to flush those before the final response head.
+/// As the response to the previous request might still be pending, we only write once hyper
+/// flushes the IO, which happens when its write buffer is empty.
+/// NOTE: with pipeline flush enabled hyper skips flushes, so we can't use this.
+#[pin_project]
+pub(crate) struct InterimIO<T> {
+    #[pin]
+    inner: T,
+    interim: InterimSender,
+}
+
+impl<T> InterimIO<T> {
+    pub fn new(inner: T, interim: InterimSender) -> Self {
+        Self { inner, interim }
+    }
+}
+
+impl<T: AsyncWrite> InterimIO<T> {
+    fn poll_interim(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        let mut this = self.project();
+        if !this.interim.state.pending.load(Ordering::Acquire) || !this.interim.state.writable.load(Ordering::Acquire) {
+            return Poll::Ready(Ok(()));
+        }
+        let mut buffer = this.interim.state.buffer.lock().unwrap();
+        while !buffer.is_empty() {
+            let written = ready!(this.inner.as_mut().poll_write(cx, &buffer))?;
+            if written == 0 {
+                return Poll::Ready(Err(io::ErrorKind::WriteZero.into()));
+            }
+            buffer.drain(..written);
+        }
+        this.interim.state.pending.store(false, Ordering::Release);
+        this.inner.poll_flush(cx)
+    }
+}
+
+impl<T: AsyncRead + AsyncWrite> AsyncRead for InterimIO<T> {
+    fn poll_read(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        self.interim.state.waker.register(cx.waker());
+        if let Poll::Ready(Err(err)) = self.as_mut().poll_interim(cx) {
+            return Poll::Ready(Err(err));
+        }
+        self.project().inner.poll_read(cx, buf)
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for InterimIO<T> {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        ready!(self.as_mut().poll_interim(cx))?;
+        self.interim.state.flushed.store(false, Ordering::Release);
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        ready!(self.as_mut().poll_interim(cx))?;
+        self.interim.state.flushed.store(false, Ordering::Release);
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.interim.state.writable.store(true, Ordering::Release);
+        ready!(self.as_mut().poll_interim(cx))?;
+        let this = self.project();
+        ready!(this.inner.poll_flush(cx))?;
+        this.interim.state.flushed.store(true, Ordering::Release);
+        Poll::Ready(Ok(()))
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+}
+
+enum HTTP1ConnPhase {
+    Idle(Instant),
+    Head(Instant),
+    Busy(usize),
+    Off,
+}
+
+/// Tracks the state of HTTP/1 connections, to enforce the idle keep-alive and header read timeouts
+/// and the maximum number of requests served by every connection.
+pub(crate) struct HTTP1ConnTracker {
+    phase: Mutex<HTTP1ConnPhase>,
+    changed: Notify,
+    requests: AtomicUsize,
+    header_read_timeout: Option<Duration>,
+    keep_alive_timeout: Option<Duration>,
+    max_requests: Option<usize>,
+}
+
+impl HTTP1ConnTracker {
+    pub fn new(
+        header_read_timeout: Option<Duration>,
+        keep_alive_timeout: Option<Duration>,
+        max_requests: Option<usize>,
+    ) -> Arc<Self> {
+        Arc::new(Self {
+            phase: Mutex::new(HTTP1ConnPhase::Idle(Instant::now())),
+            changed: Notify::new(),
+            requests: AtomicUsize::new(0),
+            header_read_timeout,
+            keep_alive_timeout,
+            max_requests,
+        })
+    }
+
+    fn data_received(&self, data: &[u8]) {
+        let mut phase = self.phase.lock().unwrap();
+        if let HTTP1ConnPhase::Idle(_) = *phase {
+            // NOTE: HTTP/2 connections on auto mode are not tracked
+            let len = data.len().min(H2_PREFACE.len());
+            *phase = match data[..len] == H2_PREFACE[..len] {
+                true => HTTP1ConnPhase::Off,
+                false => HTTP1ConnPhase::Head(Instant::now()),
+            };
+            self.changed.notify_one();
+        }
+    }
+
+    fn request_started(self: &Arc<Self>) -> HTTP1RequestGuard {
+        let mut phase = self.phase.lock().unwrap();
+        *phase = match *phase {
+            HTTP1ConnPhase::Busy(inflight) => HTTP1ConnPhase::Busy(inflight + 1),
+            HTTP1ConnPhase::Off => HTTP1ConnPhase::Off,
+            _ => HTTP1ConnPhase::Busy(1),
+        };
+        self.changed.notify_one();
+        let served = self.requests.fetch_add(1, Ordering::Relaxed) + 1;
+        HTTP1RequestGuard {
+            tracker: self.clone(),
+            close: self.max_requests.is_some_and(|max| served >= max),
+        }
+    }
+
+    fn request_done(&self) {
+        let mut phase = self.phase.lock().unwrap();
+        *phase = match *phase {
+            HTTP1ConnPhase::Busy(1) => HTTP1ConnPhase::Idle(Instant::now()),
+            HTTP1ConnPhase::Busy(inflight) => HTTP1ConnPhase::Busy(inflight - 1),
+            HTTP1ConnPhase::Idle(_) | HTTP1ConnPhase::Head(_) | HTTP1ConnPhase::Off => return,
+        };
+        self.changed.notify_one();
+    }
+
+    fn deadline(&self) -> Option<(Instant, bool)> {
+        match *self.phase.lock().unwrap() {
+            HTTP1ConnPhase::Idle(since) => self.keep_alive_timeout.map(|timeout| (since + timeout, true)),
+            HTTP1ConnPhase::Head(since) => self.header_read_timeout.map(|timeout| (since + timeout, false)),
+            HTTP1ConnPhase::Busy(_) | HTTP1ConnPhase::Off => None,
+        }
+    }
+
+    /// Waits for the connection to exceed one of its timeouts,
+    /// returning `true` if the connection was idle, `false` if it was reading a request head.
+    pub async fn expired(&self) -> bool {
+        loop {
+            let changed = self.changed.notified();
+            match self.deadline() {
+                Some((deadline, idle)) => tokio::select! {
+                    () = tokio::time::sleep_until(deadline.into()) => return idle,
+                    () = changed => {}
+                },
+                None => changed.await,
+            }
+        }
+    }
+}
+
+struct HTTP1RequestGuard {
+    tracker: Arc<HTTP1ConnTracker>,
+    close: bool,
+}
+
+impl Drop for HTTP1RequestGuard {
+    fn drop(&mut self) {
+        self.tracker.request_done();
+    }
+}
+
+/// Connection IO wrapper notifying the tracker about incoming data.
+#[pin_project]
+pub(crate) struct HTTP1TrackedIO<T> {
+    #[pin]
+    inner: T,
+    tracker: Option<Arc<HTTP1ConnTracker>>,
+}
+
+impl<T> HTTP1TrackedIO<T> {
+    pub fn new(inner: T, tracker: Option<Arc<HTTP1ConnTracker>>) -> Self {
+        Self { inner, tracker }
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for HTTP1TrackedIO<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let offset = buf.filled().len();
+        ready!(this.inner.poll_read(cx, buf))?;
+        if let Some(tracker) = this.tracker {
+            if buf.filled().len() > offset {
+                tracker.data_received(&buf.filled()[offset..]);
+            }
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for HTTP1TrackedIO<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+}
+
+/// Service wrapper tracking the requests served on HTTP/1 connections.
+/// Once the maximum number of requests is reached, responses get a `Connection: close` header.
+pub(crate) struct HTTP1TrackedService<S> {
+    inner: S,
+    tracker: Option<Arc<HTTP1ConnTracker>>,
+}
+
+impl<S> HTTP1TrackedService<S> {
+    pub fn new(inner: S, tracker: Option<Arc<HTTP1ConnTracker>>) -> Self {
+        Self { inner, tracker }
+    }
+}
+
+impl<S> hyper::service::Service<HTTPRequest> for HTTP1TrackedService<S>
+where
+    S: hyper::service::Service<HTTPRequest, Response = HTTPResponse>,
+{
+    type Response = HTTPResponse;
+    type Error = S::Error;
+    type Future = HTTP1TrackedFuture<S::Future>;
+
+    fn call(&self, request: HTTPRequest) -> Self::Future {
+        let guard = match &self.tracker {
+            Some(tracker) if request.version() < hyper::Version::HTTP_2 => Some(tracker.request_started()),
+            _ => None,
+        };
+        HTTP1TrackedFuture {
+            inner: self.inner.call(request),
+            guard,
+        }
+    }
+}
+
+#[pin_project]
+pub(crate) struct HTTP1TrackedFuture<F> {
+    #[pin]
+    inner: F,
+    guard: Option<HTTP1RequestGuard>,
+}
+
+impl<F, E> Future for HTTP1TrackedFuture<F>
+where
+    F: Future<Output = Result<HTTPResponse, E>>,
+{
+    type Output = Result<HTTPResponse, E>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        let mut res = ready!(this.inner.poll(cx))?;
+        let Some(guard) = this.guard.take() else {
+            return Poll::Ready(Ok(res));
+        };
+        if guard.close {
+            res.headers_mut().insert(CONNECTION, HeaderValue::from_static("close"));
+        }
+        // the request is completed once the response body is fully sent
+        Poll::Ready(Ok(hold_until_sent(res, guard)))
+    }
+}
+
+const H2_PREFACE: &[u8] = b"PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n";
+const H2_FRAME_HEADERS: u8 = 0x1;
+const H2_FRAME_RST_STREAM: u8 = 0x3;
+const H2_FRAME_SETTINGS: u8 = 0x4;
+const H2_FRAME_PING: u8 = 0x6;
+const H2_FRAME_CONTINUATION: u8 = 0x9;
+const H2_FLAG_ACK: u8 = 0x1;
+const H2_FLAG_END_HEADERS: u8 = 0x4;
+
+#[derive(Clone, Copy)]
+pub(crate) struct HTTP2AbuseLimits {
+    pub resets_per_second: Option<u32>,
+    pub control_frames_per_second: Option<u32>,
+    pub header_block_size: u32,
+}
+
+enum HTTP2FramePhase {
+    Preface(usize),
+    Frames,
+    Passthrough,
+}
+
+/// Tracks the frames received on an HTTP/2 connection,
+/// counting the ones commonly abused to exhaust server resources.
+struct HTTP2FrameCounter {
+    phase: HTTP2FramePhase,
+    header: [u8; 9],
+    header_len: usize,
+    payload_left: usize,
+    window: Instant,
+    resets: u32,
+    control_frames: u32,
+    header_block: usize,
+}
+
+impl HTTP2FrameCounter {
+    fn new() -> Self {
+        Self {
+            phase: HTTP2FramePhase::Preface(0),
+            header: [0; 9],
+            header_len: 0,
+            payload_left: 0,
+            window: Instant::now(),
+            resets: 0,
+            control_frames: 0,
+            header_block: 0,
+        }
+    }
+
+    fn feed(&mut self, mut data: &[u8], limits: &HTTP2AbuseLimits) -> Option<&'static str> {
+        while !data.is_empty() {
+            match self.phase {
+                HTTP2FramePhase::Passthrough => break,
+                HTTP2FramePhase::Preface(matched) => {
+                    let len = (H2_PREFACE.len() - matched).min(data.len());
+                    if data[..len] != H2_PREFACE[matched..matched + len] {
+                        // not an HTTP/2 connection (eg: HTTP/1 on auto mode)
+                        self.phase = HTTP2FramePhase::Passthrough;
+                        break;
+                    }
+                    self.phase = match matched + len {
+                        v if v == H2_PREFACE.len() => HTTP2FramePhase::Frames,
+                        v => HTTP2FramePhase::Preface(v),
+                    };
+                    data = &data[len..];
+                }
+                HTTP2FramePhase::Frames if self.payload_left > 0 => {
+                    let len = self.payload_left.min(data.len());
+                    self.payload_left -= len;
+                    data = &data[len..];
+                }
+                HTTP2FramePhase::Frames => {
+                    let len = (self.header.len() - self.header_len).min(data.len());
+                    self.header[self.header_len..self.header_len + len].copy_from_slice(&data[..len]);
+                    self.header_len += len;
+                    data = &data[len..];
+                    if self.header_len == self.header.len() {
+                        self.header_len = 0;
+                        self.payload_left =
+                            u32::from_be_bytes([0, self.header[0], self.header[1], self.header[2]]) as usize;
+                        if let Some(reason) = self.frame(self.header[3], self.header[4], limits) {
+                            self.phase = HTTP2FramePhase::Passthrough;
+                            return Some(reason);
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    fn frame(&mut self, kind: u8, flags: u8, limits: &HTTP2AbuseLimits) -> Option<&'static str> {
+        if self.window.elapsed() >= Duration::from_secs(1) {
+            self.window = Instant::now();
+            self.resets = 0;
+            self.control_frames = 0;
+        }
+        match kind {
+            H2_FRAME_RST_STREAM => {
+                self.resets += 1;
+                if limits.resets_per_second.is_some_and(|max| self.resets > max) {
+                    return Some("too many stream resets");
+                }
+            }
+            H2_FRAME_SETTINGS | H2_FRAME_PING if flags & H2_FLAG_ACK == 0 => {
+                self.control_frames += 1;
+                if limits
+                    .control_frames_per_second
+                    .is_some_and(|max| self.control_frames > max)
+                {
+                    return Some("too many SETTINGS/PING frames");
+                }
+            }
+            H2_FRAME_HEADERS | H2_FRAME_CONTINUATION => {
+                if kind == H2_FRAME_HEADERS {
+                    self.header_block = 0;
+                }
+                self.header_block += self.payload_left;
+                if self.header_block > limits.header_block_size as usize {
+                    return Some("header block too large");
+                }
+                if flags & H2_FLAG_END_HEADERS != 0 {
+                    self.header_block = 0;
+                }
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+/// Connection IO wrapper inspecting the frames sent by HTTP/2 clients.
+/// Misbehaving clients (rapid resets, SETTINGS/PING floods, oversized header blocks)
+/// trigger the `abuse` token, so the connection can be closed with a GOAWAY.
+#[pin_project]
+pub(crate) struct HTTP2GuardIO<T> {
+    #[pin]
+    inner: T,
+    counter: HTTP2FrameCounter,
+    limits: HTTP2AbuseLimits,
+    remote_addr: SocketAddr,
+    abuse: CancellationToken,
+}
+
+impl<T> HTTP2GuardIO<T> {
+    pub fn new(inner: T, limits: HTTP2AbuseLimits, remote_addr: SocketAddr, abuse: CancellationToken) -> Self {
+        Self {
+            inner,
+            counter: HTTP2FrameCounter::new(),
+            limits,
+            remote_addr,
+            abuse,
+        }
+    }
+}
+
+impl<T: AsyncRead> AsyncRead for HTTP2GuardIO<T> {
+    fn poll_read(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &mut ReadBuf<'_>) -> Poll<io::Result<()>> {
+        let this = self.project();
+        let offset = buf.filled().len();
+        ready!(this.inner.poll_read(cx, buf))?;
+        if let Some(reason) = this.counter.feed(&buf.filled()[offset..], this.limits) {
+            log::warn!("Closing HTTP/2 connection from {}: {}", this.remote_addr, reason);
+            this.abuse.cancel();
+        }
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl<T: AsyncWrite> AsyncWrite for HTTP2GuardIO<T> {
+    fn poll_write(self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write(cx, buf)
+    }
+
+    fn poll_write_vectored(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+        bufs: &[io::IoSlice<'_>],
+    ) -> Poll<io::Result<usize>> {
+        self.project().inner.poll_write_vectored(cx, bufs)
+    }
+
+    fn is_write_vectored(&self) -> bool {
+        self.inner.is_write_vectored()
+    }
+
+    fn poll_flush(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_flush(cx)
+    }
+
+    fn poll_shutdown(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<io::Result<()>> {
+        self.project().inner.poll_shutdown(cx)
+    }
+}
+
+/// Response body wrapper keeping a value alive until the body gets dropped,
+/// which happens once the body is fully sent or the client goes away.
+#[pin_project]
+struct HeldBody<T> {
+    #[pin]
+    inner: HTTPResponseBody,
+    _held: T,
+}
+
+impl<T> Body for HeldBody<T> {
+    type Data = Bytes;
+    type Error = anyhow::Error;
+
+    fn poll_frame(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        self.project().inner.poll_frame(cx)
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.inner.is_end_stream()
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        self.inner.size_hint()
+    }
+}
+
+pub(crate) fn hold_until_sent<T: Send + Sync + 'static>(res: HTTPResponse, value: T) -> HTTPResponse {
+    res.map(|inner| HeldBody { inner, _held: value }.boxed())
+}
+
+type HTTPBodyChunk = (Frame<Bytes>, OwnedSemaphorePermit);
+
+/// Sending half of a streamed response body.
+/// The amount of buffered data not yet consumed by the connection is bounded
+/// to the configured size, so `send` waits for the client to catch up.
+#[derive(Clone)]
+pub(crate) struct HTTPBodySender {
+    tx: mpsc::UnboundedSender<HTTPBodyChunk>,
+    capacity: Arc<Semaphore>,
+    max_permits: u32,
+}
+
+pub(crate) struct HTTPBodyReceiver {
+    rx: mpsc::UnboundedReceiver<HTTPBodyChunk>,
+    done: bool,
+}
+
+pub(crate) fn body_channel(buffer_size: usize) -> (HTTPBodySender, HTTPBodyReceiver) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let max_permits = u32::try_from(buffer_size).unwrap_or(u32::MAX);
+    (
+        HTTPBodySender {
+            tx,
+            capacity: Arc::new(Semaphore::new(max_permits as usize)),
+            max_permits,
+        },
+        HTTPBodyReceiver { rx, done: false },
+    )
+}
+
+impl HTTPBodySender {
+    async fn send_frame(&self, frame: Frame<Bytes>, size: usize) -> anyhow::Result<()> {
+        // NOTE: chunks bigger than the whole buffer are sent once the buffer is empty.
+        let permits = u32::try_from(size).unwrap_or(u32::MAX).min(self.max_permits);
+        let permit = tokio::select! {
+            biased;
+            () = self.tx.closed() => return Err(anyhow::anyhow!("transport closed")),
+            permit = self.capacity.clone().acquire_many_owned(permits) => permit?,
+        };
+        self.tx
+            .send((frame, permit))
+            .map_err(|_| anyhow::anyhow!("transport closed"))
+    }
+
+    pub async fn send(&self, data: Bytes) -> anyhow::Result<()> {
+        let size = data.len();
+        self.send_frame(Frame::data(data), size).await
+    }
+
+    pub async fn send_trailers(&self, trailers: HeaderMap) -> anyhow::Result<()> {
+        self.send_frame(Frame::trailers(trailers), 0).await
+    }
+}
+
+impl Body for HTTPBodyReceiver {
+    type Data = Bytes;
+    type Error = anyhow::Error;
+
+    fn poll_frame(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Frame<Self::Data>, Self::Error>>> {
+        if self.done {
+            return Poll::Ready(None);
+        }
+        // the permit gets released as soon as the connection pulls the chunk
+        match self.rx.poll_recv(cx) {
+            Poll::Ready(Some((frame, _permit))) => {
+                if frame.is_trailers() {
+                    self.done = true;
+                    self.rx.close();
+                }
+                Poll::Ready(Some(Ok(frame)))
+            }
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.done
+    }
+}
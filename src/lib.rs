@@ -1,6 +1,7 @@
 use pyo3::prelude::*;
 use std::sync::OnceLock;
 
+mod acme;
 mod asgi;
 mod asyncio;
 mod blocking;
@@ -8,6 +9,7 @@ mod callbacks;
 mod conversion;
 mod http;
 mod io;
+mod limits;
 mod rsgi;
 mod runtime;
 mod tcp;
@@ -30,9 +32,11 @@ pub fn get_granian_version() -> &'static str {
 fn _granian(py: Python, module: &Bound<PyModule>) -> PyResult<()> {
     module.add("__version__", get_granian_version())?;
     module.add_class::<callbacks::CallbackScheduler>()?;
+    acme::init_pymodule(module)?;
     asgi::init_pymodule(module)?;
     rsgi::init_pymodule(py, module)?;
     tcp::init_pymodule(module)?;
+    tls::init_pymodule(module)?;
     workers::init_pymodule(module)?;
     Ok(())
 }
@@ -0,0 +1,800 @@
+use anyhow::Result;
+use hyper::header::{HeaderName, HeaderValue, CONNECTION, RETRY_AFTER};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::{DefaultHasher, Hash, Hasher},
+    io,
+    net::{IpAddr, SocketAddr},
+    pin::Pin,
+    str::FromStr,
+    sync::{
+        atomic::{self, AtomicBool, AtomicU64, AtomicUsize},
+        Arc, Mutex,
+    },
+    task::{Context, Poll},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+use tls_listener::AsyncAccept;
+use tokio::{
+    net::{TcpListener, TcpStream},
+    sync::{OwnedSemaphorePermit, Semaphore},
+};
+
+use crate::{
+    http::{response_429, response_503, HTTPRequest, HTTPResponse},
+    workers::{
+        AdaptiveConcurrencyConfig, ConcurrencyLimitConfig, ConnectionConfig, LoadSheddingConfig, RateLimitConfig,
+    },
+};
+
+const REJECTIONS_LOG_INTERVAL: Duration = Duration::from_secs(10);
+const METRICS_LOG_TARGET: &str = "_granian::metrics";
+const RATE_LIMIT_SLOTS: usize = 65536;
+// NOTE: slots pack the theoretical arrival time in microseconds (which fits 52 bits till year 2112)
+//       with a fingerprint of the bucket key made of the hash top bits.
+const RATE_LIMIT_TAT_BITS: u32 = 52;
+const RATE_LIMIT_TAT_MASK: u64 = (1 << RATE_LIMIT_TAT_BITS) - 1;
+const HK_RATELIMIT_LIMIT: HeaderName = HeaderName::from_static("ratelimit-limit");
+const HK_RATELIMIT_REMAINING: HeaderName = HeaderName::from_static("ratelimit-remaining");
+const HK_RATELIMIT_RESET: HeaderName = HeaderName::from_static("ratelimit-reset");
+const ADAPTIVE_SHORT_WINDOW: f64 = 10.0;
+const ADAPTIVE_LONG_WINDOW: f64 = 600.0;
+const ADAPTIVE_TOLERANCE: f64 = 1.5;
+
+/// An IP network in CIDR notation; plain addresses are treated as single host networks.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct IPNetwork {
+    addr: IpAddr,
+    prefix: u8,
+}
+
+impl IPNetwork {
+    fn contains(&self, addr: IpAddr) -> bool {
+        match (self.addr, addr) {
+            (IpAddr::V4(net), IpAddr::V4(addr)) => {
+                let mask = u32::MAX.checked_shl(32 - u32::from(self.prefix)).unwrap_or(0);
+                u32::from(net) & mask == u32::from(addr) & mask
+            }
+            (IpAddr::V6(net), IpAddr::V6(addr)) => {
+                let mask = u128::MAX.checked_shl(128 - u32::from(self.prefix)).unwrap_or(0);
+                u128::from(net) & mask == u128::from(addr) & mask
+            }
+            _ => false,
+        }
+    }
+}
+
+impl FromStr for IPNetwork {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let invalid = || format!("Invalid IP network: {value:?}");
+        let (addr, prefix) = match value.split_once('/') {
+            Some((addr, prefix)) => (addr, Some(prefix)),
+            None => (value, None),
+        };
+        let addr: IpAddr = addr.parse().map_err(|_| invalid())?;
+        let max_prefix = if addr.is_ipv4() { 32 } else { 128 };
+        let prefix = match prefix {
+            Some(prefix) => prefix.parse().ok().filter(|v| *v <= max_prefix).ok_or_else(invalid)?,
+            None => max_prefix,
+        };
+        Ok(Self {
+            addr: addr.to_canonical(),
+            prefix,
+        })
+    }
+}
+
+enum Rejection {
+    Denied,
+    OverLimit,
+}
+
+#[derive(Default)]
+struct RejectionsLog {
+    last: Option<Instant>,
+    denied: u64,
+    over_limit: u64,
+}
+
+/// Per-worker admission of TCP peers, based on allow/deny lists and on the number of
+/// concurrent connections for every source address.
+pub(crate) struct ConnectionFilter {
+    config: ConnectionConfig,
+    active: Mutex<HashMap<IpAddr, usize>>,
+    denied: AtomicU64,
+    over_limit: AtomicU64,
+    log: Mutex<RejectionsLog>,
+}
+
+impl ConnectionFilter {
+    pub fn new(config: ConnectionConfig) -> Option<Arc<Self>> {
+        if config.max_per_ip.is_none() && config.allow.is_empty() && config.deny.is_empty() {
+            return None;
+        }
+        Some(Arc::new(Self {
+            config,
+            active: Mutex::new(HashMap::new()),
+            denied: AtomicU64::new(0),
+            over_limit: AtomicU64::new(0),
+            log: Mutex::new(RejectionsLog::default()),
+        }))
+    }
+
+    fn is_allowed(&self, addr: IpAddr) -> bool {
+        if self.config.deny.iter().any(|net| net.contains(addr)) {
+            return false;
+        }
+        self.config.allow.is_empty() || self.config.allow.iter().any(|net| net.contains(addr))
+    }
+
+    fn admit(self: &Arc<Self>, addr: &SocketAddr) -> Option<ConnectionGuard> {
+        // NOTE: IPv4 peers on dual-stack sockets are reported as IPv4-mapped IPv6 addresses.
+        let ip = addr.ip().to_canonical();
+        if !self.is_allowed(ip) {
+            self.reject(Rejection::Denied, ip);
+            return None;
+        }
+        if let Some(max_per_ip) = self.config.max_per_ip {
+            let mut active = self.active.lock().unwrap();
+            let count = active.entry(ip).or_default();
+            if *count >= max_per_ip {
+                drop(active);
+                self.reject(Rejection::OverLimit, ip);
+                return None;
+            }
+            *count += 1;
+        }
+        Some(ConnectionGuard {
+            filter: self.clone(),
+            ip,
+        })
+    }
+
+    fn release(&self, ip: IpAddr) {
+        if self.config.max_per_ip.is_none() {
+            return;
+        }
+        let mut active = self.active.lock().unwrap();
+        if let Some(count) = active.get_mut(&ip) {
+            *count -= 1;
+            if *count == 0 {
+                active.remove(&ip);
+            }
+        }
+    }
+
+    // NOTE: rejections can come in floods, so we just log a summary at most once per interval.
+    fn reject(&self, reason: Rejection, ip: IpAddr) {
+        match reason {
+            Rejection::Denied => self.denied.fetch_add(1, atomic::Ordering::Relaxed),
+            Rejection::OverLimit => self.over_limit.fetch_add(1, atomic::Ordering::Relaxed),
+        };
+        let Ok(mut log) = self.log.try_lock() else {
+            return;
+        };
+        if log.last.is_some_and(|v| v.elapsed() < REJECTIONS_LOG_INTERVAL) {
+            return;
+        }
+        let denied = self.denied.load(atomic::Ordering::Relaxed);
+        let over_limit = self.over_limit.load(atomic::Ordering::Relaxed);
+        log::warn!(
+            "Rejected {} connections by IP filters and {} over the per-IP limit (latest from {}), {} and {} in total",
+            denied - log.denied,
+            over_limit - log.over_limit,
+            ip,
+            denied,
+            over_limit
+        );
+        *log = RejectionsLog {
+            last: Some(Instant::now()),
+            denied,
+            over_limit,
+        };
+    }
+}
+
+/// Tracks an admitted connection, releasing its per-IP slot when dropped.
+pub(crate) struct ConnectionGuard {
+    filter: Arc<ConnectionFilter>,
+    ip: IpAddr,
+}
+
+impl Drop for ConnectionGuard {
+    fn drop(&mut self) {
+        self.filter.release(self.ip);
+    }
+}
+
+impl fmt::Debug for ConnectionGuard {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ConnectionGuard")
+            .field("ip", &self.ip)
+            .finish_non_exhaustive()
+    }
+}
+
+/// A TCP listener dropping connections from peers not admitted by the filter,
+/// right after `accept()` and thus before any TLS or HTTP processing.
+pub(crate) struct FilteredListener {
+    inner: TcpListener,
+    filter: Option<Arc<ConnectionFilter>>,
+}
+
+impl FilteredListener {
+    pub fn new(inner: TcpListener, filter: Option<Arc<ConnectionFilter>>) -> Self {
+        Self { inner, filter }
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.inner.local_addr()
+    }
+
+    fn poll_accept_filtered(
+        &self,
+        cx: &mut Context<'_>,
+    ) -> Poll<io::Result<(TcpStream, (SocketAddr, Option<ConnectionGuard>))>> {
+        loop {
+            let (stream, addr) = match self.inner.poll_accept(cx) {
+                Poll::Ready(Ok(conn)) => conn,
+                Poll::Ready(Err(err)) => return Poll::Ready(Err(err)),
+                Poll::Pending => return Poll::Pending,
+            };
+            let Some(filter) = &self.filter else {
+                return Poll::Ready(Ok((stream, (addr, None))));
+            };
+            if let Some(guard) = filter.admit(&addr) {
+                return Poll::Ready(Ok((stream, (addr, Some(guard)))));
+            }
+        }
+    }
+
+    pub async fn accept(&self) -> io::Result<(TcpStream, (SocketAddr, Option<ConnectionGuard>))> {
+        std::future::poll_fn(|cx| self.poll_accept_filtered(cx)).await
+    }
+}
+
+impl AsyncAccept for FilteredListener {
+    type Connection = TcpStream;
+    type Address = (SocketAddr, Option<ConnectionGuard>);
+    type Error = io::Error;
+
+    fn poll_accept(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Result<(Self::Connection, Self::Address), Self::Error>> {
+        self.poll_accept_filtered(cx)
+    }
+}
+
+/// Counts rejected requests, logging a summary at most once per interval.
+struct RejectionsCounter {
+    total: AtomicU64,
+    log: Mutex<(Option<Instant>, u64)>,
+}
+
+impl RejectionsCounter {
+    fn new() -> Self {
+        Self {
+            total: AtomicU64::new(0),
+            log: Mutex::new((None, 0)),
+        }
+    }
+
+    fn incr(&self, summary: impl FnOnce(u64, u64)) {
+        let total = self.total.fetch_add(1, atomic::Ordering::Relaxed) + 1;
+        let Ok(mut log) = self.log.try_lock() else {
+            return;
+        };
+        if log.0.is_some_and(|v| v.elapsed() < REJECTIONS_LOG_INTERVAL) {
+            return;
+        }
+        summary(total - log.1, total);
+        *log = (Some(Instant::now()), total);
+    }
+}
+
+#[derive(Clone)]
+pub(crate) enum RateLimitKey {
+    IP,
+    Header(HeaderName),
+    Path,
+}
+
+// NOTE: buckets live in a fixed size table of atomics, so that memory stays bounded regardless
+//       of the number of clients, and the very same table can be placed in shared memory.
+//       Keys hashing to the same slot don't share their bucket: the slot gets reset
+//       every time it's claimed by a different key.
+enum RateLimitSlots {
+    Local(Box<[AtomicU64]>),
+    #[cfg(unix)]
+    Shared(SharedSlots),
+}
+
+impl RateLimitSlots {
+    fn get(&self) -> &[AtomicU64] {
+        match self {
+            Self::Local(slots) => slots,
+            #[cfg(unix)]
+            Self::Shared(slots) => slots.get(),
+        }
+    }
+}
+
+#[cfg(unix)]
+struct SharedSlots {
+    ptr: std::ptr::NonNull<libc::c_void>,
+    size: usize,
+}
+
+// SAFETY: the mapped memory is only ever accessed through atomics.
+#[cfg(unix)]
+unsafe impl Send for SharedSlots {}
+#[cfg(unix)]
+unsafe impl Sync for SharedSlots {}
+
+#[cfg(unix)]
+impl SharedSlots {
+    fn open(name: &str) -> io::Result<Self> {
+        use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+
+        let name = std::ffi::CString::new(format!("/{}", name.trim_start_matches('/')))?;
+        let fd = unsafe { libc::shm_open(name.as_ptr(), libc::O_RDWR, 0) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let file = std::fs::File::from(unsafe { OwnedFd::from_raw_fd(fd) });
+        let size = usize::try_from(file.metadata()?.len()).map_err(io::Error::other)?;
+        if size < std::mem::size_of::<AtomicU64>() {
+            return Err(io::Error::other("shared memory segment is too small"));
+        }
+        let ptr = unsafe {
+            libc::mmap(
+                std::ptr::null_mut(),
+                size,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if ptr == libc::MAP_FAILED {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(Self {
+            ptr: std::ptr::NonNull::new(ptr).unwrap(),
+            size,
+        })
+    }
+
+    fn get(&self) -> &[AtomicU64] {
+        // SAFETY: mappings are page aligned, and the segment outlives the slice.
+        unsafe {
+            std::slice::from_raw_parts(
+                self.ptr.as_ptr().cast::<AtomicU64>(),
+                self.size / std::mem::size_of::<AtomicU64>(),
+            )
+        }
+    }
+}
+
+#[cfg(unix)]
+impl Drop for SharedSlots {
+    fn drop(&mut self) {
+        unsafe {
+            libc::munmap(self.ptr.as_ptr(), self.size);
+        }
+    }
+}
+
+fn unix_now_micros() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |v| u64::try_from(v.as_micros()).unwrap_or(u64::MAX))
+}
+
+/// Token bucket request rate limiting.
+///
+/// Every bucket is tracked with the GCRA algorithm: instead of storing the amount of tokens
+/// and the last refill time, the slot only stores the theoretical arrival time of the next
+/// request, which can be updated with a single atomic operation.
+pub(crate) struct RateLimiter {
+    config: RateLimitConfig,
+    interval: u64,
+    tolerance: u64,
+    slots: RateLimitSlots,
+    rejected: RejectionsCounter,
+}
+
+impl RateLimiter {
+    pub fn new(config: RateLimitConfig) -> Result<Arc<Self>> {
+        let interval = ((1_000_000.0 / config.rate) as u64).max(1);
+        let slots = match &config.shared_memory {
+            #[cfg(unix)]
+            Some(name) => RateLimitSlots::Shared(SharedSlots::open(name)?),
+            #[cfg(not(unix))]
+            Some(_) => anyhow::bail!("Shared memory rate limiting is not supported on this platform"),
+            None => RateLimitSlots::Local((0..RATE_LIMIT_SLOTS).map(|_| AtomicU64::new(0)).collect()),
+        };
+        Ok(Arc::new(Self {
+            tolerance: interval * u64::from(config.burst),
+            interval,
+            config,
+            slots,
+            rejected: RejectionsCounter::new(),
+        }))
+    }
+
+    // NOTE: the hash is seeded with a per-server random key, so that clients can't pick keys
+    //       targeting the slot of others.
+    fn bucket_key(&self, request: &HTTPRequest, remote_addr: SocketAddr) -> Option<u64> {
+        let mut hasher = DefaultHasher::new();
+        self.config.seed.hash(&mut hasher);
+        if !self.config.paths.is_empty() {
+            let path = request.uri().path();
+            self.config
+                .paths
+                .iter()
+                .find(|prefix| path.starts_with(prefix.as_str()))?
+                .hash(&mut hasher);
+        }
+        match &self.config.key {
+            RateLimitKey::Header(name) if request.headers().contains_key(name) => {
+                request.headers().get(name).unwrap().as_bytes().hash(&mut hasher);
+            }
+            RateLimitKey::IP | RateLimitKey::Header(_) => remote_addr.ip().to_canonical().hash(&mut hasher),
+            RateLimitKey::Path => {}
+        }
+        Some(hasher.finish())
+    }
+
+    /// Consumes a token from the bucket of the given request, returning the response
+    /// to send back when the bucket is empty.
+    pub fn check(&self, request: &HTTPRequest, remote_addr: SocketAddr) -> Option<HTTPResponse> {
+        let key = self.bucket_key(request, remote_addr)?;
+        let slots = self.slots.get();
+        let slot = &slots[(key % slots.len() as u64) as usize];
+        let fingerprint = key >> RATE_LIMIT_TAT_BITS;
+        let now = unix_now_micros();
+        let mut current = slot.load(atomic::Ordering::Acquire);
+        loop {
+            let last = if current >> RATE_LIMIT_TAT_BITS == fingerprint {
+                current & RATE_LIMIT_TAT_MASK
+            } else {
+                0
+            };
+            let tat = last.max(now) + self.interval;
+            if tat - now > self.tolerance {
+                self.reject(remote_addr);
+                return Some(self.response(tat - now - self.tolerance, tat - now - self.interval));
+            }
+            let next = (fingerprint << RATE_LIMIT_TAT_BITS) | tat;
+            match slot.compare_exchange_weak(current, next, atomic::Ordering::AcqRel, atomic::Ordering::Acquire) {
+                Ok(_) => return None,
+                Err(value) => current = value,
+            }
+        }
+    }
+
+    fn response(&self, retry_after: u64, reset: u64) -> HTTPResponse {
+        let mut res = response_429();
+        let headers = res.headers_mut();
+        headers.insert(RETRY_AFTER, HeaderValue::from(retry_after.div_ceil(1_000_000)));
+        headers.insert(HK_RATELIMIT_LIMIT, HeaderValue::from(self.config.burst));
+        headers.insert(HK_RATELIMIT_REMAINING, HeaderValue::from(0));
+        headers.insert(HK_RATELIMIT_RESET, HeaderValue::from(reset.div_ceil(1_000_000)));
+        res
+    }
+
+    fn reject(&self, remote_addr: SocketAddr) {
+        self.rejected.incr(|count, total| {
+            log::warn!(
+                "Rate limited {} requests (latest from {}), {} in total",
+                count,
+                remote_addr.ip(),
+                total
+            );
+        });
+    }
+}
+
+/// Answers requests with 503 when the worker is overloaded.
+pub(crate) struct LoadShedder {
+    config: LoadSheddingConfig,
+    shed: RejectionsCounter,
+}
+
+impl LoadShedder {
+    pub fn new(config: LoadSheddingConfig) -> Arc<Self> {
+        Arc::new(Self {
+            config,
+            shed: RejectionsCounter::new(),
+        })
+    }
+
+    pub fn response(&self, request: &HTTPRequest, remote_addr: SocketAddr) -> HTTPResponse {
+        self.reject(remote_addr);
+        let mut res = response_503();
+        let headers = res.headers_mut();
+        headers.insert(RETRY_AFTER, HeaderValue::from(self.config.retry_after));
+        // NOTE: connection-specific headers are not allowed in HTTP/2
+        if request.version() <= hyper::Version::HTTP_11 {
+            headers.insert(CONNECTION, HeaderValue::from_static("close"));
+        }
+        res
+    }
+
+    fn reject(&self, remote_addr: SocketAddr) {
+        self.shed.incr(|count, total| {
+            log::warn!(
+                "Shed {} requests due to overload (latest from {}), {} in total",
+                count,
+                remote_addr.ip(),
+                total
+            );
+        });
+    }
+}
+
+/// Connection slots of an accept loop, bounded by the backpressure.
+pub(crate) struct Backpressure {
+    semaphore: Arc<Semaphore>,
+    queue_timeout: Option<Duration>,
+    overloaded: AtomicBool,
+}
+
+impl Backpressure {
+    pub fn new(
+        permits: usize,
+        shedder: Option<&Arc<LoadShedder>>,
+        adaptive_limiter: Option<&Arc<AdaptiveLimiter>>,
+    ) -> Self {
+        Self {
+            semaphore: adaptive_limiter.map_or_else(|| Arc::new(Semaphore::new(permits)), |v| v.semaphore.clone()),
+            queue_timeout: shedder.map(|v| v.config.queue_timeout),
+            overloaded: AtomicBool::new(false),
+        }
+    }
+
+    /// Waits for a free slot. With load shedding enabled, gives up once the queue timeout
+    /// expires, and doesn't wait at all until slots become available again.
+    pub async fn acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let Some(queue_timeout) = self.queue_timeout else {
+            return Some(self.semaphore.clone().acquire_owned().await.unwrap());
+        };
+        if let Some(permit) = self.try_acquire() {
+            return Some(permit);
+        }
+        if self.overloaded.load(atomic::Ordering::Relaxed) {
+            return None;
+        }
+        let permit = tokio::time::timeout(queue_timeout, self.semaphore.clone().acquire_owned())
+            .await
+            .ok()
+            .map(Result::unwrap);
+        self.overloaded.store(permit.is_none(), atomic::Ordering::Relaxed);
+        permit
+    }
+
+    pub fn try_acquire(&self) -> Option<OwnedSemaphorePermit> {
+        let permit = self.semaphore.clone().try_acquire_owned().ok();
+        if permit.is_some() {
+            self.overloaded.store(false, atomic::Ordering::Relaxed);
+        }
+        permit
+    }
+}
+
+/// Bounds the number of requests processed concurrently by a worker, regardless of
+/// the number of connections they come from.
+pub(crate) struct ConcurrencyLimiter {
+    config: ConcurrencyLimitConfig,
+    semaphore: Arc<Semaphore>,
+    queued: AtomicUsize,
+    rejected: RejectionsCounter,
+}
+
+struct QueueGuard<'a>(&'a AtomicUsize);
+
+impl<'a> QueueGuard<'a> {
+    fn enter(queued: &'a AtomicUsize) -> (Self, usize) {
+        (Self(queued), queued.fetch_add(1, atomic::Ordering::Relaxed))
+    }
+}
+
+impl Drop for QueueGuard<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, atomic::Ordering::Relaxed);
+    }
+}
+
+impl ConcurrencyLimiter {
+    pub fn new(config: ConcurrencyLimitConfig) -> Arc<Self> {
+        Arc::new(Self {
+            semaphore: Arc::new(Semaphore::new(config.max_requests)),
+            config,
+            queued: AtomicUsize::new(0),
+            rejected: RejectionsCounter::new(),
+        })
+    }
+
+    /// Waits for a free slot, returning `None` when the queue is full or the queue timeout expires.
+    pub async fn acquire(&self, remote_addr: SocketAddr) -> Option<OwnedSemaphorePermit> {
+        if let Ok(permit) = self.semaphore.clone().try_acquire_owned() {
+            return Some(permit);
+        }
+        let (_guard, position) = QueueGuard::enter(&self.queued);
+        let permit = match self.config.queue_timeout {
+            _ if position >= self.config.queue_size => None,
+            Some(queue_timeout) => tokio::time::timeout(queue_timeout, self.semaphore.clone().acquire_owned())
+                .await
+                .ok()
+                .map(Result::unwrap),
+            None => Some(self.semaphore.clone().acquire_owned().await.unwrap()),
+        };
+        if permit.is_none() {
+            self.rejected.incr(|count, total| {
+                log::warn!(
+                    "Rejected {} requests over the concurrency limit (latest from {}), {} in total",
+                    count,
+                    remote_addr.ip(),
+                    total
+                );
+            });
+        }
+        permit
+    }
+}
+
+struct AdaptiveState {
+    limit: usize,
+    estimate: f64,
+    debt: usize,
+    short_rtt: f64,
+    long_rtt: f64,
+    log: (Option<Instant>, u64),
+}
+
+/// Adjusts the number of connections a worker can serve concurrently, based on the
+/// latency of the application.
+///
+/// The limit follows the gradient between the long term latency and the short term one:
+/// it grows while latency is stable, and shrinks as soon as requests start queueing up.
+pub(crate) struct AdaptiveLimiter {
+    config: AdaptiveConcurrencyConfig,
+    semaphore: Arc<Semaphore>,
+    state: Mutex<AdaptiveState>,
+    requests: AtomicUsize,
+    rejected: AtomicU64,
+}
+
+/// A request being served by the application, tracked by the adaptive limiter.
+pub(crate) struct AdaptiveRequest {
+    limiter: Arc<AdaptiveLimiter>,
+    started: Instant,
+}
+
+impl AdaptiveRequest {
+    /// Samples the latency of the completed request.
+    pub fn finish(self) {
+        self.limiter.sample(self.started.elapsed());
+    }
+}
+
+impl Drop for AdaptiveRequest {
+    fn drop(&mut self) {
+        self.limiter.requests.fetch_sub(1, atomic::Ordering::Relaxed);
+    }
+}
+
+#[allow(clippy::cast_precision_loss)]
+impl AdaptiveLimiter {
+    pub fn new(config: AdaptiveConcurrencyConfig) -> Arc<Self> {
+        Arc::new(Self {
+            semaphore: Arc::new(Semaphore::new(config.initial_limit)),
+            state: Mutex::new(AdaptiveState {
+                limit: config.initial_limit,
+                estimate: config.initial_limit as f64,
+                debt: 0,
+                short_rtt: 0.0,
+                long_rtt: 0.0,
+                log: (None, 0),
+            }),
+            config,
+            requests: AtomicUsize::new(0),
+            rejected: AtomicU64::new(0),
+        })
+    }
+
+    pub fn track(self: &Arc<Self>) -> AdaptiveRequest {
+        self.requests.fetch_add(1, atomic::Ordering::Relaxed);
+        AdaptiveRequest {
+            limiter: self.clone(),
+            started: Instant::now(),
+        }
+    }
+
+    pub fn reject(&self) {
+        self.rejected.fetch_add(1, atomic::Ordering::Relaxed);
+        // NOTE: keep reporting while every request gets rejected and no samples come in
+        let mut state = self.state.lock().unwrap();
+        self.report(&mut state);
+    }
+
+    fn sample(&self, latency: Duration) {
+        let rtt = latency.as_secs_f64();
+        let mut state = self.state.lock().unwrap();
+        if state.long_rtt == 0.0 {
+            state.short_rtt = rtt;
+            state.long_rtt = rtt;
+        }
+        state.short_rtt += (rtt - state.short_rtt) * 2.0 / (ADAPTIVE_SHORT_WINDOW + 1.0);
+        state.long_rtt += (rtt - state.long_rtt) * 2.0 / (ADAPTIVE_LONG_WINDOW + 1.0);
+        // NOTE: let the long term latency recover faster once the application gets faster
+        if state.long_rtt > state.short_rtt * 2.0 {
+            state.long_rtt *= 0.95;
+        }
+
+        let inflight = self.requests.load(atomic::Ordering::Relaxed);
+        let gradient = if state.short_rtt > 0.0 {
+            (ADAPTIVE_TOLERANCE * state.long_rtt / state.short_rtt).clamp(0.5, 1.0)
+        } else {
+            1.0
+        };
+        let mut estimate = state.estimate * gradient + state.estimate.sqrt();
+        // NOTE: don't grow the limit when the application doesn't even use the current one
+        if (inflight as f64) < state.estimate / 2.0 {
+            estimate = estimate.min(state.estimate);
+        }
+        state.estimate = (state.estimate * (1.0 - self.config.smoothing) + estimate * self.config.smoothing)
+            .clamp(self.config.min_limit as f64, self.config.max_limit as f64);
+        self.resize(&mut state);
+        self.report(&mut state);
+    }
+
+    // NOTE: permits in use cannot be revoked, so we keep track of the ones to drop once released.
+    fn resize(&self, state: &mut AdaptiveState) {
+        let limit = state.estimate as usize;
+        if limit > state.limit {
+            let increase = limit - state.limit;
+            let repaid = increase.min(state.debt);
+            state.debt -= repaid;
+            self.semaphore.add_permits(increase - repaid);
+        } else {
+            state.debt += state.limit - limit;
+        }
+        state.limit = limit;
+        if state.debt > 0 {
+            state.debt -= self.semaphore.forget_permits(state.debt);
+        }
+    }
+
+    // NOTE: permits are held by connections, including idle keep-alive ones.
+    fn connections(&self, state: &AdaptiveState) -> usize {
+        (state.limit + state.debt).saturating_sub(self.semaphore.available_permits())
+    }
+
+    // NOTE: the report goes to a dedicated `_granian.metrics` logger with a stable
+    //       `key=value` format, so it can be routed elsewhere and scraped.
+    fn report(&self, state: &mut AdaptiveState) {
+        if state.log.0.is_some_and(|v| v.elapsed() < REJECTIONS_LOG_INTERVAL) {
+            return;
+        }
+        let rejected = self.rejected.load(atomic::Ordering::Relaxed);
+        log::info!(
+            target: METRICS_LOG_TARGET,
+            "adaptive_concurrency pid={} limit={} inflight={} connections={} latency_ms={:.1} baseline_ms={:.1} rejected={} rejected_total={}",
+            std::process::id(),
+            state.limit,
+            self.requests.load(atomic::Ordering::Relaxed),
+            self.connections(state),
+            state.short_rtt * 1000.0,
+            state.long_rtt * 1000.0,
+            rejected - state.log.1,
+            rejected
+        );
+        state.log = (Some(Instant::now()), rejected);
+    }
+}
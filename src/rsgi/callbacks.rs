@@ -1,23 +1,34 @@
 use pyo3::prelude::*;
 use std::sync::OnceLock;
 use tokio::sync::oneshot;
+use tokio_util::sync::CancellationToken;
 
 use super::{
-    io::{RSGIHTTPProtocol as HTTPProtocol, RSGIWebsocketProtocol as WebsocketProtocol, WebsocketDetachedTransport},
+    io::{
+        RSGIHTTPProtocol as HTTPProtocol, RSGIWebsocketProtocol as WebsocketProtocol, WebsocketDetachedTransport,
+        HK_LAST_EVENT_ID,
+    },
     types::{PyResponse, PyResponseBody, RSGIHTTPScope as HTTPScope, RSGIWebsocketScope as WebsocketScope},
 };
 use crate::{
-    callbacks::ArcCBScheduler,
+    callbacks::{ArcCBScheduler, DisconnectWatcher},
+    http::InterimSender,
     runtime::RuntimeRef,
     utils::log_application_callable_exception,
+    workers::ServiceConfig,
     ws::{HyperWebsocket, UpgradeData},
 };
 
 macro_rules! callback_impl_done_http {
     ($self:expr) => {
-        if let Some(tx) = $self.proto.get().tx() {
-            let _ = tx.send(PyResponse::Body(PyResponseBody::empty(500, Vec::new())));
+        if let Some(watcher) = &$self.disconnect_watcher {
+            watcher.finish();
         }
+        let proto = $self.proto.get();
+        if let Some(tx) = proto.tx() {
+            let _ = tx.send(PyResponse::Body(PyResponseBody::server_error()));
+        }
+        proto.release();
     };
 }
 
@@ -47,14 +58,21 @@ pub(crate) struct CallbackWatcherHTTP {
     #[pyo3(get)]
     scope: Py<HTTPScope>,
     aio_taskref: OnceLock<PyObject>,
+    disconnect_watcher: Option<DisconnectWatcher>,
 }
 
 impl CallbackWatcherHTTP {
-    pub fn new(py: Python, proto: HTTPProtocol, scope: HTTPScope) -> Self {
+    pub fn new(
+        py: Python,
+        proto: HTTPProtocol,
+        scope: HTTPScope,
+        disconnect_watcher: Option<DisconnectWatcher>,
+    ) -> Self {
         Self {
             proto: Py::new(py, proto).unwrap(),
             scope: Py::new(py, scope).unwrap(),
             aio_taskref: OnceLock::new(),
+            disconnect_watcher,
         }
     }
 }
@@ -65,11 +83,20 @@ impl CallbackWatcherHTTP {
         callback_impl_done_http!(self);
     }
 
-    fn err(&self, err: Bound<PyAny>) {
-        callback_impl_done_err!(self, &PyErr::from_value(err));
+    fn err(&self, py: Python, err: Bound<PyAny>) {
+        self.proto.get().fail();
+        let err = PyErr::from_value(err);
+        if self.disconnect_watcher.as_ref().is_some_and(|v| v.caused(py, &err)) {
+            self.done();
+            return;
+        }
+        callback_impl_done_err!(self, &err);
     }
 
     fn taskref(&self, py: Python, task: PyObject) {
+        if let Some(watcher) = &self.disconnect_watcher {
+            watcher.watch(task.clone_ref(py));
+        }
         callback_impl_taskref!(self, py, task);
     }
 }
@@ -114,14 +141,29 @@ pub(crate) fn call_http(
     rt: RuntimeRef,
     body: hyper::body::Incoming,
     scope: HTTPScope,
+    interim: Option<InterimSender>,
+    disconnect: CancellationToken,
+    svc_cfg: &ServiceConfig,
 ) -> oneshot::Receiver<PyResponse> {
     let brt = rt.innerb.clone();
     let (tx, rx) = oneshot::channel();
-    let protocol = HTTPProtocol::new(rt, tx, body);
+    let disconnect_watcher = svc_cfg
+        .cancel_on_disconnect
+        .then(|| DisconnectWatcher::new(rt.clone(), disconnect.clone()));
+    let protocol = HTTPProtocol::new(
+        rt,
+        tx,
+        body,
+        disconnect,
+        svc_cfg.stream_buffer_size,
+        scope.grpc_deadline(),
+        scope.header(HK_LAST_EVENT_ID),
+        interim,
+    );
 
     let _ = brt.run(move || {
         Python::with_gil(|py| {
-            let watcher = Py::new(py, CallbackWatcherHTTP::new(py, protocol, scope)).unwrap();
+            let watcher = Py::new(py, CallbackWatcherHTTP::new(py, protocol, scope, disconnect_watcher)).unwrap();
             cb.get().schedule(py, watcher.as_any());
         });
     });
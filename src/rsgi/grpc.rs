@@ -0,0 +1,304 @@
+use http_body_util::BodyExt;
+use hyper::{
+    body,
+    header::{HeaderMap, HeaderName, HeaderValue},
+};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS};
+use pyo3::{prelude::*, pybacked::PyBackedStr};
+use std::{
+    borrow::Cow,
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::{sync::Mutex as AsyncMutex, time::Instant};
+use tokio_util::{
+    bytes::{Buf, BufMut, BytesMut},
+    sync::CancellationToken,
+};
+
+use super::errors::{error_proto, error_stream};
+use crate::{
+    conversion::{header_from_py, FutureResultToPy},
+    http::HTTPBodySender,
+    runtime::{future_into_py_futlike, Runtime, RuntimeRef},
+};
+
+const GRPC_HEADER_SIZE: usize = 5;
+const GRPC_MAX_MESSAGE_SIZE: usize = 4 * 1024 * 1024;
+// grpc-message values are percent-encoded, as per the HTTP/2 protocol spec
+const GRPC_MESSAGE_ENCODE_SET: &AsciiSet = &CONTROLS.add(b'%');
+
+pub(crate) const GRPC_STATUS_OK: u32 = 0;
+pub(crate) const GRPC_STATUS_UNKNOWN: u32 = 2;
+const GRPC_STATUS_DEADLINE_EXCEEDED: u32 = 4;
+const GRPC_STATUS_RESOURCE_EXHAUSTED: u32 = 8;
+const GRPC_STATUS_UNIMPLEMENTED: u32 = 12;
+const GRPC_STATUS_INTERNAL: u32 = 13;
+
+pub(crate) const HV_GRPC_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("application/grpc");
+const HK_GRPC_STATUS: HeaderName = HeaderName::from_static("grpc-status");
+const HK_GRPC_MESSAGE: HeaderName = HeaderName::from_static("grpc-message");
+pub(crate) const HK_GRPC_TIMEOUT: HeaderName = HeaderName::from_static("grpc-timeout");
+
+/// Parses a `grpc-timeout` header value, made of up to 8 digits followed by the unit.
+pub(crate) fn parse_timeout(value: &HeaderValue) -> Option<Duration> {
+    let value = value.to_str().ok()?;
+    if value.len() < 2 || value.len() > 9 {
+        return None;
+    }
+    let (amount, unit) = value.split_at(value.len() - 1);
+    if !amount.bytes().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let amount: u64 = amount.parse().ok()?;
+    match unit {
+        "H" => Some(Duration::from_secs(amount * 3600)),
+        "M" => Some(Duration::from_secs(amount * 60)),
+        "S" => Some(Duration::from_secs(amount)),
+        "m" => Some(Duration::from_millis(amount)),
+        "u" => Some(Duration::from_micros(amount)),
+        "n" => Some(Duration::from_nanos(amount)),
+        _ => None,
+    }
+}
+
+pub(crate) fn status_trailers(status: u32, message: Option<&str>, mut metadata: HeaderMap) -> HeaderMap {
+    metadata.insert(HK_GRPC_STATUS, HeaderValue::from(status));
+    if let Some(message) = message.filter(|v| !v.is_empty()) {
+        if let Ok(value) = HeaderValue::from_str(&utf8_percent_encode(message, GRPC_MESSAGE_ENCODE_SET).to_string()) {
+            metadata.insert(HK_GRPC_MESSAGE, value);
+        }
+    }
+    metadata
+}
+
+pub(crate) type GRPCSender = Arc<Mutex<Option<HTTPBodySender>>>;
+
+/// Completes the call with the given status trailers, unless it was already completed.
+/// The sender is taken right away, so that only the first caller gets to complete the call.
+pub(crate) fn finish_call(sender: &GRPCSender, trailers: HeaderMap) -> Option<impl Future<Output = bool> + Send> {
+    let tx = sender.lock().unwrap().take()?;
+    Some(async move { tx.send_trailers(trailers).await.is_ok() })
+}
+
+enum GRPCReadError {
+    Closed,
+    Status(u32, &'static str),
+}
+
+struct GRPCDecoder {
+    body: body::Incoming,
+    buffer: BytesMut,
+    eos: bool,
+}
+
+impl GRPCDecoder {
+    async fn next_message(&mut self) -> Result<Option<body::Bytes>, GRPCReadError> {
+        loop {
+            if self.buffer.len() >= GRPC_HEADER_SIZE {
+                if self.buffer[0] != 0 {
+                    return Err(GRPCReadError::Status(
+                        GRPC_STATUS_UNIMPLEMENTED,
+                        "Compressed messages are not supported",
+                    ));
+                }
+                let size =
+                    u32::from_be_bytes([self.buffer[1], self.buffer[2], self.buffer[3], self.buffer[4]]) as usize;
+                if size > GRPC_MAX_MESSAGE_SIZE {
+                    return Err(GRPCReadError::Status(
+                        GRPC_STATUS_RESOURCE_EXHAUSTED,
+                        "Received message larger than max",
+                    ));
+                }
+                if self.buffer.len() >= GRPC_HEADER_SIZE + size {
+                    self.buffer.advance(GRPC_HEADER_SIZE);
+                    return Ok(Some(self.buffer.split_to(size).freeze()));
+                }
+            }
+            if self.eos {
+                if self.buffer.is_empty() {
+                    return Ok(None);
+                }
+                return Err(GRPCReadError::Status(GRPC_STATUS_INTERNAL, "Incomplete message"));
+            }
+            // NOTE: we only pull from the body when the application asks for a message,
+            //       so the HTTP/2 flow control applies backpressure to the client.
+            match self.body.frame().await {
+                Some(Ok(frame)) => {
+                    if let Ok(data) = frame.into_data() {
+                        self.buffer.extend_from_slice(&data);
+                    }
+                }
+                Some(Err(_)) => return Err(GRPCReadError::Closed),
+                None => self.eos = true,
+            }
+        }
+    }
+}
+
+#[pyclass(frozen, module = "granian._granian")]
+pub(crate) struct RSGIGRPCTransport {
+    rt: RuntimeRef,
+    decoder: Arc<AsyncMutex<Option<GRPCDecoder>>>,
+    tx: GRPCSender,
+    deadline: Option<Instant>,
+    expired: CancellationToken,
+}
+
+impl RSGIGRPCTransport {
+    pub fn new(
+        rt: RuntimeRef,
+        body: body::Incoming,
+        tx: GRPCSender,
+        deadline: Option<Instant>,
+        disconnect: CancellationToken,
+    ) -> Self {
+        let expired = CancellationToken::new();
+        if let Some(deadline) = deadline {
+            let sender = tx.clone();
+            let expired = expired.clone();
+            rt.spawn(async move {
+                tokio::select! {
+                    biased;
+                    () = disconnect.cancelled() => {}
+                    () = tokio::time::sleep_until(deadline) => {
+                        let trailers = status_trailers(
+                            GRPC_STATUS_DEADLINE_EXCEEDED,
+                            Some("Deadline Exceeded"),
+                            HeaderMap::new(),
+                        );
+                        if let Some(finish) = finish_call(&sender, trailers) {
+                            // NOTE: the client is still there, so we only stop pending reads and writes
+                            expired.cancel();
+                            finish.await;
+                        }
+                    }
+                }
+            });
+        }
+
+        Self {
+            rt,
+            decoder: Arc::new(AsyncMutex::new(Some(GRPCDecoder {
+                body,
+                buffer: BytesMut::new(),
+                eos: false,
+            }))),
+            tx,
+            deadline,
+            expired,
+        }
+    }
+
+    #[inline]
+    fn read<'p>(&self, py: Python<'p>, stop_iteration: bool) -> PyResult<Bound<'p, PyAny>> {
+        let decoder = self.decoder.clone();
+        let sender = self.tx.clone();
+        let expired = self.expired.clone();
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            let guard = &mut *decoder.lock().await;
+            let message = match guard.as_mut() {
+                Some(state) => tokio::select! {
+                    biased;
+                    () = expired.cancelled() => Err(GRPCReadError::Closed),
+                    message = state.next_message() => message,
+                },
+                None => Ok(None),
+            };
+            match message {
+                Ok(Some(message)) => return FutureResultToPy::Bytes(message),
+                Ok(None) => {
+                    let _ = guard.take();
+                }
+                Err(GRPCReadError::Status(status, message)) => {
+                    let _ = guard.take();
+                    if let Some(finish) = finish_call(&sender, status_trailers(status, Some(message), HeaderMap::new()))
+                    {
+                        finish.await;
+                    }
+                    return FutureResultToPy::Err(error_stream!());
+                }
+                Err(GRPCReadError::Closed) => {
+                    let _ = guard.take();
+                    return FutureResultToPy::Err(error_stream!());
+                }
+            }
+            if stop_iteration {
+                return FutureResultToPy::Err(Err(pyo3::exceptions::PyStopAsyncIteration::new_err("stream exhausted")));
+            }
+            FutureResultToPy::None
+        })
+    }
+}
+
+#[pymethods]
+impl RSGIGRPCTransport {
+    #[getter(time_remaining)]
+    fn get_time_remaining(&self) -> Option<f64> {
+        self.deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()).as_secs_f64())
+    }
+
+    fn recv<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
+        self.read(py, false)
+    }
+
+    fn __aiter__(pyself: Py<Self>) -> Py<Self> {
+        pyself
+    }
+
+    fn __anext__<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
+        self.read(py, true)
+    }
+
+    fn send<'p>(&self, py: Python<'p>, data: Cow<[u8]>) -> PyResult<Bound<'p, PyAny>> {
+        let Some(transport) = self.tx.lock().unwrap().clone() else {
+            return error_stream!();
+        };
+        let Ok(size) = u32::try_from(data.len()) else {
+            return error_proto!();
+        };
+        let mut frame = BytesMut::with_capacity(GRPC_HEADER_SIZE + data.len());
+        frame.put_u8(0);
+        frame.put_u32(size);
+        frame.extend_from_slice(&data);
+        let expired = self.expired.clone();
+
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            tokio::select! {
+                biased;
+                () = expired.cancelled() => FutureResultToPy::Err(error_stream!()),
+                ret = transport.send(frame.freeze()) => match ret {
+                    Ok(()) => FutureResultToPy::None,
+                    _ => FutureResultToPy::Err(error_stream!()),
+                },
+            }
+        })
+    }
+
+    #[pyo3(signature = (status=0, message=None, trailers=vec![]))]
+    fn finish<'p>(
+        &self,
+        py: Python<'p>,
+        status: u32,
+        message: Option<String>,
+        trailers: Vec<(PyBackedStr, PyBackedStr)>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let mut metadata = HeaderMap::with_capacity(trailers.len() + 2);
+        for (key, value) in &trailers {
+            let (key, value) = header_from_py(key, value)?;
+            metadata.append(key, value);
+        }
+        let Some(finish) = finish_call(&self.tx, status_trailers(status, message.as_deref(), metadata)) else {
+            return error_stream!();
+        };
+
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            if finish.await {
+                return FutureResultToPy::None;
+            }
+            FutureResultToPy::Err(error_stream!())
+        })
+    }
+}
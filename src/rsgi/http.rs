@@ -1,7 +1,8 @@
 use http_body_util::BodyExt;
 use hyper::{header::SERVER as HK_SERVER, http::response::Builder as ResponseBuilder, StatusCode};
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 use super::{
     callbacks::{call_http, call_ws},
@@ -9,8 +10,10 @@ use super::{
 };
 use crate::{
     callbacks::ArcCBScheduler,
-    http::{empty_body, response_500, HTTPRequest, HTTPResponse, HV_SERVER},
+    http::{empty_body, response_500, validate_strict, HTTPRequest, HTTPResponse, InterimSender, HV_SERVER},
     runtime::RuntimeRef,
+    tls::TLSConnectionInfo,
+    workers::ServiceConfig,
     ws::{is_upgrade_request as is_ws_upgrade, upgrade_intent as ws_upgrade, UpgradeData},
 };
 
@@ -24,13 +27,21 @@ macro_rules! build_scope {
             $server_addr,
             $client_addr,
             $req.headers,
+            $req.extensions.get::<Arc<TLSConnectionInfo>>().cloned(),
         )
     };
 }
 
 macro_rules! handle_http_response {
-    ($handler:expr, $rt:expr, $callback:expr, $body:expr, $scope:expr) => {
-        match $handler($callback, $rt, $body, $scope).await {
+    ($handler:expr, $rt:expr, $callback:expr, $svc_cfg:expr, $body:expr, $scope:expr, $interim:expr) => {{
+        // NOTE: hyper drops the service future when the connection goes away,
+        //       so the guard signals the disconnection to the application.
+        let disconnect = CancellationToken::new();
+        let disconnect_guard = disconnect.clone().drop_guard();
+        let pyres = $handler($callback, $rt, $body, $scope, $interim, disconnect, &$svc_cfg).await;
+        disconnect_guard.disarm();
+
+        match pyres {
             Ok(PyResponse::Body(pyres)) => pyres.to_response(),
             Ok(PyResponse::File(pyres)) => pyres.to_response().await,
             _ => {
@@ -38,7 +49,7 @@ macro_rules! handle_http_response {
                 response_500()
             }
         }
-    };
+    }};
 }
 
 macro_rules! handle_request {
@@ -47,14 +58,21 @@ macro_rules! handle_request {
         pub(crate) async fn $func_name(
             rt: RuntimeRef,
             callback: ArcCBScheduler,
+            svc_cfg: Arc<ServiceConfig>,
             server_addr: SocketAddr,
             client_addr: SocketAddr,
             req: HTTPRequest,
             scheme: &str,
         ) -> HTTPResponse {
+            if svc_cfg.strict_requests {
+                if let Some(res) = validate_strict(&req, client_addr) {
+                    return res;
+                }
+            }
             let (parts, body) = req.into_parts();
+            let interim = parts.extensions.get::<InterimSender>().cloned();
             let scope = build_scope!(HTTPScope, server_addr, client_addr, parts, scheme);
-            handle_http_response!($handler, rt, callback, body, scope)
+            handle_http_response!($handler, rt, callback, svc_cfg, body, scope, interim)
         }
     };
 }
@@ -83,13 +101,19 @@ macro_rules! handle_request_with_ws {
         pub(crate) async fn $func_name(
             rt: RuntimeRef,
             callback: ArcCBScheduler,
+            svc_cfg: Arc<ServiceConfig>,
             server_addr: SocketAddr,
             client_addr: SocketAddr,
             mut req: HTTPRequest,
             scheme: &str,
         ) -> HTTPResponse {
+            if svc_cfg.strict_requests {
+                if let Some(res) = validate_strict(&req, client_addr) {
+                    return res;
+                }
+            }
             if is_ws_upgrade(&req) {
-                match ws_upgrade(&mut req, None) {
+                match ws_upgrade(&mut req, Some(svc_cfg.ws_config)) {
                     Ok((res, ws)) => {
                         let (parts, _) = req.into_parts();
                         let scope = build_scope!(WebsocketScope, server_addr, client_addr, parts, scheme);
@@ -147,8 +171,9 @@ macro_rules! handle_request_with_ws {
             }
 
             let (parts, body) = req.into_parts();
+            let interim = parts.extensions.get::<InterimSender>().cloned();
             let scope = build_scope!(HTTPScope, server_addr, client_addr, parts, scheme);
-            handle_http_response!($handler_req, rt, callback, body, scope)
+            handle_http_response!($handler_req, rt, callback, svc_cfg, body, scope, interim)
         }
     };
 }
@@ -1,34 +1,52 @@
-use futures::{sink::SinkExt, StreamExt, TryStreamExt};
+use futures::StreamExt;
 use http_body_util::BodyExt;
-use hyper::body;
+use hyper::{
+    body,
+    header::{HeaderMap, HeaderName, HeaderValue, CACHE_CONTROL, CONTENT_TYPE},
+};
 use pyo3::{prelude::*, pybacked::PyBackedStr};
 use std::{
     borrow::Cow,
     sync::{atomic, Arc, Mutex, RwLock},
+    time::Duration,
+};
+use tokio::{
+    sync::{oneshot, Mutex as AsyncMutex},
+    time::Instant,
 };
-use tokio::sync::{mpsc, oneshot, Mutex as AsyncMutex};
 use tokio_tungstenite::tungstenite::Message;
+use tokio_util::sync::CancellationToken;
 
 use super::{
     errors::{error_proto, error_stream},
-    types::{PyResponse, PyResponseBody, PyResponseFile},
+    grpc::{
+        finish_call, status_trailers, GRPCSender, RSGIGRPCTransport, GRPC_STATUS_OK, GRPC_STATUS_UNKNOWN,
+        HV_GRPC_CONTENT_TYPE,
+    },
+    types::{PyResponse, PyResponseBody, PyResponseFile, RSGIHeaders},
 };
 use crate::{
-    conversion::FutureResultToPy,
+    conversion::{header_from_py, FutureResultToPy},
+    http::{body_channel, DisconnectAwareBody, HTTPBodySender, InterimSender, HEAD_EARLY_HINTS},
     runtime::{future_into_py_futlike, Runtime, RuntimeRef},
-    ws::{HyperWebsocket, UpgradeData, WSRxStream, WSStream, WSTxStream},
+    ws::{HyperWebsocket, UpgradeData, WSFragments, WSRxStream, WSStream, WSTxStream},
 };
 
 pub(crate) type WebsocketDetachedTransport = (i32, bool, Option<tokio::task::JoinHandle<()>>);
 
+pub(super) const HK_LAST_EVENT_ID: HeaderName = HeaderName::from_static("last-event-id");
+const SSE_HEARTBEAT: &[u8] = b":\n\n";
+const HV_SSE_CONTENT_TYPE: HeaderValue = HeaderValue::from_static("text/event-stream");
+const HV_SSE_CACHE_CONTROL: HeaderValue = HeaderValue::from_static("no-cache");
+
 #[pyclass(frozen, module = "granian._granian")]
 pub(crate) struct RSGIHTTPStreamTransport {
     rt: RuntimeRef,
-    tx: mpsc::Sender<Result<body::Bytes, anyhow::Error>>,
+    tx: HTTPBodySender,
 }
 
 impl RSGIHTTPStreamTransport {
-    pub fn new(rt: RuntimeRef, transport: mpsc::Sender<Result<body::Bytes, anyhow::Error>>) -> Self {
+    pub fn new(rt: RuntimeRef, transport: HTTPBodySender) -> Self {
         Self { rt, tx: transport }
     }
 }
@@ -40,7 +58,7 @@ impl RSGIHTTPStreamTransport {
         let bdata: Box<[u8]> = data.into();
 
         future_into_py_futlike(self.rt.clone(), py, async move {
-            match transport.send(Ok(body::Bytes::from(bdata))).await {
+            match transport.send(body::Bytes::from(bdata)).await {
                 Ok(()) => FutureResultToPy::None,
                 _ => FutureResultToPy::Err(error_stream!()),
             }
@@ -51,7 +69,119 @@ impl RSGIHTTPStreamTransport {
         let transport = self.tx.clone();
 
         future_into_py_futlike(self.rt.clone(), py, async move {
-            match transport.send(Ok(body::Bytes::from(data))).await {
+            match transport.send(body::Bytes::from(data)).await {
+                Ok(()) => FutureResultToPy::None,
+                _ => FutureResultToPy::Err(error_stream!()),
+            }
+        })
+    }
+
+    fn send_trailers<'p>(
+        &self,
+        py: Python<'p>,
+        headers: Vec<(PyBackedStr, PyBackedStr)>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let mut trailers = HeaderMap::with_capacity(headers.len());
+        for (key, value) in &headers {
+            let (key, value) = header_from_py(key, value)?;
+            trailers.append(key, value);
+        }
+        let transport = self.tx.clone();
+
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            match transport.send_trailers(trailers).await {
+                Ok(()) => FutureResultToPy::None,
+                _ => FutureResultToPy::Err(error_stream!()),
+            }
+        })
+    }
+}
+
+#[pyclass(frozen, module = "granian._granian")]
+pub(crate) struct RSGISSETransport {
+    rt: RuntimeRef,
+    tx: HTTPBodySender,
+    #[pyo3(get)]
+    last_event_id: Option<String>,
+}
+
+impl RSGISSETransport {
+    pub fn new(
+        rt: RuntimeRef,
+        transport: HTTPBodySender,
+        last_event_id: Option<String>,
+        heartbeat: Option<Duration>,
+        disconnect: CancellationToken,
+    ) -> Self {
+        if let Some(period) = heartbeat {
+            let tx = transport.clone();
+            rt.spawn(async move {
+                let mut interval = tokio::time::interval_at(Instant::now() + period, period);
+                loop {
+                    tokio::select! {
+                        biased;
+                        () = disconnect.cancelled() => break,
+                        _ = interval.tick() => {
+                            if tx.send(body::Bytes::from_static(SSE_HEARTBEAT)).await.is_err() {
+                                break;
+                            }
+                        }
+                    }
+                }
+            });
+        }
+
+        Self {
+            rt,
+            tx: transport,
+            last_event_id,
+        }
+    }
+}
+
+#[pymethods]
+impl RSGISSETransport {
+    #[pyo3(signature = (data, event=None, id=None, retry=None))]
+    fn send<'p>(
+        &self,
+        py: Python<'p>,
+        data: &str,
+        event: Option<&str>,
+        id: Option<&str>,
+        retry: Option<u64>,
+    ) -> PyResult<Bound<'p, PyAny>> {
+        let mut message = String::with_capacity(data.len() + 16);
+        if let Some(event) = event {
+            if event.contains(['\r', '\n']) {
+                return error_proto!();
+            }
+            message.push_str("event: ");
+            message.push_str(event);
+            message.push('\n');
+        }
+        if let Some(id) = id {
+            if id.contains(['\r', '\n', '\0']) {
+                return error_proto!();
+            }
+            message.push_str("id: ");
+            message.push_str(id);
+            message.push('\n');
+        }
+        if let Some(retry) = retry {
+            message.push_str("retry: ");
+            message.push_str(&retry.to_string());
+            message.push('\n');
+        }
+        for line in data.replace("\r\n", "\n").split(['\r', '\n']) {
+            message.push_str("data: ");
+            message.push_str(line);
+            message.push('\n');
+        }
+        message.push('\n');
+
+        let transport = self.tx.clone();
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            match transport.send(body::Bytes::from(message)).await {
                 Ok(()) => FutureResultToPy::None,
                 _ => FutureResultToPy::Err(error_stream!()),
             }
@@ -65,30 +195,78 @@ pub(crate) struct RSGIHTTPProtocol {
     tx: Mutex<Option<oneshot::Sender<PyResponse>>>,
     body: Mutex<Option<body::Incoming>>,
     body_stream: Arc<AsyncMutex<Option<http_body_util::BodyStream<body::Incoming>>>>,
+    disconnect: CancellationToken,
+    stream_buffer_size: usize,
+    trailers: Arc<Mutex<Option<HeaderMap>>>,
+    grpc_deadline: Option<Instant>,
+    grpc_tx: GRPCSender,
+    last_event_id: Option<HeaderValue>,
+    interim: Option<InterimSender>,
 }
 
 impl RSGIHTTPProtocol {
-    pub fn new(rt: RuntimeRef, tx: oneshot::Sender<PyResponse>, body: body::Incoming) -> Self {
+    pub fn new(
+        rt: RuntimeRef,
+        tx: oneshot::Sender<PyResponse>,
+        body: body::Incoming,
+        disconnect: CancellationToken,
+        stream_buffer_size: usize,
+        grpc_deadline: Option<Instant>,
+        last_event_id: Option<HeaderValue>,
+        interim: Option<InterimSender>,
+    ) -> Self {
         Self {
             rt,
             tx: Mutex::new(Some(tx)),
             body: Mutex::new(Some(body)),
             body_stream: Arc::new(AsyncMutex::new(None)),
+            disconnect,
+            stream_buffer_size,
+            trailers: Arc::new(Mutex::new(None)),
+            grpc_deadline,
+            grpc_tx: Arc::new(Mutex::new(None)),
+            last_event_id,
+            interim,
         }
     }
 
     pub fn tx(&self) -> Option<oneshot::Sender<PyResponse>> {
         self.tx.lock().unwrap().take()
     }
+
+    pub fn release(&self) {
+        self.finish_grpc(GRPC_STATUS_OK);
+        self.disconnect.cancel();
+    }
+
+    pub fn fail(&self) {
+        self.finish_grpc(GRPC_STATUS_UNKNOWN);
+    }
+
+    // NOTE: gRPC calls not explicitly finished by the application get their status
+    //       from the outcome of the application callable.
+    fn finish_grpc(&self, status: u32) {
+        if let Some(finish) = finish_call(&self.grpc_tx, status_trailers(status, None, HeaderMap::new())) {
+            self.rt.spawn(async move {
+                finish.await;
+            });
+        }
+    }
 }
 
 #[pymethods]
 impl RSGIHTTPProtocol {
     fn __call__<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
         if let Some(body) = self.body.lock().unwrap().take() {
+            let trailers = self.trailers.clone();
             return future_into_py_futlike(self.rt.clone(), py, async move {
                 match body.collect().await {
-                    Ok(data) => FutureResultToPy::Bytes(data.to_bytes()),
+                    Ok(data) => {
+                        if let Some(headers) = data.trailers() {
+                            *trailers.lock().unwrap() = Some(headers.clone());
+                        }
+                        FutureResultToPy::Bytes(data.to_bytes())
+                    }
                     _ => FutureResultToPy::Err(error_stream!()),
                 }
             });
@@ -111,49 +289,93 @@ impl RSGIHTTPProtocol {
             return Err(pyo3::exceptions::PyStopAsyncIteration::new_err("stream exhausted"));
         }
         let body_stream = self.body_stream.clone();
+        let trailers = self.trailers.clone();
         future_into_py_futlike(self.rt.clone(), py, async move {
             let guard = &mut *body_stream.lock().await;
-            match guard.as_mut().unwrap().next().await {
-                Some(chunk) => {
-                    let chunk = chunk
-                        .map(|buf| buf.into_data().unwrap_or_default())
-                        .unwrap_or(body::Bytes::new());
-                    FutureResultToPy::Bytes(chunk)
-                }
-                _ => {
-                    let _ = guard.take();
-                    FutureResultToPy::Bytes(body::Bytes::new())
+            loop {
+                match guard.as_mut().unwrap().next().await {
+                    Some(Ok(frame)) => match frame.into_data() {
+                        Ok(chunk) => return FutureResultToPy::Bytes(chunk),
+                        Err(frame) => {
+                            if let Ok(headers) = frame.into_trailers() {
+                                *trailers.lock().unwrap() = Some(headers);
+                            }
+                        }
+                    },
+                    Some(Err(_)) => return FutureResultToPy::Bytes(body::Bytes::new()),
+                    _ => {
+                        let _ = guard.take();
+                        return FutureResultToPy::Bytes(body::Bytes::new());
+                    }
                 }
             }
         })
     }
 
+    fn trailers(&self) -> Option<RSGIHeaders> {
+        self.trailers.lock().unwrap().clone().map(RSGIHeaders::new)
+    }
+
+    fn client_disconnect<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
+        let disconnect = self.disconnect.clone();
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            disconnect.cancelled().await;
+            FutureResultToPy::None
+        })
+    }
+
+    fn send_early_hints(&self, headers: Vec<(PyBackedStr, PyBackedStr)>) -> PyResult<()> {
+        if self.tx.lock().unwrap().is_none() {
+            return error_proto!();
+        }
+        let mut hints = HeaderMap::with_capacity(headers.len());
+        for (key, value) in &headers {
+            let (key, value) = header_from_py(key, value)?;
+            hints.append(key, value);
+        }
+        // NOTE: informational responses are only supported on HTTP/1.1 connections,
+        //       elsewhere hints are dropped so applications don't need to care about the protocol.
+        match &self.interim {
+            Some(interim) => interim.send(HEAD_EARLY_HINTS, &hints),
+            None => log::debug!("Dropping early hints: unsupported on the current connection"),
+        }
+        Ok(())
+    }
+
     #[pyo3(signature = (status=200, headers=vec![]))]
-    fn response_empty(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>) {
+    fn response_empty(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>) -> PyResult<()> {
+        let response = PyResponseBody::empty(status, headers)?;
         if let Some(tx) = self.tx.lock().unwrap().take() {
-            let _ = tx.send(PyResponse::Body(PyResponseBody::empty(status, headers)));
+            let _ = tx.send(PyResponse::Body(response));
         }
+        Ok(())
     }
 
     #[pyo3(signature = (status=200, headers=vec![], body=vec![].into()))]
-    fn response_bytes(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: Cow<[u8]>) {
+    fn response_bytes(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: Cow<[u8]>) -> PyResult<()> {
+        let response = PyResponseBody::from_bytes(status, headers, body)?;
         if let Some(tx) = self.tx.lock().unwrap().take() {
-            let _ = tx.send(PyResponse::Body(PyResponseBody::from_bytes(status, headers, body)));
+            let _ = tx.send(PyResponse::Body(response));
         }
+        Ok(())
     }
 
     #[pyo3(signature = (status=200, headers=vec![], body=String::new()))]
-    fn response_str(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: String) {
+    fn response_str(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: String) -> PyResult<()> {
+        let response = PyResponseBody::from_string(status, headers, body)?;
         if let Some(tx) = self.tx.lock().unwrap().take() {
-            let _ = tx.send(PyResponse::Body(PyResponseBody::from_string(status, headers, body)));
+            let _ = tx.send(PyResponse::Body(response));
         }
+        Ok(())
     }
 
     #[pyo3(signature = (status, headers, file))]
-    fn response_file(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, file: String) {
+    fn response_file(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, file: String) -> PyResult<()> {
+        let response = PyResponseFile::new(status, headers, file)?;
         if let Some(tx) = self.tx.lock().unwrap().take() {
-            let _ = tx.send(PyResponse::File(PyResponseFile::new(status, headers, file)));
+            let _ = tx.send(PyResponse::File(response));
         }
+        Ok(())
     }
 
     #[pyo3(signature = (status=200, headers=vec![]))]
@@ -163,21 +385,103 @@ impl RSGIHTTPProtocol {
         status: u16,
         headers: Vec<(PyBackedStr, PyBackedStr)>,
     ) -> PyResult<Bound<'p, RSGIHTTPStreamTransport>> {
+        let (body_tx, body_rx) = body_channel(self.stream_buffer_size);
+        let response = PyResponseBody::new(
+            status,
+            headers,
+            DisconnectAwareBody::boxed(BodyExt::boxed(body_rx), self.disconnect.clone()),
+        )?;
         if let Some(tx) = self.tx.lock().unwrap().take() {
-            let (body_tx, body_rx) = mpsc::channel::<Result<body::Bytes, anyhow::Error>>(1);
-            let body_stream = http_body_util::StreamBody::new(
-                tokio_stream::wrappers::ReceiverStream::new(body_rx).map_ok(body::Frame::data),
-            );
-            let _ = tx.send(PyResponse::Body(PyResponseBody::new(
-                status,
-                headers,
-                BodyExt::boxed(BodyExt::map_err(body_stream, std::convert::Into::into)),
-            )));
+            let _ = tx.send(PyResponse::Body(response));
             let trx = Py::new(py, RSGIHTTPStreamTransport::new(self.rt.clone(), body_tx))?;
             return Ok(trx.into_bound(py));
         }
         error_proto!()
     }
+
+    #[pyo3(signature = (status=200, headers=vec![], heartbeat=Some(15.0)))]
+    fn response_sse<'p>(
+        &self,
+        py: Python<'p>,
+        status: u16,
+        headers: Vec<(PyBackedStr, PyBackedStr)>,
+        heartbeat: Option<f64>,
+    ) -> PyResult<Bound<'p, RSGISSETransport>> {
+        let heartbeat = match heartbeat.map(Duration::try_from_secs_f64) {
+            Some(Ok(period)) if !period.is_zero() => Some(period),
+            Some(_) => return error_proto!(),
+            None => None,
+        };
+        let (body_tx, body_rx) = body_channel(self.stream_buffer_size);
+        let response = PyResponseBody::new(
+            status,
+            headers,
+            DisconnectAwareBody::boxed(BodyExt::boxed(body_rx), self.disconnect.clone()),
+        )?
+        .with_default_header(CONTENT_TYPE, HV_SSE_CONTENT_TYPE)
+        .with_default_header(CACHE_CONTROL, HV_SSE_CACHE_CONTROL);
+        if let Some(tx) = self.tx.lock().unwrap().take() {
+            let _ = tx.send(PyResponse::Body(response));
+            let trx = Py::new(
+                py,
+                RSGISSETransport::new(
+                    self.rt.clone(),
+                    body_tx,
+                    self.last_event_id
+                        .as_ref()
+                        .and_then(|v| v.to_str().ok())
+                        .map(ToOwned::to_owned),
+                    heartbeat,
+                    self.disconnect.clone(),
+                ),
+            )?;
+            return Ok(trx.into_bound(py));
+        }
+        error_proto!()
+    }
+
+    #[pyo3(signature = (headers=vec![]))]
+    fn grpc<'p>(
+        &self,
+        py: Python<'p>,
+        headers: Vec<(PyBackedStr, PyBackedStr)>,
+    ) -> PyResult<Bound<'p, RSGIGRPCTransport>> {
+        let (body_tx, body_rx) = body_channel(self.stream_buffer_size);
+        let response = PyResponseBody::new(
+            200,
+            headers,
+            DisconnectAwareBody::boxed(BodyExt::boxed(body_rx), self.disconnect.clone()),
+        )?
+        .with_default_header(CONTENT_TYPE, HV_GRPC_CONTENT_TYPE);
+        let tx = self.tx.lock().unwrap().take();
+        let body = self.body.lock().unwrap().take();
+        match (tx, body) {
+            (Some(tx), Some(body)) => {
+                *self.grpc_tx.lock().unwrap() = Some(body_tx);
+                let _ = tx.send(PyResponse::Body(response));
+                let trx = Py::new(
+                    py,
+                    RSGIGRPCTransport::new(
+                        self.rt.clone(),
+                        body,
+                        self.grpc_tx.clone(),
+                        self.grpc_deadline,
+                        self.disconnect.clone(),
+                    ),
+                )?;
+                Ok(trx.into_bound(py))
+            }
+            (tx, body) => {
+                if let Some(tx) = tx {
+                    *self.tx.lock().unwrap() = Some(tx);
+                }
+                if let Some(body) = body {
+                    *self.body.lock().unwrap() = Some(body);
+                }
+                error_proto!()
+            }
+        }
+    }
 }
 
 #[pyclass(frozen, module = "granian._granian")]
@@ -186,6 +490,7 @@ pub(crate) struct RSGIWebsocketTransport {
     tx: Arc<AsyncMutex<WSTxStream>>,
     rx: Arc<AsyncMutex<WSRxStream>>,
     closed: atomic::AtomicBool,
+    fragments: Arc<WSFragments>,
 }
 
 impl RSGIWebsocketTransport {
@@ -196,9 +501,32 @@ impl RSGIWebsocketTransport {
             tx: Arc::new(AsyncMutex::new(tx)),
             rx: Arc::new(AsyncMutex::new(rx)),
             closed: false.into(),
+            fragments: Arc::new(WSFragments::new()),
         }
     }
 
+    #[inline]
+    fn send_fragment<'p>(&self, py: Python<'p>, message: Message, fin: bool) -> PyResult<Bound<'p, PyAny>> {
+        let transport = self.tx.clone();
+        let fragments = self.fragments.clone();
+
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            if let Ok(stream) = transport.try_lock() {
+                let Some((frame, state)) = fragments.frame(message, fin) else {
+                    return FutureResultToPy::Err(error_proto!());
+                };
+                return match stream.send(Message::Frame(frame)).await {
+                    Ok(()) => {
+                        fragments.commit(state);
+                        FutureResultToPy::None
+                    }
+                    _ => FutureResultToPy::Err(error_stream!()),
+                };
+            }
+            FutureResultToPy::Err(error_proto!())
+        })
+    }
+
     pub fn close(&self) -> Option<tokio::task::JoinHandle<()>> {
         if self.closed.load(atomic::Ordering::Relaxed) {
             return None;
@@ -207,7 +535,7 @@ impl RSGIWebsocketTransport {
 
         let tx = self.tx.clone();
         let handle = self.rt.spawn(async move {
-            if let Ok(mut tx) = tx.try_lock() {
+            if let Ok(tx) = tx.try_lock() {
                 if let Err(err) = tx.close().await {
                     log::info!("Failed to close websocket with error {:?}", err);
                 }
@@ -223,12 +551,21 @@ impl RSGIWebsocketTransport {
         let transport = self.rx.clone();
         future_into_py_futlike(self.rt.clone(), py, async move {
             if let Ok(mut stream) = transport.try_lock() {
-                while let Some(recv) = stream.next().await {
-                    match recv {
-                        Ok(Message::Ping(_) | Message::Pong(_)) => continue,
-                        Ok(message) => return FutureResultToPy::RSGIWSMessage(message),
-                        _ => break,
-                    }
+                if let Some(Ok(message)) = stream.next().await {
+                    return FutureResultToPy::RSGIWSMessage(message, true);
+                }
+                return FutureResultToPy::Err(error_stream!());
+            }
+            FutureResultToPy::Err(error_proto!())
+        })
+    }
+
+    fn receive_fragment<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
+        let transport = self.rx.clone();
+        future_into_py_futlike(self.rt.clone(), py, async move {
+            if let Ok(mut stream) = transport.try_lock() {
+                if let Some(Ok((message, fin))) = stream.next_fragment().await {
+                    return FutureResultToPy::RSGIWSMessage(message, fin);
                 }
                 return FutureResultToPy::Err(error_stream!());
             }
@@ -238,10 +575,14 @@ impl RSGIWebsocketTransport {
 
     fn send_bytes<'p>(&self, py: Python<'p>, data: Cow<[u8]>) -> PyResult<Bound<'p, PyAny>> {
         let transport = self.tx.clone();
+        let fragments = self.fragments.clone();
         let bdata: Box<[u8]> = data.into();
 
         future_into_py_futlike(self.rt.clone(), py, async move {
-            if let Ok(mut stream) = transport.try_lock() {
+            if let Ok(stream) = transport.try_lock() {
+                if fragments.pending() {
+                    return FutureResultToPy::Err(error_proto!());
+                }
                 return match stream.send(bdata[..].into()).await {
                     Ok(()) => FutureResultToPy::None,
                     _ => FutureResultToPy::Err(error_stream!()),
@@ -253,9 +594,13 @@ impl RSGIWebsocketTransport {
 
     fn send_str<'p>(&self, py: Python<'p>, data: String) -> PyResult<Bound<'p, PyAny>> {
         let transport = self.tx.clone();
+        let fragments = self.fragments.clone();
 
         future_into_py_futlike(self.rt.clone(), py, async move {
-            if let Ok(mut stream) = transport.try_lock() {
+            if let Ok(stream) = transport.try_lock() {
+                if fragments.pending() {
+                    return FutureResultToPy::Err(error_proto!());
+                }
                 return match stream.send(data.into()).await {
                     Ok(()) => FutureResultToPy::None,
                     _ => FutureResultToPy::Err(error_stream!()),
@@ -264,6 +609,17 @@ impl RSGIWebsocketTransport {
             FutureResultToPy::Err(error_proto!())
         })
     }
+
+    #[pyo3(signature = (data, fin=false))]
+    fn send_bytes_fragment<'p>(&self, py: Python<'p>, data: Cow<[u8]>, fin: bool) -> PyResult<Bound<'p, PyAny>> {
+        let bdata: Box<[u8]> = data.into();
+        self.send_fragment(py, Message::Binary(body::Bytes::from(bdata)), fin)
+    }
+
+    #[pyo3(signature = (data, fin=false))]
+    fn send_str_fragment<'p>(&self, py: Python<'p>, data: String, fin: bool) -> PyResult<Bound<'p, PyAny>> {
+        self.send_fragment(py, Message::Text(data.into()), fin)
+    }
 }
 
 #[pyclass(frozen, module = "granian._granian")]
@@ -314,7 +670,9 @@ impl RSGIWebsocketProtocol {
 
     fn accept<'p>(&self, py: Python<'p>) -> PyResult<Bound<'p, PyAny>> {
         let rth = self.rt.clone();
-        let mut upgrade = self.upgrade.write().unwrap().take().unwrap();
+        let Some(mut upgrade) = self.upgrade.write().unwrap().take() else {
+            return error_proto!();
+        };
         let transport = self.websocket.clone();
         let itransport = self.transport.clone();
         future_into_py_futlike(self.rt.clone(), py, async move {
@@ -323,10 +681,12 @@ impl RSGIWebsocketProtocol {
                 Ok(()) => match (&mut *ws).await {
                     Ok(stream) => {
                         let mut trx = itransport.lock().unwrap();
-                        Python::with_gil(|py| {
-                            let pytransport = Py::new(py, RSGIWebsocketTransport::new(rth, stream)).unwrap();
-                            *trx = Some(pytransport.clone_ref(py));
-                            FutureResultToPy::Py(pytransport.into_any())
+                        Python::with_gil(|py| match Py::new(py, RSGIWebsocketTransport::new(rth, stream)) {
+                            Ok(pytransport) => {
+                                *trx = Some(pytransport.clone_ref(py));
+                                FutureResultToPy::Py(pytransport.into_any())
+                            }
+                            Err(err) => FutureResultToPy::Err(Err(err)),
                         })
                     }
                     _ => FutureResultToPy::Err(error_proto!()),
@@ -11,10 +11,15 @@ use percent_encoding::percent_decode_str;
 use pyo3::types::{PyBytes, PyIterator, PyList, PyString};
 use pyo3::{prelude::*, pybacked::PyBackedStr};
 use std::{borrow::Cow, net::SocketAddr, sync::Arc};
-use tokio::fs::File;
+use tokio::{fs::File, time::Instant};
 use tokio_util::io::ReaderStream;
 
-use crate::http::{empty_body, response_404, HTTPResponseBody, HV_SERVER};
+use super::grpc::{parse_timeout, HK_GRPC_TIMEOUT};
+use crate::{
+    conversion::{header_from_py, status_code_from_py},
+    http::{empty_body, response_404, HTTPResponseBody, HV_SERVER},
+    tls::TLSConnectionInfo,
+};
 
 const RSGI_PROTO_VERSION: &str = "1.4";
 
@@ -93,7 +98,8 @@ impl RSGIHeaders {
             self.inner
                 .get_all(key)
                 .iter()
-                .map(|v| PyString::new(py, v.to_str().unwrap()))
+                .filter_map(|v| v.to_str().ok())
+                .map(|v| PyString::new(py, v))
                 .collect::<Vec<Bound<PyString>>>(),
         )
     }
@@ -111,6 +117,7 @@ macro_rules! rsgi_scope_cls {
             client: SocketAddr,
             #[pyo3(get)]
             headers: RSGIHeaders,
+            tls: Option<Arc<TLSConnectionInfo>>,
         }
 
         impl $name {
@@ -122,6 +129,7 @@ macro_rules! rsgi_scope_cls {
                 server: SocketAddr,
                 client: SocketAddr,
                 headers: HeaderMap,
+                tls: Option<Arc<TLSConnectionInfo>>,
             ) -> Self {
                 Self {
                     http_version,
@@ -131,6 +139,7 @@ macro_rules! rsgi_scope_cls {
                     server,
                     client,
                     headers: RSGIHeaders::new(headers),
+                    tls,
                 }
             }
         }
@@ -192,6 +201,26 @@ macro_rules! rsgi_scope_cls {
             fn get_query_string(&self) -> &str {
                 self.uri.query().unwrap_or("")
             }
+
+            #[getter(tls_protocol)]
+            fn get_tls_protocol(&self) -> Option<&str> {
+                self.tls.as_ref().and_then(|v| v.protocol_name())
+            }
+
+            #[getter(tls_cipher)]
+            fn get_tls_cipher(&self) -> Option<Cow<str>> {
+                self.tls.as_ref().and_then(|v| v.cipher_suite_name())
+            }
+
+            #[getter(tls_alpn_protocol)]
+            fn get_tls_alpn_protocol(&self) -> Option<&str> {
+                self.tls.as_ref().and_then(|v| v.alpn_protocol_name())
+            }
+
+            #[getter(tls_sni)]
+            fn get_tls_sni(&self) -> Option<&str> {
+                self.tls.as_ref().and_then(|v| v.server_name.as_deref())
+            }
         }
     };
 }
@@ -199,6 +228,20 @@ macro_rules! rsgi_scope_cls {
 rsgi_scope_cls!(RSGIHTTPScope, "http");
 rsgi_scope_cls!(RSGIWebsocketScope, "ws");
 
+impl RSGIHTTPScope {
+    pub fn header(&self, key: HeaderName) -> Option<HeaderValue> {
+        self.headers.inner.get(key).cloned()
+    }
+
+    pub fn grpc_deadline(&self) -> Option<Instant> {
+        self.headers
+            .inner
+            .get(HK_GRPC_TIMEOUT)
+            .and_then(parse_timeout)
+            .map(|timeout| Instant::now() + timeout)
+    }
+}
+
 pub(crate) enum PyResponse {
     Body(PyResponseBody),
     File(PyResponseFile),
@@ -220,52 +263,65 @@ macro_rules! headers_from_py {
     ($headers:expr) => {{
         let mut headers = HeaderMap::with_capacity($headers.len() + 3);
         headers.insert(HK_SERVER, HV_SERVER);
-        for (key, value) in $headers {
-            headers.append(
-                HeaderName::from_bytes(key.as_bytes()).unwrap(),
-                HeaderValue::from_str(&value).unwrap(),
-            );
+        for (key, value) in &$headers {
+            let (key, value) = header_from_py(key, value)?;
+            headers.append(key, value);
         }
         headers
     }};
 }
 
 impl PyResponseBody {
-    pub fn new(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: HTTPResponseBody) -> Self {
-        Self {
-            status: status.try_into().unwrap(),
+    pub fn new(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: HTTPResponseBody) -> PyResult<Self> {
+        Ok(Self {
+            status: status_code_from_py(status)?,
             headers: headers_from_py!(headers),
             body,
-        }
+        })
     }
 
-    pub fn empty(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>) -> Self {
-        Self {
-            status: status.try_into().unwrap(),
+    pub fn empty(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>) -> PyResult<Self> {
+        Ok(Self {
+            status: status_code_from_py(status)?,
             headers: headers_from_py!(headers),
             body: empty_body(),
+        })
+    }
+
+    pub fn server_error() -> Self {
+        let mut headers = HeaderMap::with_capacity(1);
+        headers.insert(HK_SERVER, HV_SERVER);
+        Self {
+            status: hyper::StatusCode::INTERNAL_SERVER_ERROR,
+            headers,
+            body: empty_body(),
         }
     }
 
-    pub fn from_bytes(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: Cow<[u8]>) -> Self {
+    pub fn from_bytes(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: Cow<[u8]>) -> PyResult<Self> {
         let rbody: Box<[u8]> = body.into();
-        Self {
-            status: status.try_into().unwrap(),
+        Ok(Self {
+            status: status_code_from_py(status)?,
             headers: headers_from_py!(headers),
             body: http_body_util::Full::new(Bytes::from(rbody))
                 .map_err(|e| match e {})
                 .boxed(),
-        }
+        })
     }
 
-    pub fn from_string(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: String) -> Self {
-        Self {
-            status: status.try_into().unwrap(),
+    pub fn from_string(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: String) -> PyResult<Self> {
+        Ok(Self {
+            status: status_code_from_py(status)?,
             headers: headers_from_py!(headers),
             body: http_body_util::Full::new(Bytes::from(body))
                 .map_err(|e| match e {})
                 .boxed(),
-        }
+        })
+    }
+
+    pub fn with_default_header(mut self, key: HeaderName, value: HeaderValue) -> Self {
+        self.headers.entry(key).or_insert(value);
+        self
     }
 
     #[inline]
@@ -278,12 +334,12 @@ impl PyResponseBody {
 }
 
 impl PyResponseFile {
-    pub fn new(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, file_path: String) -> Self {
-        Self {
-            status: status.try_into().unwrap(),
+    pub fn new(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, file_path: String) -> PyResult<Self> {
+        Ok(Self {
+            status: status_code_from_py(status)?,
             headers: headers_from_py!(headers),
             file_path,
-        }
+        })
     }
 
     #[inline]
@@ -315,12 +371,15 @@ enum WebsocketMessageType {
 pub(super) struct WebsocketInboundCloseMessage {
     #[pyo3(get)]
     kind: usize,
+    #[pyo3(get)]
+    fin: bool,
 }
 
 impl WebsocketInboundCloseMessage {
     pub fn new() -> Self {
         Self {
             kind: WebsocketMessageType::Close as usize,
+            fin: true,
         }
     }
 }
@@ -331,13 +390,16 @@ pub(super) struct WebsocketInboundBytesMessage {
     kind: usize,
     #[pyo3(get)]
     data: Py<PyBytes>,
+    #[pyo3(get)]
+    fin: bool,
 }
 
 impl WebsocketInboundBytesMessage {
-    pub fn new(data: Py<PyBytes>) -> Self {
+    pub fn new(data: Py<PyBytes>, fin: bool) -> Self {
         Self {
             kind: WebsocketMessageType::Bytes as usize,
             data,
+            fin,
         }
     }
 }
@@ -348,13 +410,16 @@ pub(super) struct WebsocketInboundTextMessage {
     kind: usize,
     #[pyo3(get)]
     data: Py<PyString>,
+    #[pyo3(get)]
+    fin: bool,
 }
 
 impl WebsocketInboundTextMessage {
-    pub fn new(data: Py<PyString>) -> Self {
+    pub fn new(data: Py<PyString>, fin: bool) -> Self {
         Self {
             kind: WebsocketMessageType::Text as usize,
             data,
+            fin,
         }
     }
 }
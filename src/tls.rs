@@ -1,55 +1,775 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, bail, Result};
+use cbc::cipher::{block_padding::Pkcs7, BlockCipher, BlockDecryptMut, KeyInit, KeyIvInit};
+use md5::{Digest, Md5};
+use pyo3::{exceptions::PyValueError, prelude::*};
 use std::{
+    borrow::Cow,
     fs, io,
     iter::Iterator,
     net::{SocketAddr, TcpListener},
-    sync::Arc,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex, RwLock},
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 use tls_listener::{
     rustls::{
         rustls::{
+            crypto::{ring, CryptoProvider},
             pki_types::{CertificateDer as Certificate, PrivateKeyDer as PrivateKey},
-            server::ServerConfig,
+            server::{
+                ClientHello, NoServerSessionStorage, ResolvesServerCert, ServerConfig, ServerSessionMemoryCache,
+                WantsServerCert,
+            },
+            sign::CertifiedKey,
+            version::{TLS12, TLS13},
+            CipherSuite, ConfigBuilder, KeyLogFile, ProtocolVersion, SupportedCipherSuite, SupportedProtocolVersion,
         },
+        server::TlsStream,
         TlsAcceptor,
     },
     TlsListener,
 };
 
+use crate::{
+    acme::{ACMECertResolver, ACME_TLS_ALPN_PROTOCOL},
+    conversion::worker_tls_config_from_py,
+    limits::{ConnectionFilter, FilteredListener},
+    workers::TLSConfig,
+};
+
+/// TLS details of an established connection, carried within request extensions.
+pub(crate) struct TLSConnectionInfo {
+    pub protocol_version: Option<ProtocolVersion>,
+    pub cipher_suite: Option<CipherSuite>,
+    pub server_cert: Option<Arc<str>>,
+    pub peer_certificates: Vec<Certificate<'static>>,
+    pub alpn_protocol: Option<Vec<u8>>,
+    pub server_name: Option<String>,
+}
+
+impl TLSConnectionInfo {
+    /// The negotiated protocol version, in the `TLSv1.x` notation.
+    pub fn protocol_name(&self) -> Option<&'static str> {
+        match self.protocol_version? {
+            ProtocolVersion::TLSv1_2 => Some("TLSv1.2"),
+            ProtocolVersion::TLSv1_3 => Some("TLSv1.3"),
+            _ => None,
+        }
+    }
+
+    /// The negotiated cipher suite, using its IANA name.
+    pub fn cipher_suite_name(&self) -> Option<Cow<'static, str>> {
+        let name = self.cipher_suite?.as_str()?;
+        Some(match name.strip_prefix("TLS13_") {
+            Some(name) => format!("TLS_{name}").into(),
+            None => name.into(),
+        })
+    }
+
+    pub fn alpn_protocol_name(&self) -> Option<&str> {
+        self.alpn_protocol.as_deref().and_then(|v| std::str::from_utf8(v).ok())
+    }
+}
+
+pub(crate) trait TLSInfoProvider {
+    fn tls_info(&self, server_cert: Option<&Arc<str>>) -> Option<Arc<TLSConnectionInfo>>;
+}
+
+impl TLSInfoProvider for tokio::net::TcpStream {
+    fn tls_info(&self, _server_cert: Option<&Arc<str>>) -> Option<Arc<TLSConnectionInfo>> {
+        None
+    }
+}
+
+impl TLSInfoProvider for TlsStream<tokio::net::TcpStream> {
+    fn tls_info(&self, server_cert: Option<&Arc<str>>) -> Option<Arc<TLSConnectionInfo>> {
+        let (_, conn) = self.get_ref();
+        Some(Arc::new(TLSConnectionInfo {
+            protocol_version: conn.protocol_version(),
+            cipher_suite: conn.negotiated_cipher_suite().map(|v| v.suite()),
+            server_cert: server_cert.cloned(),
+            peer_certificates: conn.peer_certificates().map(<[_]>::to_vec).unwrap_or_default(),
+            alpn_protocol: conn.alpn_protocol().map(<[_]>::to_vec),
+            server_name: conn.server_name().map(ToOwned::to_owned),
+        }))
+    }
+}
+
 pub(crate) fn tls_listener(
     config: Arc<ServerConfig>,
     tcp: TcpListener,
-) -> Result<(TlsListener<tokio::net::TcpListener, TlsAcceptor>, SocketAddr)> {
-    let tcp_listener = tokio::net::TcpListener::from_std(tcp).unwrap();
+    filter: Option<Arc<ConnectionFilter>>,
+) -> Result<(TlsListener<FilteredListener, TlsAcceptor>, SocketAddr)> {
+    let tcp_listener = FilteredListener::new(tokio::net::TcpListener::from_std(tcp).unwrap(), filter);
     let local_addr = tcp_listener.local_addr()?;
     let listener = TlsListener::new(TlsAcceptor::from(config), tcp_listener);
     Ok((listener, local_addr))
 }
 
-pub(crate) fn load_certs(filename: String) -> io::Result<Vec<Certificate<'static>>> {
-    rustls_pemfile::certs(&mut io::BufReader::new(fs::File::open(filename)?)).collect()
-}
-
-pub(crate) fn load_private_key(filename: String, password: Option<String>) -> Result<PrivateKey<'static>> {
-    match &password {
-        Some(pwd) => {
-            let expected_tag = "ENCRYPTED PRIVATE KEY";
-            let content = fs::read(filename)?;
-            let sections = pem::parse_many(content)?;
-            let mut iter = sections
-                .into_iter()
-                .filter(|v| v.tag() == expected_tag)
-                .map(|v| v.contents().to_vec());
-            let key = pkcs8::EncryptedPrivateKeyInfo::try_from(
-                iter.next().map_or_else(|| Err(anyhow!("Invalid key")), Ok)?.as_slice(),
-            )
-            .map_err(|_| anyhow!("Invalid key"))?
-            .decrypt(pwd)
-            .map_err(|_| anyhow!("Cannot decrypt key"))?;
-            Ok(PrivateKey::Pkcs8(key.to_bytes().to_vec().into()))
-        }
-        None => rustls_pemfile::private_key(&mut io::BufReader::new(fs::File::open(filename)?))
-            .map_err(std::convert::Into::into)
-            .map(std::option::Option::unwrap),
+pub(crate) fn cert_to_pem(cert: &Certificate) -> String {
+    pem::encode_config(
+        &pem::Pem::new("CERTIFICATE", cert.to_vec()),
+        pem::EncodeConfig::new().set_line_ending(pem::LineEnding::LF),
+    )
+}
+
+/// TLS material passed from Python, either as a path to a file or as in-memory contents.
+#[derive(Clone, FromPyObject)]
+pub(crate) enum TLSMaterial {
+    Path(String),
+    Data(Vec<u8>),
+}
+
+impl TLSMaterial {
+    fn read(&self) -> io::Result<Cow<[u8]>> {
+        match self {
+            Self::Path(path) => fs::read(path).map(Cow::Owned),
+            Self::Data(data) => Ok(Cow::Borrowed(data)),
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Self::Path(path) => path,
+            Self::Data(_) => "memory",
+        }
+    }
+}
+
+const PEM_BOUNDARY: &[u8] = b"-----BEGIN ";
+
+fn is_pem(data: &[u8]) -> bool {
+    data.windows(PEM_BOUNDARY.len()).any(|v| v == PEM_BOUNDARY)
+}
+
+// NOTE: both PFX and X.509 certificates are DER sequences, but the former starts with its version (3),
+//       while the latter starts with the TBS certificate sequence.
+fn is_pkcs12(data: &[u8]) -> bool {
+    der_read(data).is_some_and(|(tag, content, _)| tag == 0x30 && content.starts_with(&[0x02, 0x01, 0x03]))
+}
+
+fn is_der_sequence(data: &[u8]) -> bool {
+    der_read(data).is_some_and(|(tag, _, rest)| tag == 0x30 && rest.is_empty())
+}
+
+fn load_pkcs12(data: &[u8], password: Option<&str>) -> Result<(Vec<Certificate<'static>>, PrivateKey<'static>)> {
+    let store = p12_keystore::KeyStore::from_pkcs12(data, password.unwrap_or_default())
+        .map_err(|err| anyhow!("Cannot decode bundle: {err}"))?;
+    let (_, chain) = store
+        .private_key_chain()
+        .ok_or_else(|| anyhow!("No private key found"))?;
+    let certs: Vec<_> = chain
+        .chain()
+        .iter()
+        .map(|cert| Certificate::from(cert.as_der().to_vec()))
+        .collect();
+    if certs.is_empty() {
+        bail!("No certificates found");
+    }
+    Ok((certs, PrivateKey::Pkcs8(chain.key().to_vec().into())))
+}
+
+fn decrypt_pkcs8(data: &[u8], password: &str) -> Result<PrivateKey<'static>> {
+    let key = pkcs8::EncryptedPrivateKeyInfo::try_from(data)
+        .map_err(|_| anyhow!("Invalid key"))?
+        .decrypt(password)
+        .map_err(|_| anyhow!("Cannot decrypt key"))?;
+    Ok(PrivateKey::Pkcs8(key.to_bytes().to_vec().into()))
+}
+
+fn cbc_decrypt<C>(key: &[u8], iv: &[u8], data: &[u8]) -> Result<Vec<u8>>
+where
+    C: BlockCipher + BlockDecryptMut + KeyInit,
+{
+    cbc::Decryptor::<C>::new_from_slices(key, iv)
+        .map_err(|_| anyhow!("Invalid key encryption parameters"))?
+        .decrypt_padded_vec_mut::<Pkcs7>(data)
+        .map_err(|_| anyhow!("Cannot decrypt key"))
+}
+
+// NOTE: legacy OpenSSL encryption of PKCS#1 and SEC1 keys (`Proc-Type: 4,ENCRYPTED`),
+//       which derives the cipher key from the password with `EVP_BytesToKey` over MD5,
+//       using the first 8 bytes of the IV as salt.
+fn decrypt_legacy_pem(data: &[u8], dek_info: &str, password: &str) -> Result<Vec<u8>> {
+    let (algorithm, iv) = dek_info
+        .split_once(',')
+        .ok_or_else(|| anyhow!("Invalid DEK-Info header"))?;
+    let iv = (0..iv.len())
+        .step_by(2)
+        .map(|idx| iv.get(idx..idx + 2).and_then(|v| u8::from_str_radix(v, 16).ok()))
+        .collect::<Option<Vec<u8>>>()
+        .filter(|iv| iv.len() >= 8)
+        .ok_or_else(|| anyhow!("Invalid DEK-Info header"))?;
+    let key_len = match algorithm.trim() {
+        "AES-128-CBC" => 16,
+        "AES-192-CBC" | "DES-EDE3-CBC" => 24,
+        "AES-256-CBC" => 32,
+        "DES-CBC" => 8,
+        algorithm => bail!("Unsupported key encryption algorithm {algorithm}"),
+    };
+    let mut key = Vec::with_capacity(key_len + 16);
+    let mut digest = Vec::new();
+    while key.len() < key_len {
+        digest = Md5::new()
+            .chain_update(&digest)
+            .chain_update(password)
+            .chain_update(&iv[..8])
+            .finalize()
+            .to_vec();
+        key.extend_from_slice(&digest);
+    }
+    key.truncate(key_len);
+    let decrypted = match algorithm.trim() {
+        "AES-128-CBC" => cbc_decrypt::<aes::Aes128>(&key, &iv, data),
+        "AES-192-CBC" => cbc_decrypt::<aes::Aes192>(&key, &iv, data),
+        "AES-256-CBC" => cbc_decrypt::<aes::Aes256>(&key, &iv, data),
+        "DES-EDE3-CBC" => cbc_decrypt::<des::TdesEde3>(&key, &iv, data),
+        _ => cbc_decrypt::<des::Des>(&key, &iv, data),
+    }?;
+    // a wrong password might still produce valid padding, but not a valid DER structure
+    if !is_der_sequence(&decrypted) {
+        bail!("Cannot decrypt key");
+    }
+    Ok(decrypted)
+}
+
+fn load_private_key(data: &[u8], password: Option<&str>) -> Result<PrivateKey<'static>> {
+    if !is_pem(data) {
+        if let Some(pwd) = password {
+            if pkcs8::EncryptedPrivateKeyInfo::try_from(data).is_ok() {
+                return decrypt_pkcs8(data, pwd);
+            }
+        }
+        return PrivateKey::try_from(data)
+            .map(|key| key.clone_key())
+            .map_err(|err| anyhow!(err));
+    }
+
+    for section in pem::parse_many(data)? {
+        let tag = section.tag();
+        if !matches!(
+            tag,
+            "PRIVATE KEY" | "RSA PRIVATE KEY" | "EC PRIVATE KEY" | "ENCRYPTED PRIVATE KEY"
+        ) {
+            continue;
+        }
+        let password = password.ok_or_else(|| anyhow!("Key is encrypted, but no password was provided"));
+        if tag == "ENCRYPTED PRIVATE KEY" {
+            return decrypt_pkcs8(section.contents(), password?);
+        }
+        let contents = match section.headers().get("DEK-Info") {
+            Some(dek_info) => decrypt_legacy_pem(section.contents(), dek_info, password?)?,
+            None => section.contents().to_vec(),
+        };
+        return Ok(match tag {
+            "RSA PRIVATE KEY" => PrivateKey::Pkcs1(contents.into()),
+            "EC PRIVATE KEY" => PrivateKey::Sec1(contents.into()),
+            _ => PrivateKey::Pkcs8(contents.into()),
+        });
+    }
+    bail!("No private key found")
+}
+
+/// Loads the certificate chain and the private key from the given material.
+///
+/// Certificates can be PEM, DER or PKCS#12 bundles; when no key is given, it is looked up
+/// within the certificate material (PKCS#12 bundles or combined PEM files).
+pub(crate) fn load_identity(
+    cert: &TLSMaterial,
+    key: Option<&TLSMaterial>,
+    password: Option<&str>,
+) -> Result<(Vec<Certificate<'static>>, PrivateKey<'static>)> {
+    let cert_data = cert
+        .read()
+        .map_err(|err| anyhow!("Unable to load certificates from {}: {err}", cert.name()))?;
+    if !is_pem(&cert_data) && is_pkcs12(&cert_data) {
+        return load_pkcs12(&cert_data, password)
+            .map_err(|err| anyhow!("Unable to load PKCS#12 bundle from {}: {err}", cert.name()));
+    }
+
+    let certs = match is_pem(&cert_data) {
+        true => rustls_pemfile::certs(&mut &cert_data[..])
+            .collect::<io::Result<Vec<_>>>()
+            .map_err(|err| anyhow!("Unable to load certificates from {}: {err}", cert.name()))?,
+        false if is_der_sequence(&cert_data) => vec![Certificate::from(cert_data.to_vec())],
+        false => Vec::new(),
+    };
+    if certs.is_empty() {
+        bail!("No certificates found in {}", cert.name());
+    }
+
+    let key_src = key.unwrap_or(cert);
+    let key_data = match key {
+        Some(key) => key
+            .read()
+            .map_err(|err| anyhow!("Unable to load private key from {}: {err}", key.name()))?,
+        None => cert_data,
+    };
+    let pkey = load_private_key(&key_data, password)
+        .map_err(|err| anyhow!("Unable to load private key from {}: {err}", key_src.name()))?;
+    Ok((certs, pkey))
+}
+
+const CERT_EXPIRY_WARNING_DAYS: i64 = 14;
+const OCSP_EXPIRY_WARNING_HOURS: i64 = 24;
+const OCSP_CLOCK_SKEW_SECS: i64 = 300;
+const OCSP_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+
+pub(crate) fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |v| i64::try_from(v.as_secs()).unwrap_or(i64::MAX))
+}
+
+fn alpn_protocols(http_mode: &str) -> Result<Vec<Vec<u8>>> {
+    match http_mode {
+        "1" => Ok(vec![b"http/1.1".to_vec()]),
+        "2" => Ok(vec![b"h2".to_vec()]),
+        "auto" => Ok(vec![b"h2".to_vec(), b"http/1.1".to_vec()]),
+        _ => bail!("Unsupported HTTP mode '{http_mode}' for TLS ALPN negotiation"),
+    }
+}
+
+fn protocol_versions(min_version: &str) -> Result<Vec<&'static SupportedProtocolVersion>> {
+    match min_version {
+        "1.2" => Ok(vec![&TLS13, &TLS12]),
+        "1.3" => Ok(vec![&TLS13]),
+        _ => bail!("Unsupported minimum TLS version '{min_version}'"),
+    }
+}
+
+// NOTE: rustls names TLS 1.3 suites with a `TLS13_` prefix, while IANA uses `TLS_`;
+//       we accept both.
+fn cipher_suite_matches(suite: &SupportedCipherSuite, name: &str) -> bool {
+    let suite_name = suite.suite().as_str().unwrap_or_default();
+    suite_name == name
+        || suite_name
+            .strip_prefix("TLS13_")
+            .is_some_and(|v| name.strip_prefix("TLS_") == Some(v))
+}
+
+fn crypto_provider(ciphers: Option<&Vec<String>>) -> Result<CryptoProvider> {
+    let mut provider = ring::default_provider();
+    if let Some(ciphers) = ciphers {
+        if let Some(name) = ciphers.iter().find(|name| {
+            !provider
+                .cipher_suites
+                .iter()
+                .any(|suite| cipher_suite_matches(suite, name))
+        }) {
+            bail!("Unsupported TLS cipher suite '{name}'");
+        }
+        provider
+            .cipher_suites
+            .retain(|suite| ciphers.iter().any(|name| cipher_suite_matches(suite, name)));
+    }
+    Ok(provider)
+}
+
+/// Builds the TLS server configuration, with errors describing which part of the material is invalid.
+pub(crate) fn server_config(
+    cert: &TLSMaterial,
+    key: Option<&TLSMaterial>,
+    key_password: Option<&str>,
+    http_mode: &str,
+    opts: &TLSConfig,
+) -> Result<ServerConfig> {
+    let (certs, pkey) = load_identity(cert, key, key_password)?;
+    let keys_mismatch = |err| {
+        anyhow!(
+            "Certificate chain from {} doesn't match private key from {}: {err}",
+            cert.name(),
+            key.unwrap_or(cert).name()
+        )
+    };
+    let (provider, builder) = config_builder(opts)?;
+    let cfg = match &opts.ocsp_response {
+        Some(ocsp_path) => {
+            let (ocsp, status) = load_ocsp_response(ocsp_path, &certs[0])?;
+            let signing_key = provider
+                .key_provider
+                .load_private_key(pkey)
+                .map_err(|err| anyhow!("Unable to load private key from {}: {err}", key.unwrap_or(cert).name()))?;
+            let mut certified_key = CertifiedKey::new(certs, signing_key);
+            certified_key.keys_match().map_err(keys_mismatch)?;
+            // NOTE: a stale response shouldn't prevent serving, the resolver will pick up a fresh one
+            let next_update = match status {
+                Ok(next_update) => {
+                    certified_key.ocsp = Some(ocsp);
+                    next_update
+                }
+                Err(err) => {
+                    log::warn!("{err}, stapling disabled");
+                    None
+                }
+            };
+            builder.with_cert_resolver(Arc::new(OCSPStaplingResolver::new(
+                certified_key,
+                ocsp_path,
+                next_update,
+            )))
+        }
+        None => builder.with_single_cert(certs, pkey).map_err(keys_mismatch)?,
+    };
+    finalize_config(cfg, http_mode, opts)
+}
+
+/// Builds the TLS server configuration for certificates managed by the ACME client in the given storage.
+pub(crate) fn acme_server_config(storage: &Path, http_mode: &str, opts: &TLSConfig) -> Result<ServerConfig> {
+    let (provider, builder) = config_builder(opts)?;
+    let resolver = ACMECertResolver::new(storage, provider);
+    let mut cfg = finalize_config(builder.with_cert_resolver(Arc::new(resolver)), http_mode, opts)?;
+    cfg.alpn_protocols.push(ACME_TLS_ALPN_PROTOCOL.to_vec());
+    Ok(cfg)
+}
+
+fn config_builder(opts: &TLSConfig) -> Result<(Arc<CryptoProvider>, ConfigBuilder<ServerConfig, WantsServerCert>)> {
+    let provider = Arc::new(crypto_provider(opts.ciphers.as_ref())?);
+    let builder = ServerConfig::builder_with_provider(provider.clone())
+        .with_protocol_versions(&protocol_versions(&opts.min_version)?)
+        .map_err(|err| anyhow!("Invalid TLS settings: {err}"))?
+        .with_no_client_auth();
+    Ok((provider, builder))
+}
+
+fn finalize_config(mut cfg: ServerConfig, http_mode: &str, opts: &TLSConfig) -> Result<ServerConfig> {
+    cfg.alpn_protocols = alpn_protocols(http_mode)?;
+    cfg.session_storage = match opts.session_cache_size {
+        0 => Arc::new(NoServerSessionStorage {}),
+        size => ServerSessionMemoryCache::new(size),
+    };
+    // NOTE: the ticketer rotates its keys every 6 hours
+    if opts.session_tickets {
+        cfg.ticketer =
+            ring::Ticketer::new().map_err(|err| anyhow!("Unable to initialize TLS session tickets: {err}"))?;
+    } else if opts.session_cache_size == 0 {
+        cfg.send_tls13_tickets = 0;
+    }
+    // NOTE: `KeyLogFile` writes to the path in `SSLKEYLOGFILE` env var, if any
+    if opts.keylog {
+        cfg.key_log = Arc::new(KeyLogFile::new());
+    }
+    Ok(cfg)
+}
+
+/// Loads the OCSP response from the given file, failing if it can't be parsed or refers to another certificate.
+/// Along with the response, returns either its expiration or the reason why it can't be stapled.
+fn load_ocsp_response(path: &Path, cert: &Certificate) -> Result<(Vec<u8>, Result<Option<i64>>)> {
+    let data = fs::read(path).map_err(|err| anyhow!("Unable to load OCSP response from {}: {err}", path.display()))?;
+    let (status, response) =
+        ocsp_parse(&data, cert).map_err(|err| anyhow!("Invalid OCSP response from {}: {err}", path.display()))?;
+    let next_update = ocsp_check(status, response.as_ref(), unix_now())
+        .map_err(|err| anyhow!("Unusable OCSP response from {}: {err}", path.display()));
+    Ok((data, next_update))
+}
+
+/// Parses the OCSP response, checking it refers to the given certificate.
+///
+/// NOTE: the response signature is not verified, that's up to the clients.
+fn ocsp_parse<'a>(data: &'a [u8], cert: &Certificate) -> Result<(u8, Option<OCSPSingleResponse<'a>>)> {
+    let Some((status, response)) = ocsp_single_response(data) else {
+        bail!("Unable to parse OCSP response");
+    };
+    if response.as_ref().is_some_and(|v| cert_serial(cert) != Some(v.serial)) {
+        bail!("OCSP response doesn't refer to the certificate");
+    }
+    Ok((status, response))
+}
+
+/// Checks the OCSP response is successful, good and fresh, returning its expiration.
+fn ocsp_check(status: u8, response: Option<&OCSPSingleResponse>, now: i64) -> Result<Option<i64>> {
+    let Some(response) = response else {
+        bail!("OCSP response status is not successful ({status})");
+    };
+    match response.cert_status {
+        0x80 => {}
+        0xa1 => bail!("OCSP response reports the certificate as revoked"),
+        _ => bail!("OCSP response reports the certificate status as unknown"),
+    }
+    if response.this_update > now + OCSP_CLOCK_SKEW_SECS {
+        bail!("OCSP response is not valid yet");
+    }
+    if response.next_update.is_some_and(|v| v <= now) {
+        bail!("OCSP response is expired");
+    }
+    Ok(response.next_update)
+}
+
+/// Certificate resolver stapling an OCSP response, reloaded from its file when it changes.
+#[derive(Debug)]
+struct OCSPStaplingResolver {
+    key: RwLock<Arc<CertifiedKey>>,
+    path: PathBuf,
+    state: Mutex<OCSPStaplingState>,
+}
+
+#[derive(Debug)]
+struct OCSPStaplingState {
+    checked_at: Instant,
+    modified: Option<SystemTime>,
+    next_update: Option<i64>,
+}
+
+impl OCSPStaplingResolver {
+    fn new(key: CertifiedKey, path: &Path, next_update: Option<i64>) -> Self {
+        Self {
+            key: RwLock::new(Arc::new(key)),
+            path: path.into(),
+            state: Mutex::new(OCSPStaplingState {
+                checked_at: Instant::now(),
+                modified: fs::metadata(path).and_then(|v| v.modified()).ok(),
+                next_update,
+            }),
+        }
+    }
+
+    fn staple(&self, ocsp: Option<Vec<u8>>) {
+        let mut key = self.key.write().unwrap();
+        let mut next = (**key).clone();
+        next.ocsp = ocsp;
+        *key = Arc::new(next);
+    }
+
+    // NOTE: we check the file at most once per `OCSP_REFRESH_INTERVAL`,
+    //       and skip the check entirely if another handshake is already performing it.
+    fn refresh(&self) {
+        let Ok(mut state) = self.state.try_lock() else {
+            return;
+        };
+        if state.checked_at.elapsed() < OCSP_REFRESH_INTERVAL {
+            return;
+        }
+        state.checked_at = Instant::now();
+
+        let now = unix_now();
+        let modified = fs::metadata(&self.path).and_then(|v| v.modified()).ok();
+        if modified != state.modified {
+            state.modified = modified;
+            let cert = self.key.read().unwrap().cert[0].clone();
+            match load_ocsp_response(&self.path, &cert) {
+                Ok((ocsp, Ok(next_update))) => {
+                    if let Some(hours) = next_update.map(|v| (v - now) / 3600) {
+                        if hours < OCSP_EXPIRY_WARNING_HOURS {
+                            log::warn!("OCSP response from {} expires in {hours} hours", self.path.display());
+                        }
+                    }
+                    state.next_update = next_update;
+                    self.staple(Some(ocsp));
+                    return;
+                }
+                Ok((_, Err(err))) | Err(err) => log::warn!("{err}"),
+            }
+        }
+        if state.next_update.is_some_and(|v| v <= now) {
+            log::warn!(
+                "OCSP response from {} is expired, stapling disabled",
+                self.path.display()
+            );
+            state.next_update = None;
+            self.staple(None);
+        }
+    }
+}
+
+impl ResolvesServerCert for OCSPStaplingResolver {
+    fn resolve(&self, _client_hello: ClientHello) -> Option<Arc<CertifiedKey>> {
+        self.refresh();
+        Some(self.key.read().unwrap().clone())
+    }
+}
+
+// NOTE: minimal DER walk, we only need a few fields from certificates and OCSP responses.
+fn der_read(data: &[u8]) -> Option<(u8, &[u8], &[u8])> {
+    let (&tag, rest) = data.split_first()?;
+    let (&len, rest) = rest.split_first()?;
+    let (len, rest) = match len {
+        0..=0x7f => (len as usize, rest),
+        0x81..=0x84 => {
+            let size = (len & 0x7f) as usize;
+            if rest.len() < size {
+                return None;
+            }
+            let (len_bytes, rest) = rest.split_at(size);
+            (len_bytes.iter().fold(0usize, |acc, &v| (acc << 8) | v as usize), rest)
+        }
+        _ => return None,
+    };
+    if rest.len() < len {
+        return None;
+    }
+    let (content, rest) = rest.split_at(len);
+    Some((tag, content, rest))
+}
+
+fn der_time(tag: u8, value: &[u8]) -> Option<i64> {
+    let value = std::str::from_utf8(value).ok()?.strip_suffix('Z')?;
+    let (year, value) = match tag {
+        // UTCTime
+        0x17 => {
+            let year: i64 = value.get(..2)?.parse().ok()?;
+            (if year < 50 { 2000 + year } else { 1900 + year }, &value[2..])
+        }
+        // GeneralizedTime
+        0x18 => (value.get(..4)?.parse().ok()?, &value[4..]),
+        _ => return None,
+    };
+    let field = |idx: usize| -> Option<i64> { value.get(idx..idx + 2)?.parse().ok() };
+    let (month, day, hours, minutes) = (field(0)?, field(2)?, field(4)?, field(6)?);
+    let seconds = field(8).unwrap_or(0);
+    // days from civil, see http://howardhinnant.github.io/date_algorithms.html
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (month + if month > 2 { -3 } else { 9 }) + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146_097 + doe - 719_468;
+    Some(days * 86400 + hours * 3600 + minutes * 60 + seconds)
+}
+
+fn cert_serial<'a>(cert: &'a Certificate) -> Option<&'a [u8]> {
+    let (_, cert, _) = der_read(cert.as_ref())?;
+    let (_, mut tbs, _) = der_read(cert)?;
+    if tbs.first() == Some(&0xa0) {
+        tbs = der_read(tbs)?.2;
+    }
+    let (_, serial, _) = der_read(tbs)?;
+    Some(serial)
+}
+
+pub(crate) fn cert_not_after(cert: &Certificate) -> Option<i64> {
+    let (_, cert, _) = der_read(cert.as_ref())?;
+    let (_, mut tbs, _) = der_read(cert)?;
+    // skip the optional explicit version, the serial number, signature algorithm and issuer
+    if tbs.first() == Some(&0xa0) {
+        tbs = der_read(tbs)?.2;
+    }
+    for _ in 0..3 {
+        tbs = der_read(tbs)?.2;
+    }
+    let (_, validity, _) = der_read(tbs)?;
+    let (_, _, validity) = der_read(validity)?;
+    let (tag, not_after, _) = der_read(validity)?;
+    der_time(tag, not_after)
+}
+
+struct OCSPSingleResponse<'a> {
+    serial: &'a [u8],
+    cert_status: u8,
+    this_update: i64,
+    next_update: Option<i64>,
+}
+
+// NOTE: walks `OCSPResponse -> BasicOCSPResponse -> ResponseData` down to the first `SingleResponse`,
+//       returning the response status along with it (which is missing for unsuccessful responses).
+fn ocsp_single_response(data: &[u8]) -> Option<(u8, Option<OCSPSingleResponse>)> {
+    let (_, response, _) = der_read(data)?;
+    let (tag, status, rest) = der_read(response)?;
+    if tag != 0x0a || status.len() != 1 {
+        return None;
+    }
+    if status[0] != 0 {
+        return Some((status[0], None));
+    }
+    let (_, response_bytes, _) = der_read(rest)?;
+    let (_, response_bytes, _) = der_read(response_bytes)?;
+    // skip the response type
+    let (_, basic, _) = der_read(der_read(response_bytes)?.2)?;
+    let (_, basic, _) = der_read(basic)?;
+    let (_, mut data, _) = der_read(basic)?;
+    // skip the optional explicit version, the responder ID and the production time
+    if data.first() == Some(&0xa0) {
+        data = der_read(data)?.2;
+    }
+    for _ in 0..2 {
+        data = der_read(data)?.2;
+    }
+    let (_, responses, _) = der_read(data)?;
+    let (_, single, _) = der_read(responses)?;
+    let (_, mut cert_id, rest) = der_read(single)?;
+    // skip the hash algorithm, the issuer name and key hashes
+    for _ in 0..3 {
+        cert_id = der_read(cert_id)?.2;
+    }
+    let (_, serial, _) = der_read(cert_id)?;
+    let (cert_status, _, rest) = der_read(rest)?;
+    let (tag, this_update, rest) = der_read(rest)?;
+    let this_update = der_time(tag, this_update)?;
+    let next_update = match der_read(rest) {
+        Some((0xa0, value, _)) => {
+            let (tag, value, _) = der_read(value)?;
+            Some(der_time(tag, value)?)
+        }
+        _ => None,
+    };
+    Some((
+        0,
+        Some(OCSPSingleResponse {
+            serial,
+            cert_status,
+            this_update,
+            next_update,
+        }),
+    ))
+}
+
+/// Checks the TLS material the workers will load, returning warnings about it.
+#[pyfunction]
+#[pyo3(signature = (cert, key=None, key_password=None, http_mode="auto", settings=None, acme_storage=None))]
+fn validate_tls_config(
+    py: Python,
+    cert: TLSMaterial,
+    key: Option<TLSMaterial>,
+    key_password: Option<&str>,
+    http_mode: &str,
+    settings: Option<PyObject>,
+    acme_storage: Option<PathBuf>,
+) -> PyResult<Vec<String>> {
+    let opts = worker_tls_config_from_py(py, settings)?;
+    if let Some(storage) = &acme_storage {
+        acme_server_config(storage, http_mode, &opts).map_err(|err| PyValueError::new_err(err.to_string()))?;
+        // NOTE: the ACME client might not have issued the certificate yet
+        if matches!(&cert, TLSMaterial::Path(path) if !Path::new(path).exists()) {
+            return Ok(Vec::new());
+        }
     }
+    server_config(&cert, key.as_ref(), key_password, http_mode, &opts)
+        .map_err(|err| PyValueError::new_err(err.to_string()))?;
+
+    let mut warnings = Vec::new();
+    let now = unix_now();
+    let (certs, _) = load_identity(&cert, key.as_ref(), key_password)?;
+    let name = cert.name();
+    match certs.first().and_then(cert_not_after) {
+        Some(not_after) if not_after <= now => {
+            warnings.push(format!("TLS certificate from {name} is expired"));
+        }
+        Some(not_after) if not_after - now < CERT_EXPIRY_WARNING_DAYS * 86400 => {
+            warnings.push(format!(
+                "TLS certificate from {name} expires in {} days",
+                (not_after - now) / 86400
+            ));
+        }
+        Some(_) => {}
+        None => warnings.push(format!("Unable to read validity period of TLS certificate from {name}")),
+    }
+    if let Some(ocsp_path) = &opts.ocsp_response {
+        match load_ocsp_response(ocsp_path, &certs[0]) {
+            Ok((_, Ok(Some(next_update)))) if next_update - now < OCSP_EXPIRY_WARNING_HOURS * 3600 => {
+                warnings.push(format!(
+                    "OCSP response from {} expires in {} hours",
+                    ocsp_path.display(),
+                    (next_update - now) / 3600
+                ));
+            }
+            Ok((_, Err(err))) => warnings.push(format!("{err}, stapling disabled")),
+            _ => {}
+        }
+    }
+    Ok(warnings)
+}
+
+pub(crate) fn init_pymodule(module: &Bound<PyModule>) -> PyResult<()> {
+    module.add_function(wrap_pyfunction!(validate_tls_config, module)?)?;
+
+    Ok(())
 }
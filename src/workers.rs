@@ -1,6 +1,8 @@
 use pyo3::prelude::*;
 use std::net::TcpListener;
+use std::path::PathBuf;
 use std::sync::Mutex;
+use tokio_tungstenite::tungstenite::protocol::WebSocketConfig;
 
 #[cfg(unix)]
 use std::os::unix::io::FromRawFd;
@@ -8,8 +10,14 @@ use std::os::unix::io::FromRawFd;
 use std::os::windows::io::FromRawSocket;
 
 use super::asgi::serve::ASGIWorker;
+use super::limits::{
+    AdaptiveLimiter, ConcurrencyLimiter, ConnectionFilter, IPNetwork, LoadShedder, RateLimitKey, RateLimiter,
+};
 use super::rsgi::serve::RSGIWorker;
-use super::tls::{load_certs as tls_load_certs, load_private_key as tls_load_pkey};
+use super::tls::{
+    acme_server_config as tls_acme_server_config, cert_to_pem as tls_cert_to_pem, load_identity as tls_load_identity,
+    server_config as tls_server_config, TLSMaterial,
+};
 use super::wsgi::serve::WSGIWorker;
 
 pub(crate) enum WorkerSignals {
@@ -75,6 +83,23 @@ pub(crate) struct HTTP1Config {
     pub keep_alive: bool,
     pub max_buffer_size: usize,
     pub pipeline_flush: bool,
+    pub max_headers: usize,
+    pub header_read_timeout: Option<core::time::Duration>,
+    pub keep_alive_timeout: Option<core::time::Duration>,
+    pub max_requests: Option<usize>,
+}
+
+impl HTTP1Config {
+    pub fn conn_tracker(&self) -> Option<std::sync::Arc<crate::http::HTTP1ConnTracker>> {
+        if self.header_read_timeout.is_none() && self.keep_alive_timeout.is_none() && self.max_requests.is_none() {
+            return None;
+        }
+        Some(crate::http::HTTP1ConnTracker::new(
+            self.header_read_timeout,
+            self.keep_alive_timeout,
+            self.max_requests,
+        ))
+    }
 }
 
 #[derive(Clone)]
@@ -88,8 +113,77 @@ pub(crate) struct HTTP2Config {
     pub max_frame_size: u32,
     pub max_headers_size: u32,
     pub max_send_buffer_size: usize,
+    pub max_pending_accept_reset_streams: usize,
+    pub max_local_error_reset_streams: Option<usize>,
+    pub max_resets_per_second: Option<u32>,
+    pub max_control_frames_per_second: Option<u32>,
+}
+
+impl HTTP2Config {
+    pub fn abuse_limits(&self) -> crate::http::HTTP2AbuseLimits {
+        crate::http::HTTP2AbuseLimits {
+            resets_per_second: self.max_resets_per_second,
+            control_frames_per_second: self.max_control_frames_per_second,
+            header_block_size: self.max_headers_size,
+        }
+    }
+}
+
+#[derive(Clone)]
+pub(crate) struct ConnectionConfig {
+    pub max_per_ip: Option<usize>,
+    pub allow: Vec<IPNetwork>,
+    pub deny: Vec<IPNetwork>,
 }
 
+#[derive(Clone)]
+pub(crate) struct RateLimitConfig {
+    pub rate: f64,
+    pub burst: u32,
+    pub key: RateLimitKey,
+    pub paths: Vec<String>,
+    pub shared_memory: Option<String>,
+    pub seed: u64,
+}
+
+#[derive(Clone)]
+pub(crate) struct LoadSheddingConfig {
+    pub queue_timeout: core::time::Duration,
+    pub retry_after: u64,
+}
+
+#[derive(Clone)]
+pub(crate) struct ConcurrencyLimitConfig {
+    pub max_requests: usize,
+    pub queue_size: usize,
+    pub queue_timeout: Option<core::time::Duration>,
+}
+
+#[derive(Clone)]
+pub(crate) struct AdaptiveConcurrencyConfig {
+    pub initial_limit: usize,
+    pub min_limit: usize,
+    pub max_limit: usize,
+    pub smoothing: f64,
+}
+
+#[derive(Clone)]
+pub(crate) struct WebsocketConfig {
+    pub max_message_size: Option<usize>,
+    pub max_frame_size: Option<usize>,
+}
+
+#[derive(Clone)]
+pub(crate) struct TLSConfig {
+    pub min_version: String,
+    pub ciphers: Option<Vec<String>>,
+    pub session_tickets: bool,
+    pub session_cache_size: usize,
+    pub keylog: bool,
+    pub ocsp_response: Option<PathBuf>,
+}
+
+#[allow(clippy::struct_excessive_bools)]
 pub(crate) struct WorkerConfig {
     pub id: i32,
     socket_fd: i32,
@@ -99,14 +193,26 @@ pub(crate) struct WorkerConfig {
     pub http_mode: String,
     pub http1_opts: HTTP1Config,
     pub http2_opts: HTTP2Config,
+    connection_opts: ConnectionConfig,
+    rate_limit_opts: Option<RateLimitConfig>,
+    load_shedding_opts: Option<LoadSheddingConfig>,
+    concurrency_opts: Option<ConcurrencyLimitConfig>,
+    adaptive_concurrency_opts: Option<AdaptiveConcurrencyConfig>,
+    strict_requests: bool,
     pub websockets_enabled: bool,
+    pub websockets_opts: WebsocketConfig,
+    pub cancel_on_disconnect: bool,
+    pub stream_buffer_size: usize,
     pub ssl_enabled: bool,
-    ssl_cert: Option<String>,
-    ssl_key: Option<String>,
+    ssl_cert: Option<TLSMaterial>,
+    ssl_key: Option<TLSMaterial>,
     ssl_key_password: Option<String>,
+    ssl_opts: TLSConfig,
+    ssl_acme: Option<PathBuf>,
 }
 
 impl WorkerConfig {
+    #[allow(clippy::fn_params_excessive_bools)]
     pub fn new(
         id: i32,
         socket_fd: i32,
@@ -116,11 +222,22 @@ impl WorkerConfig {
         http_mode: &str,
         http1_opts: HTTP1Config,
         http2_opts: HTTP2Config,
+        connection_opts: ConnectionConfig,
+        rate_limit_opts: Option<RateLimitConfig>,
+        load_shedding_opts: Option<LoadSheddingConfig>,
+        concurrency_opts: Option<ConcurrencyLimitConfig>,
+        adaptive_concurrency_opts: Option<AdaptiveConcurrencyConfig>,
+        strict_requests: bool,
         websockets_enabled: bool,
+        websockets_opts: WebsocketConfig,
+        cancel_on_disconnect: bool,
+        stream_buffer_size: usize,
         ssl_enabled: bool,
-        ssl_cert: Option<&str>,
-        ssl_key: Option<&str>,
+        ssl_cert: Option<TLSMaterial>,
+        ssl_key: Option<TLSMaterial>,
         ssl_key_password: Option<&str>,
+        ssl_opts: TLSConfig,
+        ssl_acme: Option<PathBuf>,
     ) -> Self {
         Self {
             id,
@@ -131,11 +248,22 @@ impl WorkerConfig {
             http_mode: http_mode.into(),
             http1_opts,
             http2_opts,
+            connection_opts,
+            rate_limit_opts,
+            load_shedding_opts,
+            concurrency_opts,
+            adaptive_concurrency_opts,
+            strict_requests,
             websockets_enabled,
+            websockets_opts,
+            cancel_on_disconnect,
+            stream_buffer_size,
             ssl_enabled,
-            ssl_cert: ssl_cert.map(std::convert::Into::into),
-            ssl_key: ssl_key.map(std::convert::Into::into),
+            ssl_cert,
+            ssl_key,
             ssl_key_password: ssl_key_password.map(std::convert::Into::into),
+            ssl_opts,
+            ssl_acme,
         }
     }
 
@@ -153,21 +281,60 @@ impl WorkerConfig {
         listener
     }
 
-    pub fn tls_cfg(&self) -> tls_listener::rustls::rustls::ServerConfig {
-        let mut cfg = tls_listener::rustls::rustls::ServerConfig::builder()
-            .with_no_client_auth()
-            .with_single_cert(
-                tls_load_certs(self.ssl_cert.clone().unwrap()).unwrap(),
-                tls_load_pkey(self.ssl_key.clone().unwrap(), self.ssl_key_password.clone()).unwrap(),
-            )
-            .unwrap();
-        cfg.alpn_protocols = match &self.http_mode[..] {
-            "1" => vec![b"http/1.1".to_vec()],
-            "2" => vec![b"h2".to_vec()],
-            _ => vec![b"h2".to_vec(), b"http/1.1".to_vec()],
-        };
-        cfg
+    // NOTE: the TLS material gets validated in the main process before spawning workers,
+    //       thus failures here would mean files changed in the meantime (eg: during a rotation).
+    pub fn tls_cfg(&self) -> anyhow::Result<tls_listener::rustls::rustls::ServerConfig> {
+        if let Some(storage) = &self.ssl_acme {
+            return tls_acme_server_config(storage, &self.http_mode, &self.ssl_opts);
+        }
+        tls_server_config(
+            self.ssl_cert.as_ref().unwrap(),
+            self.ssl_key.as_ref(),
+            self.ssl_key_password.as_deref(),
+            &self.http_mode,
+            &self.ssl_opts,
+        )
     }
+
+    pub fn service_config(&self) -> std::sync::Arc<ServiceConfig> {
+        std::sync::Arc::new(ServiceConfig {
+            ws_config: WebSocketConfig::default()
+                .max_message_size(self.websockets_opts.max_message_size)
+                .max_frame_size(self.websockets_opts.max_frame_size),
+            cancel_on_disconnect: self.cancel_on_disconnect,
+            strict_requests: self.strict_requests,
+            stream_buffer_size: self.stream_buffer_size,
+            tls_server_cert: match (self.ssl_enabled, &self.ssl_cert) {
+                (true, Some(cert)) => tls_load_identity(cert, self.ssl_key.as_ref(), self.ssl_key_password.as_deref())
+                    .ok()
+                    .and_then(|(certs, _)| certs.first().map(|cert| tls_cert_to_pem(cert).into())),
+                _ => None,
+            },
+            connection_filter: ConnectionFilter::new(self.connection_opts.clone()),
+            // NOTE: the shared memory segment is created by the main process before spawning workers,
+            //       thus failures here would mean it was removed in the meantime.
+            rate_limiter: self
+                .rate_limit_opts
+                .clone()
+                .map(|config| RateLimiter::new(config).unwrap()),
+            load_shedder: self.load_shedding_opts.clone().map(LoadShedder::new),
+            concurrency_limiter: self.concurrency_opts.clone().map(ConcurrencyLimiter::new),
+            adaptive_limiter: self.adaptive_concurrency_opts.clone().map(AdaptiveLimiter::new),
+        })
+    }
+}
+
+pub(crate) struct ServiceConfig {
+    pub ws_config: WebSocketConfig,
+    pub cancel_on_disconnect: bool,
+    pub strict_requests: bool,
+    pub stream_buffer_size: usize,
+    pub tls_server_cert: Option<std::sync::Arc<str>>,
+    pub connection_filter: Option<std::sync::Arc<ConnectionFilter>>,
+    pub rate_limiter: Option<std::sync::Arc<RateLimiter>>,
+    pub load_shedder: Option<std::sync::Arc<LoadShedder>>,
+    pub concurrency_limiter: Option<std::sync::Arc<ConcurrencyLimiter>>,
+    pub adaptive_limiter: Option<std::sync::Arc<AdaptiveLimiter>>,
 }
 
 // pub(crate) struct Worker<R>
@@ -200,33 +367,100 @@ where
 }
 
 macro_rules! build_service {
-    ($local_addr:expr, $remote_addr:expr, $callback_wrapper:expr, $rt:expr, $target:expr, $proto:expr) => {
-        hyper::service::service_fn(move |request: crate::http::HTTPRequest| {
+    ($local_addr:expr, $remote_addr:expr, $tls_info:expr, $interim:expr, $shed:expr, $callback_wrapper:expr, $rt:expr, $svc_cfg:expr, $target:expr, $proto:expr) => {
+        hyper::service::service_fn(move |mut request: crate::http::HTTPRequest| {
             let callback_wrapper = $callback_wrapper.clone();
             let rth = $rt.clone();
+            let svc_cfg = $svc_cfg.clone();
+            let shed = $shed.clone();
+            if let Some(tls_info) = &$tls_info {
+                request.extensions_mut().insert(tls_info.clone());
+            }
+            // NOTE: informational responses can only be sent on HTTP/1.1 connections
+            let interim = $interim
+                .as_ref()
+                .filter(|_| request.version() == hyper::Version::HTTP_11)
+                .cloned();
+            if let Some(interim) = &interim {
+                interim.request_started();
+                request.extensions_mut().insert(interim.clone());
+            }
+
+            let handle = async move {
+                if let Some(shed) = shed {
+                    if let Some(limiter) = svc_cfg.adaptive_limiter.as_ref() {
+                        limiter.reject();
+                    }
+                    // NOTE: shut down the connection, so the client retries on a new one
+                    shed.cancel();
+                    return Ok(svc_cfg.load_shedder.as_ref().unwrap().response(&request, $remote_addr));
+                }
+                if let Some(res) = svc_cfg
+                    .rate_limiter
+                    .as_ref()
+                    .and_then(|limiter| limiter.check(&request, $remote_addr))
+                {
+                    return Ok(res);
+                }
+                let permit = match svc_cfg.concurrency_limiter.as_ref() {
+                    Some(limiter) => match limiter.acquire($remote_addr).await {
+                        Some(permit) => Some(permit),
+                        None => return Ok(crate::http::response_503()),
+                    },
+                    None => None,
+                };
+                let adaptive_request = svc_cfg
+                    .adaptive_limiter
+                    .as_ref()
+                    .map(crate::limits::AdaptiveLimiter::track);
+                let res = $target(
+                    rth,
+                    callback_wrapper,
+                    svc_cfg,
+                    $local_addr,
+                    $remote_addr,
+                    request,
+                    $proto,
+                )
+                .await;
+                if let Some(request) = adaptive_request {
+                    request.finish();
+                }
+                Ok::<_, anyhow::Error>(match permit {
+                    Some(permit) => crate::http::hold_until_sent(res, permit),
+                    None => res,
+                })
+            };
 
             async move {
-                Ok::<_, anyhow::Error>($target(rth, callback_wrapper, $local_addr, $remote_addr, request, $proto).await)
+                let res = handle.await;
+                if let Some(interim) = interim {
+                    interim.response_started();
+                }
+                res
             }
         })
     };
 }
 
 macro_rules! handle_connection_loop {
-    ($tcp_listener:expr, $quit_signal:expr, $backpressure:expr, $inner:expr) => {
-        let tcp_listener = tokio::net::TcpListener::from_std($tcp_listener).unwrap();
+    ($tcp_listener:expr, $conn_filter:expr, $load_shedder:expr, $adaptive_limiter:expr, $quit_signal:expr, $backpressure:expr, $inner:expr) => {
+        let tcp_listener = crate::limits::FilteredListener::new(
+            tokio::net::TcpListener::from_std($tcp_listener).unwrap(),
+            $conn_filter,
+        );
         let local_addr = tcp_listener.local_addr().unwrap();
         let mut accept_loop = true;
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new($backpressure));
+        let backpressure = crate::limits::Backpressure::new($backpressure, $load_shedder, $adaptive_limiter);
 
         while accept_loop {
-            let semaphore = semaphore.clone();
             tokio::select! {
-                (permit, Ok((stream, remote_addr))) = async {
-                    let permit = semaphore.acquire_owned().await.unwrap();
+                (permit, Ok((stream, (remote_addr, guard)))) = async {
+                    let permit = backpressure.acquire().await;
                     (permit, tcp_listener.accept().await)
                 } => {
-                    $inner(local_addr, remote_addr, stream, permit)
+                    let permit = permit.or_else(|| backpressure.try_acquire());
+                    $inner(local_addr, remote_addr, stream, (permit, guard))
                 },
                 _ = $quit_signal => {
                     accept_loop = false;
@@ -237,21 +471,23 @@ macro_rules! handle_connection_loop {
 }
 
 macro_rules! handle_connection_loop_tls {
-    ($tcp_listener:expr, $tls_config:expr, $quit_signal:expr, $backpressure:expr, $inner:expr) => {
-        let (mut tls_listener, local_addr) = crate::tls::tls_listener($tls_config.into(), $tcp_listener).unwrap();
-        let semaphore = std::sync::Arc::new(tokio::sync::Semaphore::new($backpressure));
+    ($tcp_listener:expr, $conn_filter:expr, $load_shedder:expr, $adaptive_limiter:expr, $tls_config:expr, $quit_signal:expr, $backpressure:expr, $inner:expr) => {
+        let (mut tls_listener, local_addr) =
+            crate::tls::tls_listener($tls_config.into(), $tcp_listener, $conn_filter).unwrap();
+        let backpressure = crate::limits::Backpressure::new($backpressure, $load_shedder, $adaptive_limiter);
         let mut accept_loop = true;
 
         while accept_loop {
-            let semaphore = semaphore.clone();
             tokio::select! {
                 (permit, accept) = async {
-                    let permit = semaphore.acquire_owned().await.unwrap();
+                    let permit = backpressure.acquire().await;
                     (permit, tls_listener.accept().await)
                 } => {
                     match accept {
-                        Ok((stream, remote_addr)) => {
-                            $inner(local_addr, remote_addr, stream, permit)
+                        Ok((stream, _)) if crate::acme::is_challenge_connection(&stream) => {},
+                        Ok((stream, (remote_addr, guard))) => {
+                            let permit = permit.or_else(|| backpressure.try_acquire());
+                            $inner(local_addr, remote_addr, stream, (permit, guard))
                         },
                         Err(err) => {
                             log::info!("TLS handshake failed with {:?}", err);
@@ -267,18 +503,40 @@ macro_rules! handle_connection_loop_tls {
 }
 
 macro_rules! handle_connection_http1 {
-    ($rth:expr, $callback:expr, $spawner:expr, $stream_wrapper:expr, $proto:expr, $http_opts:expr, $target:expr) => {
-        |local_addr, remote_addr, stream, permit| {
+    ($rth:expr, $callback:expr, $svc_cfg:expr, $spawner:expr, $stream_wrapper:expr, $proto:expr, $http_opts:expr, $target:expr) => {
+        |local_addr, remote_addr, stream, permit: (Option<tokio::sync::OwnedSemaphorePermit>, _)| {
+            // NOTE: on HTTP/1 the shed response closes the connection on its own
+            let shed = permit.0.is_none().then(tokio_util::sync::CancellationToken::new);
             let rth = $rth.clone();
             let callback_wrapper = $callback.clone();
+            let svc_cfg = $svc_cfg.clone();
+            let tls_info = crate::tls::TLSInfoProvider::tls_info(&stream, svc_cfg.tls_server_cert.as_ref());
+            let interim = crate::http::InterimSender::default();
+            let tracker = $http_opts.conn_tracker();
             $spawner(async move {
-                let svc =
-                    crate::workers::build_service!(local_addr, remote_addr, callback_wrapper, rth, $target, $proto);
+                // NOTE: informational responses need hyper to flush every response
+                let svc_interim = (!$http_opts.pipeline_flush).then(|| interim.clone());
+                let svc = crate::workers::build_service!(
+                    local_addr,
+                    remote_addr,
+                    tls_info,
+                    svc_interim,
+                    shed,
+                    callback_wrapper,
+                    rth,
+                    svc_cfg,
+                    $target,
+                    $proto
+                );
                 let mut conn = hyper::server::conn::http1::Builder::new();
                 conn.keep_alive($http_opts.keep_alive);
                 conn.max_buf_size($http_opts.max_buffer_size);
                 conn.pipeline_flush($http_opts.pipeline_flush);
-                let _ = conn.serve_connection($stream_wrapper(stream), svc).await;
+                conn.max_headers($http_opts.max_headers);
+                let svc = crate::http::HTTP1TrackedService::new(svc, tracker.clone());
+                let stream = crate::http::HTTP1TrackedIO::new(stream, tracker.clone());
+                let conn = conn.serve_connection($stream_wrapper(crate::http::InterimIO::new(stream, interim)), svc);
+                crate::workers::serve_tracked_connection!(conn, tracker);
                 drop(permit);
             });
         }
@@ -286,36 +544,123 @@ macro_rules! handle_connection_http1 {
 }
 
 macro_rules! handle_connection_http1_upgrades {
-    ($rth:expr, $callback:expr, $spawner:expr, $stream_wrapper:expr, $proto:expr, $http_opts:expr, $target:expr) => {
-        |local_addr, remote_addr, stream, permit| {
+    ($rth:expr, $callback:expr, $svc_cfg:expr, $spawner:expr, $stream_wrapper:expr, $proto:expr, $http_opts:expr, $target:expr) => {
+        |local_addr, remote_addr, stream, permit: (Option<tokio::sync::OwnedSemaphorePermit>, _)| {
+            // NOTE: on HTTP/1 the shed response closes the connection on its own
+            let shed = permit.0.is_none().then(tokio_util::sync::CancellationToken::new);
             let rth = $rth.clone();
             let callback_wrapper = $callback.clone();
+            let svc_cfg = $svc_cfg.clone();
+            let tls_info = crate::tls::TLSInfoProvider::tls_info(&stream, svc_cfg.tls_server_cert.as_ref());
+            let interim = crate::http::InterimSender::default();
+            let tracker = $http_opts.conn_tracker();
             $spawner(async move {
-                let svc =
-                    crate::workers::build_service!(local_addr, remote_addr, callback_wrapper, rth, $target, $proto);
+                // NOTE: informational responses need hyper to flush every response
+                let svc_interim = (!$http_opts.pipeline_flush).then(|| interim.clone());
+                let svc = crate::workers::build_service!(
+                    local_addr,
+                    remote_addr,
+                    tls_info,
+                    svc_interim,
+                    shed,
+                    callback_wrapper,
+                    rth,
+                    svc_cfg,
+                    $target,
+                    $proto
+                );
                 let mut conn = hyper::server::conn::http1::Builder::new();
                 conn.keep_alive($http_opts.keep_alive);
                 conn.max_buf_size($http_opts.max_buffer_size);
                 conn.pipeline_flush($http_opts.pipeline_flush);
-                let _ = conn
-                    .serve_connection($stream_wrapper(stream), svc)
-                    .with_upgrades()
-                    .await;
+                conn.max_headers($http_opts.max_headers);
+                let svc = crate::http::HTTP1TrackedService::new(svc, tracker.clone());
+                let stream = crate::http::HTTP1TrackedIO::new(stream, tracker.clone());
+                let conn = conn
+                    .serve_connection($stream_wrapper(crate::http::InterimIO::new(stream, interim)), svc)
+                    .with_upgrades();
+                crate::workers::serve_tracked_connection!(conn, tracker);
                 drop(permit);
             });
         }
     };
 }
 
+// NOTE: idle connections get closed gracefully, connections stalled reading a request head are dropped.
+macro_rules! serve_tracked_connection {
+    ($conn:expr, $tracker:expr) => {
+        let conn = $conn;
+        match $tracker {
+            Some(tracker) => {
+                tokio::pin!(conn);
+                tokio::select! {
+                    _ = conn.as_mut() => {}
+                    idle = tracker.expired() => {
+                        if idle {
+                            conn.as_mut().graceful_shutdown();
+                            let _ = conn.await;
+                        }
+                    }
+                }
+            }
+            None => {
+                let _ = conn.await;
+            }
+        }
+    };
+}
+
+// NOTE: on abuse we send a GOAWAY to the client, giving in-flight streams a short time to complete.
+macro_rules! serve_guarded_connection {
+    ($conn:expr, $abuse:expr) => {
+        crate::workers::serve_guarded_connection!($conn, $abuse, None::<std::sync::Arc<crate::http::HTTP1ConnTracker>>)
+    };
+    ($conn:expr, $abuse:expr, $tracker:expr) => {
+        let conn = $conn;
+        let tracker = $tracker;
+        tokio::pin!(conn);
+        tokio::select! {
+            _ = conn.as_mut() => {}
+            () = $abuse.cancelled() => {
+                conn.as_mut().graceful_shutdown();
+                let _ = tokio::time::timeout(core::time::Duration::from_secs(5), conn).await;
+            }
+            idle = async { tracker.as_ref().unwrap().expired().await }, if tracker.is_some() => {
+                if idle {
+                    conn.as_mut().graceful_shutdown();
+                    let _ = conn.await;
+                }
+            }
+        }
+    };
+}
+
 macro_rules! handle_connection_http2 {
-    ($rth:expr, $callback:expr, $spawner:expr, $executor_builder:expr, $stream_wrapper:expr, $proto:expr, $http_opts:expr, $target:expr) => {
-        |local_addr, remote_addr, stream, permit| {
+    ($rth:expr, $callback:expr, $svc_cfg:expr, $spawner:expr, $executor_builder:expr, $stream_wrapper:expr, $proto:expr, $http_opts:expr, $target:expr) => {
+        |local_addr, remote_addr, stream, permit: (Option<tokio::sync::OwnedSemaphorePermit>, _)| {
             let rth = $rth.clone();
             let callback_wrapper = $callback.clone();
+            let svc_cfg = $svc_cfg.clone();
+            let tls_info = crate::tls::TLSInfoProvider::tls_info(&stream, svc_cfg.tls_server_cert.as_ref());
+            let abuse_limits = $http_opts.abuse_limits();
+            let abuse = tokio_util::sync::CancellationToken::new();
+            // NOTE: shed connections get shut down like abusive ones, after the first response
+            let shed = permit.0.is_none().then(|| abuse.clone());
             $spawner(async move {
-                let svc =
-                    crate::workers::build_service!(local_addr, remote_addr, callback_wrapper, rth, $target, $proto);
-                let mut conn = hyper::server::conn::http2::Builder::new($executor_builder());
+                let svc = crate::workers::build_service!(
+                    local_addr,
+                    remote_addr,
+                    tls_info,
+                    None::<crate::http::InterimSender>,
+                    shed,
+                    callback_wrapper,
+                    rth,
+                    svc_cfg,
+                    $target,
+                    $proto
+                );
+                let mut conn = hyper::server::conn::http2::Builder::new($executor_builder())
+                    .max_local_error_reset_streams($http_opts.max_local_error_reset_streams);
                 conn.adaptive_window($http_opts.adaptive_window);
                 conn.initial_connection_window_size($http_opts.initial_connection_window_size);
                 conn.initial_stream_window_size($http_opts.initial_stream_window_size);
@@ -325,7 +670,10 @@ macro_rules! handle_connection_http2 {
                 conn.max_frame_size($http_opts.max_frame_size);
                 conn.max_header_list_size($http_opts.max_headers_size);
                 conn.max_send_buf_size($http_opts.max_send_buffer_size);
-                let _ = conn.serve_connection($stream_wrapper(stream), svc).await;
+                conn.max_pending_accept_reset_streams($http_opts.max_pending_accept_reset_streams);
+                let stream = crate::http::HTTP2GuardIO::new(stream, abuse_limits, remote_addr, abuse.clone());
+                let conn = conn.serve_connection($stream_wrapper(stream), svc);
+                crate::workers::serve_guarded_connection!(conn, abuse);
                 drop(permit);
             });
         }
@@ -333,17 +681,38 @@ macro_rules! handle_connection_http2 {
 }
 
 macro_rules! handle_connection_httpa {
-    ($rth:expr, $callback:expr, $spawner:expr, $executor_builder:expr, $conn_method:ident, $stream_wrapper:expr, $proto:expr, $http1_opts:expr, $http2_opts:expr, $target:expr) => {
-        |local_addr, remote_addr, stream, permit| {
+    ($rth:expr, $callback:expr, $svc_cfg:expr, $spawner:expr, $executor_builder:expr, $conn_method:ident, $stream_wrapper:expr, $proto:expr, $http1_opts:expr, $http2_opts:expr, $target:expr) => {
+        |local_addr, remote_addr, stream, permit: (Option<tokio::sync::OwnedSemaphorePermit>, _)| {
             let rth = $rth.clone();
             let callback_wrapper = $callback.clone();
+            let svc_cfg = $svc_cfg.clone();
+            let tls_info = crate::tls::TLSInfoProvider::tls_info(&stream, svc_cfg.tls_server_cert.as_ref());
+            let interim = crate::http::InterimSender::default();
+            let tracker = $http1_opts.conn_tracker();
+            let abuse_limits = $http2_opts.abuse_limits();
+            let abuse = tokio_util::sync::CancellationToken::new();
+            // NOTE: shed connections get shut down like abusive ones, after the first response
+            let shed = permit.0.is_none().then(|| abuse.clone());
             $spawner(async move {
-                let svc =
-                    crate::workers::build_service!(local_addr, remote_addr, callback_wrapper, rth, $target, $proto);
+                // NOTE: informational responses need hyper to flush every response
+                let svc_interim = (!$http1_opts.pipeline_flush).then(|| interim.clone());
+                let svc = crate::workers::build_service!(
+                    local_addr,
+                    remote_addr,
+                    tls_info,
+                    svc_interim,
+                    shed,
+                    callback_wrapper,
+                    rth,
+                    svc_cfg,
+                    $target,
+                    $proto
+                );
                 let mut conn = hyper_util::server::conn::auto::Builder::new($executor_builder());
                 conn.http1().keep_alive($http1_opts.keep_alive);
                 conn.http1().max_buf_size($http1_opts.max_buffer_size);
                 conn.http1().pipeline_flush($http1_opts.pipeline_flush);
+                conn.http1().max_headers($http1_opts.max_headers);
                 conn.http2().adaptive_window($http2_opts.adaptive_window);
                 conn.http2()
                     .initial_connection_window_size($http2_opts.initial_connection_window_size);
@@ -355,7 +724,15 @@ macro_rules! handle_connection_httpa {
                 conn.http2().max_frame_size($http2_opts.max_frame_size);
                 conn.http2().max_header_list_size($http2_opts.max_headers_size);
                 conn.http2().max_send_buf_size($http2_opts.max_send_buffer_size);
-                let _ = conn.$conn_method($stream_wrapper(stream), svc).await;
+                conn.http2()
+                    .max_pending_accept_reset_streams($http2_opts.max_pending_accept_reset_streams);
+                let svc = crate::http::HTTP1TrackedService::new(svc, tracker.clone());
+                let stream = crate::http::HTTP1TrackedIO::new(
+                    crate::http::HTTP2GuardIO::new(stream, abuse_limits, remote_addr, abuse.clone()),
+                    tracker.clone(),
+                );
+                let conn = conn.$conn_method($stream_wrapper(crate::http::InterimIO::new(stream, interim)), svc);
+                crate::workers::serve_guarded_connection!(conn, abuse, tracker);
                 drop(permit);
             });
         }
@@ -371,6 +748,7 @@ macro_rules! loop_match {
         $backpressure:expr,
         $rth:expr,
         $callback_wrapper:expr,
+        $svc_cfg:expr,
         $spawner:expr,
         $executor:expr,
         $http1_opts:expr,
@@ -382,11 +760,15 @@ macro_rules! loop_match {
             ("auto", true) => {
                 crate::workers::handle_connection_loop!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_httpa!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         $executor,
                         serve_connection_with_upgrades,
@@ -401,11 +783,15 @@ macro_rules! loop_match {
             ("auto", false) => {
                 crate::workers::handle_connection_loop!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_httpa!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         $executor,
                         serve_connection,
@@ -420,11 +806,15 @@ macro_rules! loop_match {
             ("1", true) => {
                 crate::workers::handle_connection_loop!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_http1_upgrades!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         hyper_util::rt::TokioIo::new,
                         "http",
@@ -436,11 +826,15 @@ macro_rules! loop_match {
             ("1", false) => {
                 crate::workers::handle_connection_loop!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_http1!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         hyper_util::rt::TokioIo::new,
                         "http",
@@ -452,11 +846,15 @@ macro_rules! loop_match {
             ("2", _) => {
                 crate::workers::handle_connection_loop!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_http2!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         $executor,
                         $http2_stream_wrapper,
@@ -481,6 +879,7 @@ macro_rules! loop_match_tls {
         $backpressure:expr,
         $rth:expr,
         $callback_wrapper:expr,
+        $svc_cfg:expr,
         $spawner:expr,
         $executor:expr,
         $http1_opts:expr,
@@ -492,12 +891,16 @@ macro_rules! loop_match_tls {
             ("auto", true) => {
                 crate::workers::handle_connection_loop_tls!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $tls_config,
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_httpa!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         $executor,
                         serve_connection_with_upgrades,
@@ -512,12 +915,16 @@ macro_rules! loop_match_tls {
             ("auto", false) => {
                 crate::workers::handle_connection_loop_tls!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $tls_config,
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_httpa!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         $executor,
                         serve_connection,
@@ -532,12 +939,16 @@ macro_rules! loop_match_tls {
             ("1", true) => {
                 crate::workers::handle_connection_loop_tls!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $tls_config,
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_http1_upgrades!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         hyper_util::rt::TokioIo::new,
                         "https",
@@ -549,12 +960,16 @@ macro_rules! loop_match_tls {
             ("1", false) => {
                 crate::workers::handle_connection_loop_tls!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $tls_config,
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_http1!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         hyper_util::rt::TokioIo::new,
                         "https",
@@ -566,12 +981,16 @@ macro_rules! loop_match_tls {
             ("2", _) => {
                 crate::workers::handle_connection_loop_tls!(
                     $tcp_listener,
+                    $svc_cfg.connection_filter.clone(),
+                    $svc_cfg.load_shedder.as_ref(),
+                    $svc_cfg.adaptive_limiter.as_ref(),
                     $tls_config,
                     $pyrx.changed(),
                     $backpressure,
                     crate::workers::handle_connection_http2!(
                         $rth,
                         $callback_wrapper,
+                        $svc_cfg,
                         $spawner,
                         $executor,
                         $http2_stream_wrapper,
@@ -605,6 +1024,7 @@ macro_rules! serve_rth {
             let http1_opts = self.config.http1_opts.clone();
             let http2_opts = self.config.http2_opts.clone();
             let backpressure = self.config.backpressure.clone();
+            let svc_cfg = self.config.service_config();
             let callback_wrapper = std::sync::Arc::new(callback);
 
             let rt = crate::runtime::init_runtime_mt(
@@ -640,6 +1060,7 @@ macro_rules! serve_rth {
                     backpressure,
                     rth,
                     callback_wrapper,
+                    svc_cfg,
                     tokio::spawn,
                     hyper_util::rt::TokioExecutor::new,
                     http1_opts,
@@ -685,7 +1106,18 @@ macro_rules! serve_rth_ssl {
             let http1_opts = self.config.http1_opts.clone();
             let http2_opts = self.config.http2_opts.clone();
             let backpressure = self.config.backpressure.clone();
-            let tls_cfg = self.config.tls_cfg();
+            let svc_cfg = self.config.service_config();
+            let tls_cfg = match self.config.tls_cfg() {
+                Ok(cfg) => cfg,
+                Err(err) => {
+                    log::error!(
+                        "Unable to load TLS configuration for worker-{}: {}",
+                        worker_id,
+                        err
+                    );
+                    std::process::exit(1);
+                }
+            };
             // let callback_wrapper = crate::callbacks::CallbackWrapper::new(callback, event_loop.clone(), context);
             let callback_wrapper = std::sync::Arc::new(callback);
 
@@ -723,6 +1155,7 @@ macro_rules! serve_rth_ssl {
                     backpressure,
                     rth,
                     callback_wrapper,
+                    svc_cfg,
                     tokio::spawn,
                     hyper_util::rt::TokioExecutor::new,
                     http1_opts,
@@ -753,6 +1186,7 @@ macro_rules! serve_wth_inner {
         // let callback_wrapper = crate::callbacks::CallbackWrapper::new($callback, $event_loop.clone(), $context);
         let callback_wrapper = std::sync::Arc::new($callback);
         let py_loop = std::sync::Arc::new($event_loop.clone().unbind());
+        let svc_cfg = $self.config.service_config();
 
         for thread_id in 0..$self.config.threads {
             log::info!("Started worker-{} runtime-{}", $wid, thread_id + 1);
@@ -765,6 +1199,7 @@ macro_rules! serve_wth_inner {
             let blocking_threads = $self.config.blocking_threads.clone();
             let backpressure = $self.config.backpressure.clone();
             let callback_wrapper = callback_wrapper.clone();
+            let svc_cfg = svc_cfg.clone();
             let py_loop = py_loop.clone();
             let mut srx = $srx.clone();
 
@@ -782,6 +1217,7 @@ macro_rules! serve_wth_inner {
                         backpressure,
                         rth,
                         callback_wrapper,
+                        svc_cfg,
                         tokio::task::spawn_local,
                         crate::workers::WorkerExecutor::new,
                         http1_opts,
@@ -867,6 +1303,14 @@ macro_rules! serve_wth_ssl_inner {
         // let callback_wrapper = crate::callbacks::CallbackWrapper::new($callback, $event_loop.clone(), $context);
         let callback_wrapper = std::sync::Arc::new($callback);
         let py_loop = std::sync::Arc::new($event_loop.clone().unbind());
+        let svc_cfg = $self.config.service_config();
+        let tls_cfg = match $self.config.tls_cfg() {
+            Ok(cfg) => cfg,
+            Err(err) => {
+                log::error!("Unable to load TLS configuration for worker-{}: {}", $wid, err);
+                std::process::exit(1);
+            }
+        };
 
         for thread_id in 0..$self.config.threads {
             log::info!("Started worker-{} runtime-{}", $wid, thread_id + 1);
@@ -876,10 +1320,11 @@ macro_rules! serve_wth_ssl_inner {
             let http_upgrades = $self.config.websockets_enabled;
             let http1_opts = $self.config.http1_opts.clone();
             let http2_opts = $self.config.http2_opts.clone();
-            let tls_cfg = $self.config.tls_cfg();
+            let tls_cfg = tls_cfg.clone();
             let blocking_threads = $self.config.blocking_threads.clone();
             let backpressure = $self.config.backpressure.clone();
             let callback_wrapper = callback_wrapper.clone();
+            let svc_cfg = svc_cfg.clone();
             let py_loop = py_loop.clone();
             let mut srx = $srx.clone();
 
@@ -898,6 +1343,7 @@ macro_rules! serve_wth_ssl_inner {
                         backpressure,
                         rth,
                         callback_wrapper,
+                        svc_cfg,
                         tokio::task::spawn_local,
                         crate::workers::WorkerExecutor::new,
                         http1_opts,
@@ -980,8 +1426,10 @@ pub(crate) use handle_connection_loop;
 pub(crate) use handle_connection_loop_tls;
 pub(crate) use loop_match;
 pub(crate) use loop_match_tls;
+pub(crate) use serve_guarded_connection;
 pub(crate) use serve_rth;
 pub(crate) use serve_rth_ssl;
+pub(crate) use serve_tracked_connection;
 pub(crate) use serve_wth;
 pub(crate) use serve_wth_inner;
 pub(crate) use serve_wth_ssl;
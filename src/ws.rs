@@ -1,3 +1,4 @@
+use futures::SinkExt;
 use http_body_util::BodyExt;
 use hyper::{
     header::{HeaderName, HeaderValue, CONNECTION, UPGRADE},
@@ -7,30 +8,84 @@ use hyper::{
 use pin_project::pin_project;
 use std::{
     future::Future,
+    io::Cursor,
     pin::Pin,
+    sync::{atomic, Arc},
     task::{Context, Poll},
 };
-use tokio::sync::mpsc;
+use tokio::{
+    io::{AsyncRead, AsyncReadExt, AsyncWrite, ReadBuf, ReadHalf, WriteHalf},
+    sync::{mpsc, Mutex as AsyncMutex},
+};
 use tokio_tungstenite::{
     tungstenite::{
-        error::ProtocolError,
+        error::{CapacityError, ProtocolError},
         handshake::derive_accept_key,
-        protocol::{Role, WebSocketConfig},
+        protocol::{
+            frame::{
+                coding::{CloseCode, Control as OpControl, Data as OpData, OpCode},
+                CloseFrame, Frame, FrameHeader, Utf8Bytes,
+            },
+            Role, WebSocketConfig,
+        },
         Error as TungsteniteError, Message,
     },
     WebSocketStream,
 };
+use tokio_util::bytes::{Buf, Bytes, BytesMut};
 
 use super::http::HTTPResponse;
 use super::utils::header_contains_value;
 
-pub(crate) type WSStream = WebSocketStream<hyper_util::rt::TokioIo<hyper::upgrade::Upgraded>>;
-pub(crate) type WSRxStream = futures::stream::SplitStream<WSStream>;
-pub(crate) type WSTxStream = futures::stream::SplitSink<WSStream, Message>;
+type WSIo = hyper_util::rt::TokioIo<hyper::upgrade::Upgraded>;
+
+const WS_READ_CHUNK_SIZE: usize = 8 * 1024;
+
+const WS_FRAGMENT_NONE: u8 = 0;
+const WS_FRAGMENT_BYTES: u8 = 1;
+const WS_FRAGMENT_STR: u8 = 2;
+
+/// Tracks the type of the fragmented message being sent over a websocket, if any.
+///
+/// The state should only be read and updated while holding the sending half of the stream,
+/// and committed once the frame has been sent.
+pub(crate) struct WSFragments(atomic::AtomicU8);
+
+impl WSFragments {
+    pub fn new() -> Self {
+        Self(WS_FRAGMENT_NONE.into())
+    }
+
+    pub fn pending(&self) -> bool {
+        self.0.load(atomic::Ordering::Relaxed) != WS_FRAGMENT_NONE
+    }
+
+    /// Builds the frame for the given fragment, along with the state to commit once it gets sent.
+    /// Returns `None` when the fragment doesn't match the type of the message in progress.
+    pub fn frame(&self, message: Message, fin: bool) -> Option<(Frame, u8)> {
+        let (kind, data) = match message {
+            Message::Binary(data) => (WS_FRAGMENT_BYTES, data),
+            Message::Text(data) => (WS_FRAGMENT_STR, data.into()),
+            _ => return None,
+        };
+        let opcode = match self.0.load(atomic::Ordering::Relaxed) {
+            WS_FRAGMENT_NONE if kind == WS_FRAGMENT_BYTES => OpData::Binary,
+            WS_FRAGMENT_NONE => OpData::Text,
+            current if current == kind => OpData::Continue,
+            _ => return None,
+        };
+        let next = if fin { WS_FRAGMENT_NONE } else { kind };
+        Some((Frame::message(data, OpCode::Data(opcode), fin), next))
+    }
+
+    pub fn commit(&self, state: u8) {
+        self.0.store(state, atomic::Ordering::Relaxed);
+    }
+}
 
 #[pin_project]
 #[derive(Debug)]
-pub struct HyperWebsocket {
+pub(crate) struct HyperWebsocket {
     #[pin]
     inner: hyper::upgrade::OnUpgrade,
     config: Option<WebSocketConfig>,
@@ -49,16 +104,278 @@ impl Future for HyperWebsocket {
         let upgraded = upgraded.map_err(|_| TungsteniteError::Protocol(ProtocolError::HandshakeIncomplete))?;
 
         let io = hyper_util::rt::TokioIo::new(upgraded);
-        let stream = WebSocketStream::from_raw_socket(io, Role::Server, this.config.take());
+        let config = this.config.take().unwrap_or_default();
+        let (rx, tx) = tokio::io::split(io);
+        let stream = WebSocketStream::from_raw_socket(WSWriter(tx), Role::Server, Some(config));
         tokio::pin!(stream);
 
         match stream.as_mut().poll(cx) {
             Poll::Pending => unreachable!(),
-            Poll::Ready(x) => Poll::Ready(Ok(x)),
+            Poll::Ready(x) => Poll::Ready(Ok(WSStream {
+                rx,
+                tx: WSTxStream(Arc::new(AsyncMutex::new(x))),
+                config,
+            })),
+        }
+    }
+}
+
+/// An accepted websocket connection, to be split into its receiving and sending halves.
+pub(crate) struct WSStream {
+    rx: ReadHalf<WSIo>,
+    tx: WSTxStream,
+    config: WebSocketConfig,
+}
+
+impl WSStream {
+    pub fn split(self) -> (WSTxStream, WSRxStream) {
+        let rx = WSRxStream {
+            io: self.rx,
+            tx: self.tx.clone(),
+            buffer: BytesMut::new(),
+            max_frame_size: self.config.max_frame_size,
+            max_message_size: self.config.max_message_size,
+            message: None,
+            utf8: Vec::new(),
+            closed: false,
+        };
+        (self.tx, rx)
+    }
+}
+
+// NOTE: tungstenite only writes frames on the sending half, as we never poll it for
+//       incoming messages: reading from it would just end the stream.
+struct WSWriter(WriteHalf<WSIo>);
+
+impl AsyncRead for WSWriter {
+    fn poll_read(self: Pin<&mut Self>, _cx: &mut Context<'_>, _buf: &mut ReadBuf<'_>) -> Poll<std::io::Result<()>> {
+        Poll::Ready(Ok(()))
+    }
+}
+
+impl AsyncWrite for WSWriter {
+    fn poll_write(mut self: Pin<&mut Self>, cx: &mut Context<'_>, buf: &[u8]) -> Poll<std::io::Result<usize>> {
+        Pin::new(&mut self.0).poll_write(cx, buf)
+    }
+
+    fn poll_flush(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_flush(cx)
+    }
+
+    fn poll_shutdown(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<std::io::Result<()>> {
+        Pin::new(&mut self.0).poll_shutdown(cx)
+    }
+}
+
+/// The sending half of a websocket, shared with the receiving one to answer pings and close frames.
+#[derive(Clone)]
+pub(crate) struct WSTxStream(Arc<AsyncMutex<WebSocketStream<WSWriter>>>);
+
+impl WSTxStream {
+    pub async fn send(&self, message: Message) -> Result<(), TungsteniteError> {
+        self.0.lock().await.send(message).await
+    }
+
+    pub async fn close(&self) -> Result<(), TungsteniteError> {
+        SinkExt::close(&mut *self.0.lock().await).await
+    }
+}
+
+/// The receiving half of a websocket.
+///
+/// Unlike tungstenite, it reads the stream frame by frame, so applications can consume
+/// large messages as a sequence of fragments instead of waiting for the whole message.
+pub(crate) struct WSRxStream {
+    io: ReadHalf<WSIo>,
+    tx: WSTxStream,
+    buffer: BytesMut,
+    max_frame_size: Option<usize>,
+    max_message_size: Option<usize>,
+    message: Option<OpData>,
+    utf8: Vec<u8>,
+    closed: bool,
+}
+
+impl WSRxStream {
+    async fn read_frame(&mut self) -> Result<Option<Frame>, TungsteniteError> {
+        loop {
+            let mut cursor = Cursor::new(&self.buffer[..]);
+            if let Some((header, size)) = FrameHeader::parse(&mut cursor)? {
+                let size = usize::try_from(size).unwrap_or(usize::MAX);
+                if let Some(max_size) = self.max_frame_size.filter(|v| size > *v) {
+                    return Err(CapacityError::MessageTooLong { size, max_size }.into());
+                }
+                let offset = usize::try_from(cursor.position()).unwrap_or(usize::MAX);
+                if self.buffer.len() - offset >= size {
+                    self.buffer.advance(offset);
+                    let mut payload = self.buffer.split_to(size);
+                    let Some(mask) = header.mask else {
+                        return Err(ProtocolError::UnmaskedFrameFromClient.into());
+                    };
+                    for (idx, byte) in payload.iter_mut().enumerate() {
+                        *byte ^= mask[idx % 4];
+                    }
+                    return Ok(Some(Frame::from_payload(header, payload.freeze())));
+                }
+                self.buffer.reserve(offset + size - self.buffer.len());
+            } else {
+                self.buffer.reserve(WS_READ_CHUNK_SIZE);
+            }
+            if self.io.read_buf(&mut self.buffer).await? == 0 {
+                return Ok(None);
+            }
+        }
+    }
+
+    // NOTE: control frames are handled here, replying to pings and close frames,
+    //       so only data frames and the closing message reach the caller.
+    async fn read_data(&mut self) -> Option<Result<WSData, TungsteniteError>> {
+        if self.closed {
+            return None;
+        }
+        loop {
+            let frame = match self.read_frame().await {
+                Ok(Some(frame)) => frame,
+                Ok(None) => return None,
+                Err(err) => return Some(Err(self.fail(err).await)),
+            };
+            let header = frame.header();
+            if header.rsv1 || header.rsv2 || header.rsv3 {
+                return Some(Err(self.fail(ProtocolError::NonZeroReservedBits.into()).await));
+            }
+            let fin = header.is_final;
+            match header.opcode {
+                OpCode::Control(_) if !fin => {
+                    return Some(Err(self.fail(ProtocolError::FragmentedControlFrame.into()).await));
+                }
+                OpCode::Control(_) if frame.len() > 125 => {
+                    return Some(Err(self.fail(ProtocolError::ControlFrameTooBig.into()).await));
+                }
+                OpCode::Control(OpControl::Ping) => {
+                    let _ = self.tx.send(Message::Pong(frame.into_payload())).await;
+                }
+                OpCode::Control(OpControl::Pong) => {}
+                OpCode::Control(OpControl::Close) => {
+                    self.closed = true;
+                    let frame = close_frame(frame.into_payload());
+                    let _ = self.tx.send(Message::Close(frame.clone())).await;
+                    return Some(Ok(WSData::Close(frame)));
+                }
+                OpCode::Control(OpControl::Reserved(opcode)) => {
+                    return Some(Err(self
+                        .fail(ProtocolError::UnknownControlFrameType(opcode).into())
+                        .await));
+                }
+                OpCode::Data(OpData::Reserved(opcode)) => {
+                    return Some(Err(self.fail(ProtocolError::UnknownDataFrameType(opcode).into()).await));
+                }
+                OpCode::Data(opcode) => {
+                    let kind = match (opcode, self.message) {
+                        (OpData::Continue, Some(kind)) => kind,
+                        (OpData::Continue, None) => {
+                            return Some(Err(self.fail(ProtocolError::UnexpectedContinueFrame.into()).await));
+                        }
+                        (_, Some(kind)) => {
+                            return Some(Err(self.fail(ProtocolError::ExpectedFragment(kind).into()).await));
+                        }
+                        (kind, None) => kind,
+                    };
+                    self.message = if fin { None } else { Some(kind) };
+                    return Some(Ok(WSData::Frame(kind, frame.into_payload(), fin)));
+                }
+            }
+        }
+    }
+
+    // NOTE: on errors we start the closing handshake and stop reading,
+    //       as the stream cannot be trusted anymore.
+    async fn fail(&mut self, err: TungsteniteError) -> TungsteniteError {
+        self.closed = true;
+        let code = match err {
+            TungsteniteError::Capacity(_) => CloseCode::Size,
+            TungsteniteError::Utf8 => CloseCode::Invalid,
+            _ => CloseCode::Protocol,
+        };
+        let frame = CloseFrame {
+            code,
+            reason: Utf8Bytes::default(),
+        };
+        let _ = self.tx.send(Message::Close(Some(frame))).await;
+        err
+    }
+
+    /// Receives the next complete message, reassembling fragmented ones within the
+    /// configured message size limit.
+    pub async fn next(&mut self) -> Option<Result<Message, TungsteniteError>> {
+        let mut data = BytesMut::from(&std::mem::take(&mut self.utf8)[..]);
+        loop {
+            let (kind, payload, fin) = match self.read_data().await? {
+                Ok(WSData::Frame(kind, payload, fin)) => (kind, payload, fin),
+                Ok(WSData::Close(frame)) => return Some(Ok(Message::Close(frame))),
+                Err(err) => return Some(Err(err)),
+            };
+            data.extend_from_slice(&payload);
+            if let Some(max_size) = self.max_message_size.filter(|v| data.len() > *v) {
+                let err = CapacityError::MessageTooLong {
+                    size: data.len(),
+                    max_size,
+                };
+                return Some(Err(self.fail(err.into()).await));
+            }
+            if fin {
+                return Some(match kind {
+                    OpData::Text => match Utf8Bytes::try_from(data.freeze()) {
+                        Ok(text) => Ok(Message::Text(text)),
+                        Err(_) => Err(self.fail(TungsteniteError::Utf8).await),
+                    },
+                    _ => Ok(Message::Binary(data.freeze())),
+                });
+            }
+        }
+    }
+
+    /// Receives the next fragment of a message, along with a flag telling whether it is the last one.
+    ///
+    /// Fragments are bounded by the configured frame size limit, while the size of the whole
+    /// message is not checked. Text fragments are split on character boundaries.
+    pub async fn next_fragment(&mut self) -> Option<Result<(Message, bool), TungsteniteError>> {
+        let (kind, payload, fin) = match self.read_data().await? {
+            Ok(WSData::Frame(kind, payload, fin)) => (kind, payload, fin),
+            Ok(WSData::Close(frame)) => return Some(Ok((Message::Close(frame), true))),
+            Err(err) => return Some(Err(err)),
+        };
+        if kind != OpData::Text {
+            return Some(Ok((Message::Binary(payload), fin)));
+        }
+        let mut data = std::mem::take(&mut self.utf8);
+        data.extend_from_slice(&payload);
+        let valid = match std::str::from_utf8(&data) {
+            Ok(_) => data.len(),
+            Err(err) if err.error_len().is_none() && !fin => err.valid_up_to(),
+            Err(_) => return Some(Err(self.fail(TungsteniteError::Utf8).await)),
+        };
+        self.utf8 = data.split_off(valid);
+        match Utf8Bytes::try_from(data) {
+            Ok(text) => Some(Ok((Message::Text(text), fin))),
+            Err(_) => Some(Err(self.fail(TungsteniteError::Utf8).await)),
         }
     }
 }
 
+enum WSData {
+    Frame(OpData, Bytes, bool),
+    Close(Option<CloseFrame>),
+}
+
+fn close_frame(payload: Bytes) -> Option<CloseFrame> {
+    if payload.len() < 2 {
+        return None;
+    }
+    let code = CloseCode::from(u16::from_be_bytes([payload[0], payload[1]]));
+    let reason = Utf8Bytes::try_from(payload.slice(2..)).unwrap_or_default();
+    Some(CloseFrame { code, reason })
+}
+
 pub(crate) struct UpgradeData {
     response: Option<(Builder, mpsc::Sender<HTTPResponse>)>,
 }
@@ -71,23 +388,28 @@ impl UpgradeData {
     }
 
     pub async fn send(&mut self, headers: Option<Vec<(String, String)>>) -> anyhow::Result<()> {
+        let mut upgrade_headers = Vec::new();
+        for (key, val) in headers.unwrap_or_default() {
+            upgrade_headers.push((HeaderName::from_bytes(key.as_bytes())?, HeaderValue::from_str(&val)?));
+        }
         if let Some((mut builder, tx)) = self.response.take() {
-            if let Some(headers) = headers {
-                let rheaders = builder.headers_mut().unwrap();
-                for (key, val) in &headers {
-                    rheaders.append(
-                        HeaderName::from_bytes(key.as_bytes()).unwrap(),
-                        HeaderValue::from_str(val).unwrap(),
-                    );
+            if let Some(rheaders) = builder.headers_mut() {
+                for (key, val) in upgrade_headers {
+                    rheaders.append(key, val);
                 }
             }
-            let res = builder
-                .body(http_body_util::Empty::new().map_err(|e| match e {}).boxed())
-                .unwrap();
+            let res = builder.body(http_body_util::Empty::new().map_err(|e| match e {}).boxed())?;
             return Ok(tx.send(res).await?);
         }
         Err(anyhow::Error::msg("Already consumed"))
     }
+
+    pub async fn deny(&mut self, response: HTTPResponse) -> anyhow::Result<()> {
+        if let Some((_, tx)) = self.response.take() {
+            return Ok(tx.send(response).await?);
+        }
+        Err(anyhow::Error::msg("Already consumed"))
+    }
 }
 
 #[inline]
@@ -1,7 +1,7 @@
 use hyper::{
     body, header,
     http::{request, uri::Authority},
-    HeaderMap, Version,
+    HeaderMap, StatusCode, Version,
 };
 use itertools::Itertools;
 use percent_encoding::percent_decode_str;
@@ -9,7 +9,7 @@ use pyo3::{
     prelude::*,
     types::{IntoPyDict, PyBytes, PyDict},
 };
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 use tokio::sync::oneshot;
 
 use super::{io::WSGIProtocol, types::WSGIBody};
@@ -17,13 +17,14 @@ use crate::{
     callbacks::ArcCBScheduler,
     http::{empty_body, HTTPResponseBody},
     runtime::RuntimeRef,
+    tls::TLSConnectionInfo,
     utils::log_application_callable_exception,
 };
 
 #[inline]
 fn run_callback(
     rt: RuntimeRef,
-    tx: oneshot::Sender<(u16, HeaderMap, HTTPResponseBody)>,
+    tx: oneshot::Sender<(StatusCode, HeaderMap, HTTPResponseBody)>,
     cbs: ArcCBScheduler,
     mut parts: request::Parts,
     server_addr: SocketAddr,
@@ -92,12 +93,23 @@ fn run_callback(
                 content_len.to_str().unwrap_or_default(),
             )?;
         }
+        if let Some(tls_info) = parts.extensions.get::<Arc<TLSConnectionInfo>>() {
+            if let Some(protocol) = tls_info.protocol_name() {
+                environ.set_item(pyo3::intern!(py, "SSL_PROTOCOL"), protocol)?;
+            }
+            if let Some(cipher) = tls_info.cipher_suite_name() {
+                environ.set_item(pyo3::intern!(py, "SSL_CIPHER"), cipher)?;
+            }
+            if let Some(server_name) = &tls_info.server_name {
+                environ.set_item(pyo3::intern!(py, "SSL_TLS_SNI"), server_name)?;
+            }
+        }
         environ.update(headers.into_py_dict(py).unwrap().as_mapping())?;
 
         if let Err(err) = callback.call1(py, (proto.clone_ref(py), environ)) {
             log_application_callable_exception(&err);
             if let Some(tx) = proto.get().tx() {
-                let _ = tx.send((500, HeaderMap::new(), empty_body()));
+                let _ = tx.send((StatusCode::INTERNAL_SERVER_ERROR, HeaderMap::new(), empty_body()));
             }
         }
 
@@ -114,7 +126,7 @@ pub(crate) fn call_http(
     scheme: &str,
     req: request::Parts,
     body: body::Incoming,
-) -> oneshot::Receiver<(u16, HeaderMap, HTTPResponseBody)> {
+) -> oneshot::Receiver<(StatusCode, HeaderMap, HTTPResponseBody)> {
     let scheme: std::sync::Arc<str> = scheme.into();
     let (tx, rx) = oneshot::channel();
     tokio::task::spawn_blocking(move || {
@@ -1,17 +1,18 @@
 use hyper::Response;
-use std::net::SocketAddr;
+use std::{net::SocketAddr, sync::Arc};
 
 use super::callbacks::call_http;
 use crate::{
     callbacks::ArcCBScheduler,
-    http::{response_500, HTTPRequest, HTTPResponse, HTTPResponseBody},
+    http::{response_500, validate_strict, HTTPRequest, HTTPResponse, HTTPResponseBody},
     runtime::RuntimeRef,
+    workers::ServiceConfig,
 };
 
 #[inline(always)]
-fn build_response(status: u16, pyheaders: hyper::HeaderMap, body: HTTPResponseBody) -> HTTPResponse {
+fn build_response(status: hyper::StatusCode, pyheaders: hyper::HeaderMap, body: HTTPResponseBody) -> HTTPResponse {
     let mut res = Response::new(body);
-    *res.status_mut() = hyper::StatusCode::from_u16(status).unwrap();
+    *res.status_mut() = status;
     *res.headers_mut() = pyheaders;
     res
 }
@@ -20,11 +21,17 @@ fn build_response(status: u16, pyheaders: hyper::HeaderMap, body: HTTPResponseBo
 pub(crate) async fn handle(
     rt: RuntimeRef,
     callback: ArcCBScheduler,
+    svc_cfg: Arc<ServiceConfig>,
     server_addr: SocketAddr,
     client_addr: SocketAddr,
     req: HTTPRequest,
     scheme: &str,
 ) -> HTTPResponse {
+    if svc_cfg.strict_requests {
+        if let Some(res) = validate_strict(&req, client_addr) {
+            return res;
+        }
+    }
     let (parts, body) = req.into_parts();
     if let Ok((status, headers, body)) = call_http(rt, callback, server_addr, client_addr, scheme, parts, body).await {
         return build_response(status, headers, body);
@@ -2,30 +2,32 @@ use futures::TryStreamExt;
 use http_body_util::BodyExt;
 use hyper::{
     body,
-    header::{HeaderMap, HeaderName, HeaderValue, SERVER as HK_SERVER},
+    header::{HeaderMap, SERVER as HK_SERVER},
+    StatusCode,
 };
 use pyo3::{prelude::*, pybacked::PyBackedStr};
 use std::{borrow::Cow, sync::Mutex};
 use tokio::sync::{mpsc, oneshot};
 
 use crate::{
+    conversion::{header_from_py, status_code_from_py},
     http::{HTTPResponseBody, HV_SERVER},
     utils::log_application_callable_exception,
 };
 
 #[pyclass(frozen)]
 pub(super) struct WSGIProtocol {
-    tx: Mutex<Option<oneshot::Sender<(u16, HeaderMap, HTTPResponseBody)>>>,
+    tx: Mutex<Option<oneshot::Sender<(StatusCode, HeaderMap, HTTPResponseBody)>>>,
 }
 
 impl WSGIProtocol {
-    pub fn new(tx: oneshot::Sender<(u16, HeaderMap, HTTPResponseBody)>) -> Self {
+    pub fn new(tx: oneshot::Sender<(StatusCode, HeaderMap, HTTPResponseBody)>) -> Self {
         Self {
             tx: Mutex::new(Some(tx)),
         }
     }
 
-    pub fn tx(&self) -> Option<oneshot::Sender<(u16, HeaderMap, HTTPResponseBody)>> {
+    pub fn tx(&self) -> Option<oneshot::Sender<(StatusCode, HeaderMap, HTTPResponseBody)>> {
         self.tx.lock().unwrap().take()
     }
 }
@@ -34,29 +36,48 @@ macro_rules! headers_from_py {
     ($headers:expr) => {{
         let mut headers = HeaderMap::with_capacity($headers.len() + 3);
         headers.insert(HK_SERVER, HV_SERVER);
-        for (key, value) in $headers {
-            headers.append(
-                HeaderName::from_bytes(key.as_bytes()).unwrap(),
-                HeaderValue::from_str(&value).unwrap(),
-            );
+        for (key, value) in &$headers {
+            let (key, value) = header_from_py(key, value)?;
+            headers.append(key, value);
         }
         headers
     }};
 }
 
+#[inline]
+fn response_head(status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>) -> PyResult<(StatusCode, HeaderMap)> {
+    let status = status_code_from_py(status)?;
+    Ok((status, headers_from_py!(headers)))
+}
+
 #[pymethods]
 impl WSGIProtocol {
-    fn response_bytes(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: Cow<[u8]>) {
+    fn response_bytes(&self, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: Cow<[u8]>) -> PyResult<()> {
+        let (status, headers) = response_head(status, headers)?;
         if let Some(tx) = self.tx.lock().unwrap().take() {
             let data: Box<[u8]> = body.into();
             let txbody = http_body_util::Full::new(body::Bytes::from(data))
                 .map_err(|e| match e {})
                 .boxed();
-            let _ = tx.send((status, headers_from_py!(headers), txbody));
+            let _ = tx.send((status, headers, txbody));
         }
+        Ok(())
     }
 
-    fn response_iter(&self, py: Python, status: u16, headers: Vec<(PyBackedStr, PyBackedStr)>, body: Bound<PyAny>) {
+    fn response_iter(
+        &self,
+        py: Python,
+        status: u16,
+        headers: Vec<(PyBackedStr, PyBackedStr)>,
+        body: Bound<PyAny>,
+    ) -> PyResult<()> {
+        let (status, headers) = match response_head(status, headers) {
+            Ok(head) => head,
+            Err(err) => {
+                let _ = body.call_method0(pyo3::intern!(py, "close"));
+                return Err(err);
+            }
+        };
         if let Some(tx) = self.tx.lock().unwrap().take() {
             let (body_tx, body_rx) = mpsc::channel::<Result<body::Bytes, anyhow::Error>>(1);
 
@@ -64,7 +85,7 @@ impl WSGIProtocol {
                 tokio_stream::wrappers::ReceiverStream::new(body_rx).map_ok(body::Frame::data),
             );
             let txbody = BodyExt::boxed(BodyExt::map_err(body_stream, std::convert::Into::into));
-            let _ = tx.send((status, headers_from_py!(headers), txbody));
+            let _ = tx.send((status, headers, txbody));
 
             let mut closed = false;
             loop {
@@ -100,5 +121,6 @@ impl WSGIProtocol {
                 break;
             }
         }
+        Ok(())
     }
 }
@@ -1,9 +1,15 @@
 use pyo3::prelude::*;
+use std::path::PathBuf;
 
 use super::http::handle;
 
 use crate::callbacks::CallbackScheduler;
-use crate::conversion::{worker_http1_config_from_py, worker_http2_config_from_py};
+use crate::conversion::{
+    worker_adaptive_concurrency_config_from_py, worker_concurrency_config_from_py, worker_connection_config_from_py,
+    worker_http1_config_from_py, worker_http2_config_from_py, worker_load_shedding_config_from_py,
+    worker_rate_limit_config_from_py, worker_tls_config_from_py, worker_websocket_config_from_py,
+};
+use crate::tls::TLSMaterial;
 use crate::workers::{
     serve_rth, serve_rth_ssl, serve_wth, serve_wth_ssl, WorkerConfig, WorkerSignalSync, WorkerSignals,
 };
@@ -33,10 +39,20 @@ impl WSGIWorker {
             http_mode="1",
             http1_opts=None,
             http2_opts=None,
+            connection_opts=None,
+            rate_limit_opts=None,
+            rate_limit_shm=None,
+            rate_limit_seed=0,
+            load_shedding_opts=None,
+            concurrency_opts=None,
+            adaptive_concurrency_opts=None,
+            strict_requests=false,
             ssl_enabled=false,
             ssl_cert=None,
             ssl_key=None,
-            ssl_key_password=None
+            ssl_key_password=None,
+            ssl_opts=None,
+            ssl_acme=None
         )
     )]
     fn new(
@@ -49,10 +65,20 @@ impl WSGIWorker {
         http_mode: &str,
         http1_opts: Option<PyObject>,
         http2_opts: Option<PyObject>,
+        connection_opts: Option<PyObject>,
+        rate_limit_opts: Option<PyObject>,
+        rate_limit_shm: Option<String>,
+        rate_limit_seed: u64,
+        load_shedding_opts: Option<PyObject>,
+        concurrency_opts: Option<PyObject>,
+        adaptive_concurrency_opts: Option<PyObject>,
+        strict_requests: bool,
         ssl_enabled: bool,
-        ssl_cert: Option<&str>,
-        ssl_key: Option<&str>,
+        ssl_cert: Option<TLSMaterial>,
+        ssl_key: Option<TLSMaterial>,
         ssl_key_password: Option<&str>,
+        ssl_opts: Option<PyObject>,
+        ssl_acme: Option<PathBuf>,
     ) -> PyResult<Self> {
         Ok(Self {
             config: WorkerConfig::new(
@@ -64,11 +90,22 @@ impl WSGIWorker {
                 http_mode,
                 worker_http1_config_from_py(py, http1_opts)?,
                 worker_http2_config_from_py(py, http2_opts)?,
+                worker_connection_config_from_py(py, connection_opts)?,
+                worker_rate_limit_config_from_py(py, rate_limit_opts, rate_limit_shm, rate_limit_seed)?,
+                worker_load_shedding_config_from_py(py, load_shedding_opts)?,
+                worker_concurrency_config_from_py(py, concurrency_opts)?,
+                worker_adaptive_concurrency_config_from_py(py, adaptive_concurrency_opts)?,
+                strict_requests,
+                false,
+                worker_websocket_config_from_py(py, None)?,
                 false,
+                0,
                 ssl_enabled,
                 ssl_cert,
                 ssl_key,
                 ssl_key_password,
+                worker_tls_config_from_py(py, ssl_opts)?,
+                ssl_acme,
             ),
         })
     }
@@ -60,16 +60,51 @@ async def echo(scope, receive, send):
     await send({'type': 'http.response.body', 'body': body, 'more_body': False})
 
 
+async def expect(scope, receive, send):
+    if dict(scope['headers']).get(b'x-reject'):
+        await send({'type': 'http.response.start', 'status': 413, 'headers': []})
+        await send({'type': 'http.response.body', 'body': b'', 'more_body': False})
+        return
+    await echo(scope, receive, send)
+
+
 async def pathsend(scope, receive, send):
     path = pathlib.Path.cwd() / 'tests' / 'fixtures' / 'media.png'
     await send(MEDIA_RESPONSE)
     await send({'type': 'http.response.pathsend', 'path': str(path)})
 
 
+async def early_hints(scope, receive, send):
+    query = b'?' + scope['query_string'] if scope['query_string'] else b''
+    await send({'type': 'http.response.early_hint', 'links': [b'</style.css' + query + b'>; rel=preload; as=style']})
+    await send(PLAINTEXT_RESPONSE)
+    await send({'type': 'http.response.body', 'body': b'test', 'more_body': False})
+
+
+async def zerocopy(scope, receive, send):
+    path = pathlib.Path.cwd() / 'tests' / 'fixtures' / 'media.png'
+    await send({'type': 'http.response.start', 'status': 200, 'headers': [[b'content-type', b'image/png']]})
+    with path.open('rb') as f:
+        await send({'type': 'http.response.zerocopy', 'file': f, 'offset': 10, 'count': 20, 'more_body': True})
+        await send({'type': 'http.response.zerocopy', 'file': f, 'offset': 30})
+
+
 async def ws_reject(scope, receive, send):
     return
 
 
+async def ws_deny(scope, receive, send):
+    await send(
+        {
+            'type': 'websocket.http.response.start',
+            'status': 401,
+            'headers': [(b'content-type', b'application/json')],
+        }
+    )
+    await send({'type': 'websocket.http.response.body', 'body': b'{"error":', 'more_body': True})
+    await send({'type': 'websocket.http.response.body', 'body': b'"unauthorized"}'})
+
+
 async def ws_info(scope, receive, send):
     await send({'type': 'websocket.accept'})
     await send(
@@ -109,6 +144,22 @@ async def ws_echo(scope, receive, send):
     await send({'type': 'websocket.close'})
 
 
+async def ws_fragments(scope, receive, send):
+    await send({'type': 'websocket.accept'})
+
+    for _ in range(0, 3):
+        await send({'type': 'websocket.send', 'bytes': b'test', 'more_body': True})
+    await send({'type': 'websocket.send', 'bytes': b''})
+    for _ in range(0, 2):
+        await send({'type': 'websocket.send', 'text': 'test', 'more_body': True})
+    await send({'type': 'websocket.send', 'text': 'test'})
+
+    while True:
+        msg = await receive()
+        if msg['type'] == 'websocket.disconnect':
+            break
+
+
 async def ws_push(scope, receive, send):
     await send({'type': 'websocket.accept'})
 
@@ -123,6 +174,25 @@ async def err_app(scope, receive, send):
     1 / 0
 
 
+async def err_response(scope, receive, send):
+    status, headers = {
+        b'status': (1000, []),
+        b'header': (200, [(b'x-invalid', b'multi\nline')]),
+        b'headers': (200, 1),
+    }[scope['query_string']]
+    await send({'type': 'http.response.start', 'status': status, 'headers': headers})
+    await send({'type': 'http.response.body', 'body': b'', 'more_body': False})
+
+
+async def err_status(scope, receive, send):
+    try:
+        await send({'type': 'http.response.start', 'status': 1000, 'headers': []})
+    except Exception as exc:
+        error = type(exc).__name__
+    await send(PLAINTEXT_RESPONSE)
+    await send({'type': 'http.response.body', 'body': error.encode(), 'more_body': False})
+
+
 async def err_proto(scope, receive, send):
     await send({'type': 'wrong.msg'})
 
@@ -152,6 +222,92 @@ async def timeout_w(scope, receive, send):
     await send({'type': 'http.response.body', 'body': ret, 'more_body': False})
 
 
+_streamed = {'sent': 0, 'errors': []}
+STREAM_RESPONSE = {
+    'type': 'http.response.start',
+    'status': 200,
+    'headers': [[b'content-type', b'application/octet-stream']],
+}
+
+
+async def stream_slow(scope, receive, send):
+    await send(STREAM_RESPONSE)
+    for _ in range(0, 256):
+        await send({'type': 'http.response.body', 'body': b'x' * 65536, 'more_body': True})
+        _streamed['sent'] += 65536
+    await send({'type': 'http.response.body', 'body': b'', 'more_body': False})
+
+
+async def stream_infinite(scope, receive, send):
+    await send(STREAM_RESPONSE)
+    try:
+        while True:
+            await send({'type': 'http.response.body', 'body': b'x' * 65536, 'more_body': True})
+    except Exception as exc:
+        _streamed['errors'].append(type(exc).__name__)
+
+
+async def streamed(scope, receive, send):
+    await send(JSON_RESPONSE)
+    await send({'type': 'http.response.body', 'body': json.dumps(_streamed).encode('utf8'), 'more_body': False})
+
+
+async def trailers(scope, receive, send):
+    body, req_trailers = b'', []
+    more_body = True
+    while more_body:
+        msg = await receive()
+        body += msg['body']
+        more_body = msg['more_body']
+        req_trailers.extend(msg.get('trailers', []))
+    checksum = dict(req_trailers).get(b'x-checksum', b'none')
+
+    await send(
+        {
+            'type': 'http.response.start',
+            'status': 200,
+            'headers': [[b'content-type', b'text/plain; charset=utf-8'], [b'trailer', b'x-checksum']],
+            'trailers': True,
+        }
+    )
+    await send({'type': 'http.response.body', 'body': body, 'more_body': False})
+    await send({'type': 'http.response.trailers', 'headers': [[b'x-checksum', checksum]], 'more_trailers': False})
+
+
+_disconnects = []
+
+
+async def disconnect(scope, receive, send):
+    while True:
+        msg = await receive()
+        if msg['type'] == 'http.disconnect':
+            break
+    _disconnects.append(True)
+
+
+async def disconnects(scope, receive, send):
+    await send(PLAINTEXT_RESPONSE)
+    await send({'type': 'http.response.body', 'body': str(len(_disconnects)).encode('utf8'), 'more_body': False})
+
+
+_cancellations = []
+
+
+async def cancel(scope, receive, send):
+    try:
+        await asyncio.sleep(5)
+    except asyncio.CancelledError:
+        _cancellations.append(True)
+        raise
+    await send(PLAINTEXT_RESPONSE)
+    await send({'type': 'http.response.body', 'body': b'timeout', 'more_body': False})
+
+
+async def cancellations(scope, receive, send):
+    await send(PLAINTEXT_RESPONSE)
+    await send({'type': 'http.response.body', 'body': str(len(_cancellations)).encode('utf8'), 'more_body': False})
+
+
 async def lifespan(scope, receive, send):
     msg = await receive()
     if msg['type'] == 'lifespan.startup':
@@ -166,13 +322,28 @@ def app(scope, receive, send):
         '/info': info,
         '/sniffio': sniff_aio_impl,
         '/echo': echo,
+        '/expect': expect,
         '/file': pathsend,
+        '/zerocopy': zerocopy,
+        '/early_hints': early_hints,
         '/ws_reject': ws_reject,
+        '/ws_deny': ws_deny,
         '/ws_info': ws_info,
         '/ws_echo': ws_echo,
+        '/ws_fragments': ws_fragments,
         '/ws_push': ws_push,
         '/err_app': err_app,
         '/err_proto': err_proto,
+        '/err_response': err_response,
+        '/err_status': err_status,
         '/timeout_n': timeout_n,
         '/timeout_w': timeout_w,
+        '/trailers': trailers,
+        '/stream_slow': stream_slow,
+        '/stream_infinite': stream_infinite,
+        '/streamed': streamed,
+        '/disconnect': disconnect,
+        '/disconnects': disconnects,
+        '/cancel': cancel,
+        '/cancellations': cancellations,
     }[scope['path']](scope, receive, send)
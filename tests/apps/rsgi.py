@@ -1,6 +1,7 @@
+import asyncio
 import json
 
-from granian.rsgi import HTTPProtocol, Scope, WebsocketMessageType, WebsocketProtocol
+from granian.rsgi import HTTPProtocol, Scope, WebsocketMessageType, WebsocketProtocol, send_fragments
 
 
 async def info(scope: Scope, protocol: HTTPProtocol):
@@ -23,11 +24,33 @@ async def info(scope: Scope, protocol: HTTPProtocol):
     )
 
 
+async def tls_info(scope: Scope, protocol: HTTPProtocol):
+    protocol.response_bytes(
+        200,
+        [('content-type', 'application/json')],
+        json.dumps(
+            {
+                'protocol': scope.tls_protocol,
+                'cipher': scope.tls_cipher,
+                'alpn_protocol': scope.tls_alpn_protocol,
+                'sni': scope.tls_sni,
+            }
+        ).encode('utf8'),
+    )
+
+
 async def echo(_, protocol: HTTPProtocol):
     msg = await protocol()
     protocol.response_bytes(200, [('content-type', 'text/plain; charset=utf-8')], msg)
 
 
+async def expect(scope: Scope, protocol: HTTPProtocol):
+    if scope.headers.get('x-reject'):
+        protocol.response_empty(413, [])
+        return
+    await echo(scope, protocol)
+
+
 async def echo_stream(_, protocol: HTTPProtocol):
     trx = protocol.response_stream(200, [('content-type', 'text/plain; charset=utf-8')])
     async for msg in protocol:
@@ -40,6 +63,105 @@ async def stream(_, protocol: HTTPProtocol):
         await trx.send_bytes(b'test')
 
 
+async def stream_large(_, protocol: HTTPProtocol):
+    trx = protocol.response_stream(200, [('content-type', 'text/plain; charset=utf-8')])
+    for _ in range(0, 3):
+        await trx.send_bytes(b'x' * 100_000)
+
+
+_streamed = {'sent': 0, 'errors': []}
+
+
+async def stream_slow(_, protocol: HTTPProtocol):
+    trx = protocol.response_stream(200, [('content-type', 'application/octet-stream')])
+    for _ in range(0, 256):
+        await trx.send_bytes(b'x' * 65536)
+        _streamed['sent'] += 65536
+
+
+async def stream_infinite(_, protocol: HTTPProtocol):
+    trx = protocol.response_stream(200, [('content-type', 'application/octet-stream')])
+    try:
+        while True:
+            await trx.send_bytes(b'x' * 65536)
+    except Exception as exc:
+        _streamed['errors'].append(type(exc).__name__)
+
+
+async def streamed(_, protocol: HTTPProtocol):
+    protocol.response_str(200, [('content-type', 'application/json')], json.dumps(_streamed))
+
+
+async def trailers(_, protocol: HTTPProtocol):
+    body = await protocol()
+    req_trailers = protocol.trailers()
+    trx = protocol.response_stream(200, [('content-type', 'text/plain; charset=utf-8'), ('trailer', 'x-checksum')])
+    await trx.send_bytes(body)
+    await trx.send_trailers([('x-checksum', req_trailers.get('x-checksum', 'none') if req_trailers else 'none')])
+
+
+async def early_hints(scope: Scope, protocol: HTTPProtocol):
+    query = f'?{scope.query_string}' if scope.query_string else ''
+    protocol.send_early_hints([('link', f'</style.css{query}>; rel=preload; as=style')])
+    protocol.response_str(200, [('content-type', 'text/plain; charset=utf-8')], 'test')
+
+
+async def sse(_, protocol: HTTPProtocol):
+    trx = protocol.response_sse(heartbeat=0.1)
+    await trx.send('hello', event='greet', id=trx.last_event_id or '0')
+    await asyncio.sleep(0.25)
+    await trx.send('multi\nline', retry=1000)
+
+
+async def grpc(_, protocol: HTTPProtocol):
+    call = protocol.grpc([('trailer', 'grpc-status, grpc-message')])
+    async for message in call:
+        await call.send(message.upper())
+    await call.finish(0)
+
+
+async def grpc_deadline(_, protocol: HTTPProtocol):
+    call = protocol.grpc([('trailer', 'grpc-status, grpc-message')])
+    await asyncio.sleep(1)
+    await call.finish(0)
+
+
+async def grpc_error(_, protocol: HTTPProtocol):
+    protocol.grpc([('trailer', 'grpc-status, grpc-message')])
+    raise RuntimeError('grpc error')
+
+
+_disconnects = []
+
+
+async def disconnect(_, protocol: HTTPProtocol):
+    try:
+        await asyncio.wait_for(protocol.client_disconnect(), 5)
+        _disconnects.append(True)
+    except asyncio.TimeoutError:
+        protocol.response_str(200, [], 'timeout')
+
+
+async def disconnects(_, protocol: HTTPProtocol):
+    protocol.response_str(200, [('content-type', 'text/plain; charset=utf-8')], str(len(_disconnects)))
+
+
+_cancellations = []
+
+
+async def cancel(_, protocol: HTTPProtocol):
+    try:
+        await asyncio.sleep(5)
+    except asyncio.CancelledError:
+        _cancellations.append(True)
+        raise
+    protocol.response_str(200, [], 'timeout')
+
+
+async def cancellations(_, protocol: HTTPProtocol):
+    protocol.response_str(200, [('content-type', 'text/plain; charset=utf-8')], str(len(_cancellations)))
+
+
 async def ws_reject(_, protocol: WebsocketProtocol):
     protocol.close(403)
 
@@ -85,6 +207,51 @@ async def ws_echo(_, protocol: WebsocketProtocol):
     protocol.close()
 
 
+async def ws_fragments(_, protocol: WebsocketProtocol):
+    trx = await protocol.accept()
+
+    for _ in range(0, 3):
+        await trx.send_bytes_fragment(b'test')
+    await trx.send_bytes_fragment(b'', fin=True)
+    for _ in range(0, 2):
+        await trx.send_str_fragment('test')
+    await trx.send_str_fragment('test', fin=True)
+
+    while True:
+        message = await trx.receive()
+        if message.kind == WebsocketMessageType.close:
+            break
+
+    protocol.close()
+
+
+async def ws_fragments_receive(_, protocol: WebsocketProtocol):
+    trx = await protocol.accept()
+
+    fragments = []
+    while True:
+        message = await trx.receive_fragment()
+        if message.kind == WebsocketMessageType.close:
+            break
+        fragments.append(message.data)
+        if message.fin:
+            break
+
+    async def chunks():
+        for fragment in fragments:
+            yield fragment
+
+    await send_fragments(trx, chunks())
+    await trx.send_str(str(len(fragments)))
+
+    while True:
+        message = await trx.receive()
+        if message.kind == WebsocketMessageType.close:
+            break
+
+    protocol.close()
+
+
 async def ws_push(_, protocol: WebsocketProtocol):
     trx = await protocol.accept()
 
@@ -101,15 +268,53 @@ async def err_app(scope: Scope, protocol: HTTPProtocol):
     1 / 0
 
 
+async def err_response(scope: Scope, protocol: HTTPProtocol):
+    protocol.response_str(200, [('x-invalid', 'multi\nline')], 'test')
+
+
+async def err_headers(scope: Scope, protocol: HTTPProtocol):
+    errors = []
+    try:
+        protocol.send_early_hints([('x-invalid', 'multi\nline')])
+    except Exception as exc:
+        errors.append(type(exc).__name__)
+    trx = protocol.response_stream(200, [('content-type', 'text/plain; charset=utf-8')])
+    try:
+        await trx.send_trailers([('x-invalid', 'multi\nline')])
+    except Exception as exc:
+        errors.append(type(exc).__name__)
+    await trx.send_str(','.join(errors))
+
+
 def app(scope, protocol):
     return {
         '/info': info,
+        '/tls_info': tls_info,
         '/echo': echo,
+        '/expect': expect,
         '/echos': echo_stream,
         '/stream': stream,
+        '/stream_large': stream_large,
+        '/stream_slow': stream_slow,
+        '/stream_infinite': stream_infinite,
+        '/streamed': streamed,
+        '/trailers': trailers,
+        '/early_hints': early_hints,
+        '/sse': sse,
+        '/grpc': grpc,
+        '/grpc_deadline': grpc_deadline,
+        '/grpc_error': grpc_error,
+        '/disconnect': disconnect,
+        '/disconnects': disconnects,
+        '/cancel': cancel,
+        '/cancellations': cancellations,
         '/ws_reject': ws_reject,
         '/ws_info': ws_info,
         '/ws_echo': ws_echo,
+        '/ws_fragments': ws_fragments,
+        '/ws_fragments_receive': ws_fragments_receive,
         '/ws_push': ws_push,
         '/err_app': err_app,
+        '/err_response': err_response,
+        '/err_headers': err_headers,
     }[scope.path](scope, protocol)
@@ -17,11 +17,27 @@ def info(environ, protocol):
     ]
 
 
+def tls_info(environ, protocol):
+    protocol('200 OK', [('content-type', 'application/json')])
+    return [
+        json.dumps(
+            {key: environ.get(key) for key in ('SSL_PROTOCOL', 'SSL_CIPHER', 'SSL_TLS_SNI')}
+        ).encode('utf8')
+    ]
+
+
 def echo(environ, protocol):
     protocol('200 OK', [('content-type', 'text/plain; charset=utf-8')])
     return [environ['wsgi.input'].read()]
 
 
+def expect(environ, protocol):
+    if environ.get('HTTP_X_REJECT'):
+        protocol('413 Payload Too Large', [])
+        return [b'']
+    return echo(environ, protocol)
+
+
 def iterbody(environ, protocol):
     def response():
         for _ in range(0, 3):
@@ -35,7 +51,18 @@ def err_app(environ, protocol):
     1 / 0
 
 
+def err_response(environ, protocol):
+    protocol('200 OK', [('x-invalid', 'multi\nline')])
+    return [b'test']
+
+
 def app(environ, protocol):
-    return {'/info': info, '/echo': echo, '/iterbody': iterbody, '/err_app': err_app}[environ['PATH_INFO']](
-        environ, protocol
-    )
+    return {
+        '/info': info,
+        '/tls_info': tls_info,
+        '/echo': echo,
+        '/expect': expect,
+        '/iterbody': iterbody,
+        '/err_app': err_app,
+        '/err_response': err_response,
+    }[environ['PATH_INFO']](environ, protocol)
@@ -16,21 +16,63 @@ def _serve(**kwargs):
 
 
 @asynccontextmanager
-async def _server(interface, port, threading_mode, tls=False):
+async def _server(
+    interface,
+    port,
+    threading_mode,
+    tls=False,
+    ssl_settings=None,
+    ssl_acme=None,
+    http1_settings=None,
+    http2_settings=None,
+    connection_settings=None,
+    rate_limit_settings=None,
+    load_shedding_settings=None,
+    concurrency_settings=None,
+    adaptive_concurrency_settings=None,
+    strict_requests=False,
+    cancel_on_disconnect=False,
+    task_impl='auto',
+    stream_buffer_size=None,
+    backpressure=None,
+    workers=1,
+    log_dictconfig=None,
+):
     certs_path = Path.cwd() / 'tests' / 'fixtures' / 'tls'
     kwargs = {
         'interface': interface,
         'port': port,
         'threading_mode': threading_mode,
+        'http1_settings': http1_settings,
+        'http2_settings': http2_settings,
+        'connection_settings': connection_settings,
+        'rate_limit_settings': rate_limit_settings,
+        'load_shedding_settings': load_shedding_settings,
+        'concurrency_settings': concurrency_settings,
+        'adaptive_concurrency_settings': adaptive_concurrency_settings,
+        'strict_requests': strict_requests,
+        'cancel_on_disconnect': cancel_on_disconnect,
+        'task_impl': task_impl,
+        'backpressure': backpressure,
+        'workers': workers,
+        'log_dictconfig': log_dictconfig,
     }
+    if stream_buffer_size:
+        kwargs['stream_buffer_size'] = stream_buffer_size
     if tls:
-        if tls == 'private':
+        if tls == 'acme':
+            kwargs['ssl_acme'] = ssl_acme
+        elif tls == 'private':
             kwargs['ssl_cert'] = certs_path / 'pcert.pem'
             kwargs['ssl_key'] = certs_path / 'pkey.pem'
             kwargs['ssl_key_password'] = 'foobar'  # noqa: S105
+        elif tls == 'pkcs12':
+            kwargs['ssl_cert'] = certs_path / 'bundle.p12'
+            kwargs['ssl_key_password'] = 'foobar'  # noqa: S105
         else:
             kwargs['ssl_cert'] = certs_path / 'cert.pem'
             kwargs['ssl_key'] = certs_path / 'key.pem'
+        kwargs['ssl_settings'] = ssl_settings
 
     succeeded, spawn_failures = False, 0
     while spawn_failures < 3:
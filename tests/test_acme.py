@@ -0,0 +1,104 @@
+import os
+import pathlib
+import socket
+import ssl
+import time
+
+import httpx
+import pytest
+
+from granian._granian import ACMEManager
+from granian.http import ACMESettings
+
+
+TLS_FIXTURES = pathlib.Path.cwd() / 'tests' / 'fixtures' / 'tls'
+
+
+def _acme_settings(storage, **kwargs):
+    # NOTE: the ACME server is unreachable, workers will keep serving what's in the storage
+    storage.mkdir(exist_ok=True)
+    (storage / 'cert.pem').write_bytes((TLS_FIXTURES / 'cert.pem').read_bytes())
+    (storage / 'key.pem').write_bytes((TLS_FIXTURES / 'key.pem').read_bytes())
+    return ACMESettings(domains=['localhost'], storage=storage, server='https://127.0.0.1:1/directory', **kwargs)
+
+
+def _alpn_handshake(port, server_name, protocols):
+    ctx = ssl.SSLContext(ssl.PROTOCOL_TLS_CLIENT)
+    ctx.check_hostname = False
+    ctx.verify_mode = ssl.CERT_NONE
+    ctx.set_alpn_protocols(protocols)
+    with socket.create_connection(('localhost', port), timeout=2) as sock:
+        with ctx.wrap_socket(sock, server_hostname=server_name) as tls_sock:
+            return tls_sock.selected_alpn_protocol()
+
+
+@pytest.mark.parametrize(
+    'settings,error',
+    [
+        ({'domains': []}, 'At least one domain is required'),
+        ({'domains': ['../etc']}, 'Unsupported domain name'),
+        ({'domains': ['*.example.com']}, 'Unsupported domain name'),
+        ({'domains': ['example.com'], 'challenge': 'dns-01'}, 'Unsupported challenge type'),
+    ],
+)
+def test_acme_settings_errors(tmp_path, settings, error):
+    with pytest.raises(ValueError, match=error):
+        ACMEManager(ACMESettings(storage=tmp_path, **settings), '127.0.0.1')
+
+
+def test_acme_http01_bind_error(tmp_path, server_port):
+    with socket.socket(socket.AF_INET, socket.SOCK_STREAM) as sock:
+        sock.bind(('127.0.0.1', server_port))
+        sock.listen()
+        settings = ACMESettings(domains=['localhost'], storage=tmp_path, challenge='http-01', http_port=server_port)
+        with pytest.raises(ValueError, match='Unable to bind ACME HTTP-01 listener'):
+            ACMEManager(settings, '127.0.0.1')
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('server', ['asgi', 'rsgi'], indirect=True)
+async def test_acme_stored_certificate(server, tmp_path):
+    async with server('runtime', tls='acme', ssl_acme=_acme_settings(tmp_path)) as port:
+        res = httpx.get(f'https://localhost:{port}/info', verify=False)
+
+    assert res.status_code == 200
+    assert res.json()['scheme'] == 'https'
+
+
+@pytest.mark.asyncio
+async def test_acme_tls_alpn_challenge(rsgi_server, tmp_path):
+    settings = _acme_settings(tmp_path)
+    (tmp_path / 'challenges').mkdir()
+    (tmp_path / 'challenges' / 'example.com.pem').write_bytes(
+        (TLS_FIXTURES / 'cert.pem').read_bytes() + (TLS_FIXTURES / 'key.pem').read_bytes()
+    )
+
+    async with rsgi_server('runtime', tls='acme', ssl_acme=settings) as port:
+        assert _alpn_handshake(port, 'example.com', ['acme-tls/1']) == 'acme-tls/1'
+        with pytest.raises(ssl.SSLError):
+            _alpn_handshake(port, 'example.org', ['acme-tls/1'])
+        assert _alpn_handshake(port, 'localhost', ['http/1.1']) == 'http/1.1'
+
+
+# NOTE: requires a running pebble instance (with default validation ports),
+#       and `SSL_CERT_FILE` env var pointing to its certificate.
+@pytest.mark.asyncio
+@pytest.mark.skipif(not os.getenv('PEBBLE_DIRECTORY'), reason='pebble not available')
+@pytest.mark.parametrize('server_port', [5001])
+@pytest.mark.parametrize('challenge', ['tls-alpn-01', 'http-01'])
+async def test_acme_pebble(rsgi_server, tmp_path, challenge):
+    settings = ACMESettings(
+        domains=['localhost'],
+        storage=tmp_path,
+        server=os.environ['PEBBLE_DIRECTORY'],
+        contact=['admin@example.com'],
+        challenge=challenge,
+        http_port=5002,
+    )
+    async with rsgi_server('runtime', tls='acme', ssl_acme=settings) as port:
+        for _ in range(30):
+            if (tmp_path / 'cert.pem').exists():
+                break
+            time.sleep(1)
+        assert (tmp_path / 'account.json').exists()
+        assert ssl.get_server_certificate(('localhost', port)).strip() in (tmp_path / 'cert.pem').read_text()
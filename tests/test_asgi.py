@@ -1,4 +1,7 @@
+import asyncio
 import os
+import pathlib
+import sys
 
 import httpx
 import pytest
@@ -37,6 +40,49 @@ async def test_body(asgi_server, threading_mode):
     assert res.text == 'test'
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_expect_continue(asgi_server, threading_mode):
+    async with asgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                f'POST /expect HTTP/1.1\r\nhost: localhost:{port}\r\nexpect: 100-continue\r\n'
+                'content-length: 4\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        interim = await asyncio.wait_for(reader.readuntil(b'\r\n\r\n'), 5)
+        writer.write(b'test')
+        await writer.drain()
+        head = await reader.readuntil(b'\r\n\r\n')
+        body = await reader.readexactly(4)
+        writer.close()
+
+    assert interim == b'HTTP/1.1 100 Continue\r\n\r\n'
+    assert head.startswith(b'HTTP/1.1 200')
+    assert body == b'test'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_expect_continue_reject(asgi_server, threading_mode):
+    async with asgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                f'POST /expect HTTP/1.1\r\nhost: localhost:{port}\r\nexpect: 100-continue\r\n'
+                'x-reject: 1\r\ncontent-length: 4\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        res = await asyncio.wait_for(reader.read(), 5)
+        writer.close()
+
+    assert res.startswith(b'HTTP/1.1 413')
+    assert b'100 Continue' not in res
+
+
 @pytest.mark.asyncio
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
 async def test_body_large(asgi_server, threading_mode):
@@ -58,6 +104,29 @@ async def test_app_error(asgi_server, threading_mode):
     assert res.status_code == 500
 
 
+@pytest.mark.asyncio
+@pytest.mark.skipif(bool(os.getenv('PGO_RUN')), reason='PGO build')
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+@pytest.mark.parametrize('error', ['status', 'header', 'headers'])
+async def test_response_error(asgi_server, threading_mode, error):
+    async with asgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/err_response?{error}')
+        res_next = httpx.get(f'http://localhost:{port}/info')
+
+    assert res.status_code == 500
+    assert res_next.status_code == 200
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_status_error(asgi_server, threading_mode):
+    async with asgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/err_status')
+
+    assert res.status_code == 200
+    assert res.text == 'ValueError'
+
+
 @pytest.mark.asyncio
 @pytest.mark.skipif(bool(os.getenv('PGO_RUN')), reason='PGO build')
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
@@ -79,6 +148,19 @@ async def test_file(asgi_server, threading_mode):
     assert res.headers['content-length'] == '95'
 
 
+@pytest.mark.asyncio
+@pytest.mark.skipif(sys.platform == 'win32', reason='not supported on windows')
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_zerocopy(asgi_server, threading_mode):
+    media = (pathlib.Path.cwd() / 'tests' / 'fixtures' / 'media.png').read_bytes()
+    async with asgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/zerocopy')
+
+    assert res.status_code == 200
+    assert res.headers['content-type'] == 'image/png'
+    assert res.content == media[10:]
+
+
 @pytest.mark.asyncio
 @pytest.mark.skipif(bool(os.getenv('PGO_RUN')), reason='PGO build')
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
@@ -105,3 +187,81 @@ async def test_timeout(asgi_server, threading_mode):
 
     assert res.status_code == 200
     assert res.text == 'timeout'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_client_disconnect(asgi_server, threading_mode):
+    async with asgi_server(threading_mode) as port:
+        _, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(f'GET /disconnect HTTP/1.1\r\nhost: localhost:{port}\r\n\r\n'.encode('latin1'))
+        await writer.drain()
+        await asyncio.sleep(0.5)
+        writer.close()
+        await writer.wait_closed()
+        await asyncio.sleep(0.5)
+        res = httpx.get(f'http://localhost:{port}/disconnects')
+
+    assert res.status_code == 200
+    assert res.text == '1'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_client_disconnect_cancel(asgi_server, threading_mode):
+    async with asgi_server(threading_mode, cancel_on_disconnect=True, task_impl='asyncio') as port:
+        _, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(f'GET /cancel HTTP/1.1\r\nhost: localhost:{port}\r\n\r\n'.encode('latin1'))
+        await writer.drain()
+        await asyncio.sleep(0.5)
+        writer.close()
+        await writer.wait_closed()
+        await asyncio.sleep(0.5)
+        res = httpx.get(f'http://localhost:{port}/cancellations')
+
+    assert res.status_code == 200
+    assert res.text == '1'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_early_hints(asgi_server, threading_mode):
+    async with asgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (f'GET /early_hints HTTP/1.1\r\nhost: localhost:{port}\r\nconnection: close\r\n\r\n').encode('latin1')
+        )
+        await writer.drain()
+        res = await reader.read()
+        writer.close()
+
+    hints, final = res.split(b'\r\n\r\n', 1)
+    assert hints == b'HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style'
+    assert final.startswith(b'HTTP/1.1 200')
+    assert final.endswith(b'\r\n\r\ntest')
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_trailers(asgi_server, threading_mode):
+    async with asgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                'POST /trailers HTTP/1.1\r\n'
+                f'host: localhost:{port}\r\n'
+                'te: trailers\r\n'
+                'trailer: x-checksum\r\n'
+                'transfer-encoding: chunked\r\n'
+                'connection: close\r\n\r\n'
+                '4\r\ntest\r\n0\r\nx-checksum: abc\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        res = await reader.read()
+        writer.close()
+
+    head, body = res.split(b'\r\n\r\n', 1)
+    assert head.startswith(b'HTTP/1.1 200')
+    assert b'transfer-encoding: chunked' in head
+    assert body == b'4\r\ntest\r\n0\r\nx-checksum: abc\r\n\r\n'
@@ -0,0 +1,157 @@
+import asyncio
+import socket
+from contextlib import contextmanager
+
+import httpx
+import pytest
+
+from granian.errors import ConfigurationError
+from granian.http import AdaptiveConcurrencySettings, ConcurrencyLimitSettings, LoadSheddingSettings
+from granian.server import Granian
+
+
+@contextmanager
+def _pending_request(port):
+    # the request body is incomplete, thus the request holds its slot until we complete it
+    with socket.create_connection(('127.0.0.1', port)) as sock:
+        sock.sendall(b'POST /echo HTTP/1.1\r\nHost: localhost\r\nContent-Length: 4\r\n\r\nte')
+        yield sock
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_concurrency_limit(rsgi_server, threading_mode):
+    settings = ConcurrencyLimitSettings(max_requests=1)
+    async with rsgi_server(threading_mode, concurrency_settings=settings) as port:
+        with _pending_request(port) as sock:
+            await asyncio.sleep(0.2)
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 503
+
+            sock.sendall(b'st')
+            assert sock.recv(1024).startswith(b'HTTP/1.1 200')
+
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 200
+        assert res.headers['content-length'] == str(len(res.content))
+
+
+@pytest.mark.asyncio
+async def test_concurrency_limit_queue(rsgi_server):
+    settings = ConcurrencyLimitSettings(max_requests=1, queue_size=1)
+    async with rsgi_server('runtime', concurrency_settings=settings) as port:
+        with _pending_request(port) as sock:
+            await asyncio.sleep(0.2)
+            req = asyncio.create_task(asyncio.to_thread(httpx.get, f'http://127.0.0.1:{port}/info'))
+            await asyncio.sleep(0.2)
+            # the queue is full
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 503
+
+            sock.sendall(b'st')
+            res = await req
+            assert res.status_code == 200
+
+
+@pytest.mark.asyncio
+async def test_concurrency_limit_queue_timeout(rsgi_server):
+    settings = ConcurrencyLimitSettings(max_requests=1, queue_size=1, queue_timeout=0.5)
+    async with rsgi_server('runtime', concurrency_settings=settings) as port:
+        with _pending_request(port):
+            await asyncio.sleep(0.2)
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 503
+
+
+@pytest.mark.parametrize(
+    'settings',
+    [
+        ConcurrencyLimitSettings(max_requests=0),
+        ConcurrencyLimitSettings(max_requests=1, queue_size=-1),
+        ConcurrencyLimitSettings(max_requests=1, queue_timeout=-1),
+    ],
+)
+def test_concurrency_settings_errors(settings):
+    server = Granian('tests.apps.rsgi:app', concurrency_settings=settings)
+    with pytest.raises(ConfigurationError):
+        server.validate_concurrency_settings()
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_adaptive_concurrency(rsgi_server, threading_mode):
+    settings = AdaptiveConcurrencySettings(initial_limit=2)
+    async with rsgi_server(threading_mode, adaptive_concurrency_settings=settings) as port:
+        for _ in range(20):
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 200
+
+
+@pytest.mark.asyncio
+async def test_adaptive_concurrency_limit(rsgi_server):
+    # the adaptive limit replaces the backpressure
+    settings = AdaptiveConcurrencySettings(initial_limit=1, max_limit=1)
+    async with rsgi_server(
+        'runtime',
+        adaptive_concurrency_settings=settings,
+        load_shedding_settings=LoadSheddingSettings(),
+    ) as port:
+        await asyncio.sleep(0.5)
+        with socket.create_connection(('127.0.0.1', port)):
+            await asyncio.sleep(0.2)
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 503
+
+        await asyncio.sleep(0.2)
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 200
+
+
+@pytest.mark.asyncio
+async def test_adaptive_concurrency_metrics(rsgi_server, tmp_path):
+    log_path = tmp_path / 'metrics.log'
+    log_config = {
+        'handlers': {'metrics': {'class': 'logging.FileHandler', 'filename': str(log_path)}},
+        'loggers': {'_granian.metrics': {'handlers': ['metrics'], 'level': 'INFO', 'propagate': False}},
+    }
+    settings = AdaptiveConcurrencySettings(initial_limit=3)
+    async with rsgi_server('runtime', adaptive_concurrency_settings=settings, log_dictconfig=log_config) as port:
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 200
+        await asyncio.sleep(0.2)
+
+    metrics = dict(item.split('=') for item in log_path.read_text().splitlines()[0].split()[1:])
+    assert metrics['limit'] == '3'
+    assert metrics['inflight'] == '1'
+    assert int(metrics['connections']) >= 1
+    assert metrics['rejected'] == '0'
+    assert metrics['rejected_total'] == '0'
+
+
+def test_adaptive_concurrency_settings_normalization():
+    server = Granian(
+        'tests.apps.rsgi:app', backpressure=10, adaptive_concurrency_settings=AdaptiveConcurrencySettings()
+    )
+    server.validate_adaptive_concurrency_settings()
+    assert server.adaptive_concurrency_settings.max_limit == 10
+    assert server.adaptive_concurrency_settings.initial_limit == 10
+
+    server = Granian(
+        'tests.apps.rsgi:app', adaptive_concurrency_settings=AdaptiveConcurrencySettings(min_limit=50)
+    )
+    server.validate_adaptive_concurrency_settings()
+    assert server.adaptive_concurrency_settings.initial_limit == 50
+
+
+@pytest.mark.parametrize(
+    'settings',
+    [
+        AdaptiveConcurrencySettings(min_limit=0),
+        AdaptiveConcurrencySettings(min_limit=10, max_limit=5),
+        AdaptiveConcurrencySettings(smoothing=0),
+    ],
+)
+def test_adaptive_concurrency_settings_errors(settings):
+    server = Granian('tests.apps.rsgi:app', adaptive_concurrency_settings=settings)
+    with pytest.raises(ConfigurationError):
+        server.validate_adaptive_concurrency_settings()
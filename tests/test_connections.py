@@ -0,0 +1,80 @@
+import asyncio
+import socket
+
+import httpx
+import pytest
+
+from granian.errors import ConfigurationError
+from granian.http import ConnectionSettings
+from granian.server import Granian
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize(
+    'settings,allowed',
+    [
+        (ConnectionSettings(deny=['127.0.0.1']), False),
+        (ConnectionSettings(deny=['10.0.0.0/8']), True),
+        (ConnectionSettings(allow=['10.0.0.0/8']), False),
+        (ConnectionSettings(allow=['127.0.0.0/8']), True),
+        (ConnectionSettings(allow=['127.0.0.0/8'], deny=['127.0.0.1/32']), False),
+    ],
+)
+async def test_ip_filters(rsgi_server, settings, allowed):
+    async with rsgi_server('runtime', connection_settings=settings) as port:
+        if allowed:
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 200
+        else:
+            with pytest.raises(httpx.TransportError):
+                httpx.get(f'http://127.0.0.1:{port}/info')
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_ip_filters_tls(rsgi_server, threading_mode):
+    settings = ConnectionSettings(deny=['127.0.0.1'])
+    async with rsgi_server(threading_mode, tls=True, connection_settings=settings) as port:
+        with pytest.raises(httpx.TransportError):
+            httpx.get(f'https://127.0.0.1:{port}/info', verify=False)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_max_connections_per_ip(rsgi_server, threading_mode):
+    settings = ConnectionSettings(max_per_ip=1)
+    async with rsgi_server(threading_mode, connection_settings=settings) as port:
+        # let the server release the connection used to check its readiness
+        await asyncio.sleep(0.5)
+        with socket.create_connection(('127.0.0.1', port)):
+            await asyncio.sleep(0.2)
+            with pytest.raises(httpx.TransportError):
+                httpx.get(f'http://127.0.0.1:{port}/info')
+
+        await asyncio.sleep(0.2)
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 200
+
+
+def test_connection_settings_normalization():
+    server = Granian(
+        'tests.apps.rsgi:app',
+        connection_settings=ConnectionSettings(allow=['10.1.2.3/8', '::ffff:0:0/96'], deny=['10.0.0.1']),
+    )
+    server.validate_connection_settings()
+    assert server.connection_settings.allow == ['10.0.0.0/8', '::ffff:0:0/96']
+    assert server.connection_settings.deny == ['10.0.0.1/32']
+
+
+@pytest.mark.parametrize(
+    'settings',
+    [
+        ConnectionSettings(allow=['10.0.0.0/33']),
+        ConnectionSettings(deny=['localhost']),
+        ConnectionSettings(max_per_ip=0),
+    ],
+)
+def test_connection_settings_errors(settings):
+    server = Granian('tests.apps.rsgi:app', connection_settings=settings)
+    with pytest.raises(ConfigurationError):
+        server.validate_connection_settings()
@@ -0,0 +1,123 @@
+import asyncio
+import re
+import socket
+
+import pytest
+
+from granian.http import HTTP1Settings
+
+
+def _request(path='/info', headers=''):
+    return f'GET {path} HTTP/1.1\r\nhost: localhost\r\n{headers}\r\n'.encode('latin1')
+
+
+def _read_response(sock):
+    data = b''
+    while b'\r\n\r\n' not in data:
+        chunk = sock.recv(65536)
+        if not chunk:
+            break
+        data += chunk
+    head, body = data.split(b'\r\n\r\n', 1)
+    head = head.decode('latin1').lower()
+    length = int(head.split('content-length:', 1)[1].split('\r\n', 1)[0])
+    while len(body) < length:
+        body += sock.recv(65536)
+    return head
+
+
+def _is_closed(sock):
+    try:
+        return sock.recv(1) == b''
+    except ConnectionResetError:
+        return True
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_max_requests(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode, http1_settings=HTTP1Settings(max_requests=2)) as port:
+        with socket.create_connection(('127.0.0.1', port), timeout=2) as sock:
+            sock.sendall(_request())
+            head = _read_response(sock)
+            assert head.startswith('http/1.1 200')
+            assert 'connection: close' not in head
+
+            sock.sendall(_request())
+            head = _read_response(sock)
+            assert head.startswith('http/1.1 200')
+            assert 'connection: close' in head
+            assert _is_closed(sock)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_keep_alive_timeout(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode, http1_settings=HTTP1Settings(keep_alive_timeout=1)) as port:
+        with socket.create_connection(('127.0.0.1', port), timeout=3) as sock:
+            sock.sendall(_request())
+            assert _read_response(sock).startswith('http/1.1 200')
+            await asyncio.sleep(0.5)
+            sock.sendall(_request())
+            assert _read_response(sock).startswith('http/1.1 200')
+            await asyncio.sleep(1.5)
+            assert _is_closed(sock)
+
+
+@pytest.mark.asyncio
+async def test_header_read_timeout(rsgi_server):
+    async with rsgi_server('runtime', http1_settings=HTTP1Settings(header_read_timeout=1)) as port:
+        with socket.create_connection(('127.0.0.1', port), timeout=3) as sock:
+            sock.sendall(b'GET /info HTTP/1.1\r\n')
+            await asyncio.sleep(1.5)
+            assert _is_closed(sock)
+
+        # idle connections are not subject to the header read timeout
+        with socket.create_connection(('127.0.0.1', port), timeout=3) as sock:
+            await asyncio.sleep(1.5)
+            sock.sendall(_request())
+            assert _read_response(sock).startswith('http/1.1 200')
+
+
+@pytest.mark.asyncio
+async def test_max_headers(rsgi_server):
+    async with rsgi_server('runtime', http1_settings=HTTP1Settings(max_headers=5)) as port:
+        with socket.create_connection(('127.0.0.1', port), timeout=2) as sock:
+            sock.sendall(_request(headers=''.join(f'x-header-{idx}: {idx}\r\n' for idx in range(10))))
+            assert _read_response(sock).startswith('http/1.1 431')
+
+
+def _read_pipelined(sock, count):
+    data = b''
+    while data.count(b'HTTP/1.1 200') < count or not data.endswith((b'test', b'unsupported')):
+        chunk = sock.recv(65536)
+        if not chunk:
+            break
+        data += chunk
+    return data
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('server', ['asgi', 'rsgi'], indirect=True)
+async def test_early_hints_pipelined(server):
+    async with server('runtime') as port:
+        with socket.create_connection(('127.0.0.1', port), timeout=2) as sock:
+            sock.sendall(b''.join(_request(f'/early_hints?{idx}') for idx in range(3)))
+            data = _read_pipelined(sock, 3)
+
+    responses = re.findall(rb'HTTP/1\.1 (\d{3})(.*?)\r\n\r\n', data, re.S)
+    assert [status for status, _ in responses] == [b'103', b'200'] * 3
+    for idx in range(3):
+        assert f'link: </style.css?{idx}>'.encode() in responses[idx * 2][1]
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('server', ['asgi', 'rsgi'], indirect=True)
+async def test_early_hints_pipeline_flush(server):
+    async with server('runtime', http1_settings=HTTP1Settings(pipeline_flush=True)) as port:
+        with socket.create_connection(('127.0.0.1', port), timeout=2) as sock:
+            sock.sendall(b''.join(_request(f'/early_hints?{idx}') for idx in range(3)))
+            data = _read_pipelined(sock, 3)
+
+    assert b'HTTP/1.1 103' not in data
+    assert data.count(b'\r\n\r\ntest') == 3
@@ -0,0 +1,109 @@
+import asyncio
+import socket
+import struct
+
+import pytest
+
+from granian.http import HTTP2Settings, LoadSheddingSettings
+
+
+H2_PREFACE = b'PRI * HTTP/2.0\r\n\r\nSM\r\n\r\n'
+H2_FRAME_DATA = 0x0
+H2_FRAME_HEADERS = 0x1
+H2_FRAME_RST_STREAM = 0x3
+H2_FRAME_SETTINGS = 0x4
+H2_FRAME_PING = 0x6
+H2_FRAME_GOAWAY = 0x7
+
+
+def _frame(kind, flags=0, stream=0, payload=b''):
+    return struct.pack('>I', len(payload))[1:] + struct.pack('>BBI', kind, flags, stream) + payload
+
+
+def _recv_frames(sock):
+    frames, data = [], b''
+    while True:
+        try:
+            chunk = sock.recv(65536)
+        except (TimeoutError, socket.timeout, ConnectionResetError):
+            break
+        if not chunk:
+            break
+        data += chunk
+    while len(data) >= 9:
+        length = int.from_bytes(data[:3], 'big')
+        frames.append((data[3], data[9 : 9 + length]))
+        data = data[9 + length :]
+    return frames
+
+
+def _read_frames(sock):
+    return [kind for kind, _ in _recv_frames(sock)]
+
+
+def _h2_connection(port, frames):
+    sock = socket.create_connection(('127.0.0.1', port), timeout=2)
+    sock.sendall(H2_PREFACE + _frame(H2_FRAME_SETTINGS) + b''.join(frames))
+    return sock
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_ping_flood(rsgi_server, threading_mode):
+    settings = HTTP2Settings(max_control_frames_per_second=10)
+    async with rsgi_server(threading_mode, http2_settings=settings) as port:
+        pings = [_frame(H2_FRAME_PING, payload=b'\x00' * 8) for _ in range(20)]
+        with _h2_connection(port, pings) as sock:
+            assert H2_FRAME_GOAWAY in _read_frames(sock)
+
+
+@pytest.mark.asyncio
+async def test_ping_below_limit(rsgi_server):
+    settings = HTTP2Settings(max_control_frames_per_second=10)
+    async with rsgi_server('runtime', http2_settings=settings) as port:
+        pings = [_frame(H2_FRAME_PING, payload=b'\x00' * 8) for _ in range(5)]
+        with _h2_connection(port, pings) as sock:
+            frames = _read_frames(sock)
+            assert frames.count(H2_FRAME_PING) == 5
+            assert H2_FRAME_GOAWAY not in frames
+
+
+@pytest.mark.asyncio
+async def test_rapid_reset(rsgi_server):
+    settings = HTTP2Settings(max_pending_accept_reset_streams=1000, max_resets_per_second=10)
+    async with rsgi_server('runtime', http2_settings=settings) as port:
+        frames = []
+        for stream in range(1, 80, 2):
+            # GET / with static HPACK entries, followed by a cancel
+            frames.append(_frame(H2_FRAME_HEADERS, 0x5, stream, b'\x82\x86\x84'))
+            frames.append(_frame(H2_FRAME_RST_STREAM, 0, stream, struct.pack('>I', 0x8)))
+        with _h2_connection(port, frames) as sock:
+            assert H2_FRAME_GOAWAY in _read_frames(sock)
+        await asyncio.sleep(0.2)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('server', ['asgi', 'rsgi'], indirect=True)
+async def test_early_hints_ignored(server):
+    async with server('runtime') as port:
+        # GET /early_hints with static HPACK entries and a literal path
+        headers = b'\x82\x86\x44\x0c/early_hints'
+        with _h2_connection(port, [_frame(H2_FRAME_HEADERS, 0x5, 1, headers)]) as sock:
+            frames = _recv_frames(sock)
+
+    assert [kind for kind, _ in frames].count(H2_FRAME_HEADERS) == 1
+    assert b''.join(payload for kind, payload in frames if kind == H2_FRAME_DATA) == b'test'
+
+
+@pytest.mark.asyncio
+async def test_load_shedding_goaway(rsgi_server):
+    async with rsgi_server('runtime', backpressure=1, load_shedding_settings=LoadSheddingSettings()) as port:
+        await asyncio.sleep(0.5)
+        with socket.create_connection(('127.0.0.1', port)):
+            await asyncio.sleep(0.2)
+            # GET / with static HPACK entries
+            with _h2_connection(port, [_frame(H2_FRAME_HEADERS, 0x5, 1, b'\x82\x86\x84')]) as sock:
+                frames = _read_frames(sock)
+
+    assert H2_FRAME_HEADERS in frames
+    assert H2_FRAME_GOAWAY in frames
@@ -1,11 +1,21 @@
+import datetime
 import json
 import pathlib
+import socket
 import ssl
 
 import httpx
 import pytest
 import websockets
 
+from granian._granian import validate_tls_config
+from granian.errors import ConfigurationError
+from granian.http import TLSSettings
+from granian.server import Granian
+
+
+TLS_FIXTURES = pathlib.Path.cwd() / 'tests' / 'fixtures' / 'tls'
+
 
 @pytest.mark.asyncio
 @pytest.mark.parametrize('server_tls', ['asgi', 'rsgi'], indirect=True)
@@ -19,6 +29,55 @@ async def test_http_scope(server_tls, threading_mode):
     assert data['scheme'] == 'https'
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_asgi_tls_extension(asgi_server, threading_mode):
+    async with asgi_server(threading_mode, tls=True) as port:
+        res = httpx.get(f'https://localhost:{port}/info', verify=False)
+
+    assert res.status_code == 200
+    tls = res.json()['extensions']['tls']
+    assert tls['server_cert'].startswith('-----BEGIN CERTIFICATE-----')
+    assert tls['client_cert_chain'] == []
+    assert tls['tls_version'] in (0x0303, 0x0304)
+    assert isinstance(tls['cipher_suite'], int)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_rsgi_tls_scope(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode, tls=True) as port:
+        res = httpx.get(f'https://localhost:{port}/tls_info', verify=False)
+    async with rsgi_server(threading_mode) as port:
+        res_plain = httpx.get(f'http://localhost:{port}/tls_info')
+
+    assert res.status_code == 200
+    data = res.json()
+    assert data['protocol'] == 'TLSv1.3'
+    assert data['cipher'] in ('TLS_AES_128_GCM_SHA256', 'TLS_AES_256_GCM_SHA384', 'TLS_CHACHA20_POLY1305_SHA256')
+    assert data['alpn_protocol'] == 'http/1.1'
+    assert data['sni'] == 'localhost'
+    assert res_plain.json() == {'protocol': None, 'cipher': None, 'alpn_protocol': None, 'sni': None}
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_wsgi_tls_environ(wsgi_server, threading_mode):
+    settings = TLSSettings(min_version='1.2', ciphers=['TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384'])
+    async with wsgi_server(threading_mode, tls=True, ssl_settings=settings) as port:
+        res = httpx.get(f'https://localhost:{port}/tls_info', verify=False)
+    async with wsgi_server(threading_mode) as port:
+        res_plain = httpx.get(f'http://localhost:{port}/tls_info')
+
+    assert res.status_code == 200
+    assert res.json() == {
+        'SSL_PROTOCOL': 'TLSv1.2',
+        'SSL_CIPHER': 'TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384',
+        'SSL_TLS_SNI': 'localhost',
+    }
+    assert res_plain.json() == {'SSL_PROTOCOL': None, 'SSL_CIPHER': None, 'SSL_TLS_SNI': None}
+
+
 @pytest.mark.asyncio
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
 async def test_asgi_ws_scope(asgi_server, threading_mode):
@@ -57,3 +116,185 @@ async def test_tls_encrypted_key(rsgi_server):
     assert res.status_code == 200
     data = res.json()
     assert data['scheme'] == 'https'
+
+
+@pytest.mark.asyncio
+async def test_tls_pkcs12_bundle(rsgi_server):
+    async with rsgi_server('workers', tls='pkcs12') as port:
+        res = httpx.get(f'https://localhost:{port}/info?test=true', verify=False)
+
+    assert res.status_code == 200
+    data = res.json()
+    assert data['scheme'] == 'https'
+
+
+def _tls_handshake(port, version, ciphers=None):
+    ssl_context = ssl.SSLContext(ssl.PROTOCOL_TLS_CLIENT)
+    ssl_context.check_hostname = False
+    ssl_context.verify_mode = ssl.CERT_NONE
+    ssl_context.minimum_version = ssl_context.maximum_version = version
+    if ciphers:
+        ssl_context.set_ciphers(ciphers)
+    with socket.create_connection(('localhost', port), timeout=3) as sock:
+        with ssl_context.wrap_socket(sock, server_hostname='localhost') as conn:
+            return conn.version(), conn.cipher()[0]
+
+
+@pytest.mark.asyncio
+async def test_tls_min_version(rsgi_server):
+    async with rsgi_server('workers', tls=True) as port:
+        with pytest.raises(ssl.SSLError):
+            _tls_handshake(port, ssl.TLSVersion.TLSv1_2)
+        assert _tls_handshake(port, ssl.TLSVersion.TLSv1_3)[0] == 'TLSv1.3'
+
+    settings = TLSSettings(min_version='1.2')
+    async with rsgi_server('workers', tls=True, ssl_settings=settings) as port:
+        assert _tls_handshake(port, ssl.TLSVersion.TLSv1_2)[0] == 'TLSv1.2'
+
+
+@pytest.mark.asyncio
+async def test_tls_ciphers(rsgi_server):
+    settings = TLSSettings(
+        min_version='1.2', ciphers=['TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384', 'TLS_AES_128_GCM_SHA256']
+    )
+    async with rsgi_server('workers', tls=True, ssl_settings=settings) as port:
+        assert _tls_handshake(port, ssl.TLSVersion.TLSv1_2) == ('TLSv1.2', 'ECDHE-RSA-AES256-GCM-SHA384')
+        assert _tls_handshake(port, ssl.TLSVersion.TLSv1_3) == ('TLSv1.3', 'TLS_AES_128_GCM_SHA256')
+        with pytest.raises(ssl.SSLError):
+            _tls_handshake(port, ssl.TLSVersion.TLSv1_2, 'ECDHE-RSA-AES128-GCM-SHA256')
+
+
+def test_tls_validation():
+    warnings = validate_tls_config(str(TLS_FIXTURES / 'pcert.pem'), str(TLS_FIXTURES / 'pkey.pem'), 'foobar')
+    assert warnings == []
+
+    warnings = validate_tls_config(str(TLS_FIXTURES / 'cert.pem'), str(TLS_FIXTURES / 'key.pem'))
+    assert warnings == [f'TLS certificate from {TLS_FIXTURES / "cert.pem"} is expired']
+
+
+@pytest.mark.parametrize(
+    'cert,key,password,http_mode,error',
+    [
+        ('missing.pem', 'key.pem', None, 'auto', 'Unable to load certificates'),
+        ('key.pem', 'key.pem', None, 'auto', 'No certificates found'),
+        ('pcert.pem', 'pkey.pem', None, 'auto', 'Unable to load private key'),
+        ('pcert.pem', 'pkey.pem', 'wrong', 'auto', 'Unable to load private key'),
+        ('cert.pem', 'pkey.pem', 'foobar', 'auto', "doesn't match private key"),
+        ('cert.pem', 'key.pem', None, '3', 'Unsupported HTTP mode'),
+    ],
+)
+def test_tls_validation_errors(cert, key, password, http_mode, error):
+    with pytest.raises(ValueError, match=error):
+        validate_tls_config(str(TLS_FIXTURES / cert), str(TLS_FIXTURES / key), password, http_mode)
+
+
+def test_tls_formats(tmp_path):
+    cert = (TLS_FIXTURES / 'pcert.pem').read_bytes()
+    key = (TLS_FIXTURES / 'pkey.pem').read_bytes()
+
+    # legacy OpenSSL encrypted PKCS#1 key
+    assert validate_tls_config(str(TLS_FIXTURES / 'pcert.pem'), str(TLS_FIXTURES / 'lkey.pem'), 'foobar') == []
+    # PKCS#12 bundle
+    assert validate_tls_config(str(TLS_FIXTURES / 'bundle.p12'), None, 'foobar') == []
+    # combined PEM
+    combined = tmp_path / 'combined.pem'
+    combined.write_bytes(cert + key)
+    assert validate_tls_config(str(combined), None, 'foobar') == []
+    # DER
+    der = tmp_path / 'cert.der'
+    der.write_bytes(ssl.PEM_cert_to_DER_cert(cert.decode()))
+    assert validate_tls_config(str(der), str(TLS_FIXTURES / 'pkey.pem'), 'foobar') == []
+    # in-memory
+    assert validate_tls_config(cert, key, 'foobar') == []
+    assert validate_tls_config((TLS_FIXTURES / 'bundle.p12').read_bytes(), None, 'foobar') == []
+
+
+@pytest.mark.parametrize(
+    'cert,key,password,error',
+    [
+        ('pcert.pem', 'lkey.pem', None, 'no password was provided'),
+        ('pcert.pem', 'lkey.pem', 'wrong', 'Cannot decrypt key'),
+        ('bundle.p12', None, 'wrong', 'Unable to load PKCS#12 bundle'),
+        ('pcert.pem', None, 'foobar', 'No private key found'),
+    ],
+)
+def test_tls_formats_errors(cert, key, password, error):
+    with pytest.raises(ValueError, match=error):
+        validate_tls_config(str(TLS_FIXTURES / cert), key and str(TLS_FIXTURES / key), password)
+
+
+@pytest.mark.parametrize(
+    'settings,error',
+    [
+        (TLSSettings(min_version='1.1'), 'Unsupported minimum TLS version'),
+        (TLSSettings(ciphers=['TLS_RSA_WITH_RC4_128_MD5']), 'Unsupported TLS cipher suite'),
+        (TLSSettings(ciphers=['TLS_ECDHE_RSA_WITH_AES_256_GCM_SHA384']), 'Invalid TLS settings'),
+    ],
+)
+def test_tls_settings_errors(settings, error):
+    with pytest.raises(ValueError, match=error):
+        validate_tls_config(str(TLS_FIXTURES / 'cert.pem'), str(TLS_FIXTURES / 'key.pem'), settings=settings)
+
+
+def _ocsp_with_next_update(path, next_update):
+    data = (TLS_FIXTURES / 'ocsp.der').read_bytes()
+    stamp = next_update.strftime('%Y%m%d%H%M%SZ').encode()
+    idx = data.rindex(b'\x18\x0f') + 2
+    path.write_bytes(data[:idx] + stamp + data[idx + 15 :])
+    return path
+
+
+def test_tls_ocsp_response(tmp_path):
+    now = datetime.datetime.now(datetime.timezone.utc)
+    cert, key = str(TLS_FIXTURES / 'pcert.pem'), str(TLS_FIXTURES / 'pkey.pem')
+
+    assert validate_tls_config(cert, key, 'foobar', settings=TLSSettings(ocsp_response=TLS_FIXTURES / 'ocsp.der')) == []
+
+    expiring = _ocsp_with_next_update(tmp_path / 'expiring.der', now + datetime.timedelta(hours=2, minutes=30))
+    warnings = validate_tls_config(cert, key, 'foobar', settings=TLSSettings(ocsp_response=expiring))
+    assert warnings == [f'OCSP response from {expiring} expires in 2 hours']
+
+    expired = _ocsp_with_next_update(tmp_path / 'expired.der', now - datetime.timedelta(hours=1))
+    warnings = validate_tls_config(cert, key, 'foobar', settings=TLSSettings(ocsp_response=expired))
+    assert warnings == [f'Unusable OCSP response from {expired}: OCSP response is expired, stapling disabled']
+
+    revoked = TLS_FIXTURES / 'ocsp_revoked.der'
+    warnings = validate_tls_config(cert, key, 'foobar', settings=TLSSettings(ocsp_response=revoked))
+    assert warnings == [
+        f'Unusable OCSP response from {revoked}: OCSP response reports the certificate as revoked, stapling disabled'
+    ]
+
+
+@pytest.mark.asyncio
+async def test_tls_ocsp_response_expired(rsgi_server, tmp_path):
+    now = datetime.datetime.now(datetime.timezone.utc)
+    expired = _ocsp_with_next_update(tmp_path / 'expired.der', now - datetime.timedelta(hours=1))
+    settings = TLSSettings(ocsp_response=expired)
+    async with rsgi_server('workers', tls='private', ssl_settings=settings) as port:
+        res = httpx.get(f'https://localhost:{port}/info', verify=False)
+
+    assert res.status_code == 200
+
+
+@pytest.mark.parametrize(
+    'cert,key,password,ocsp,error',
+    [
+        ('cert.pem', 'key.pem', None, 'ocsp.der', "doesn't refer to the certificate"),
+        ('pcert.pem', 'pkey.pem', 'foobar', 'pcert.pem', 'Unable to parse OCSP response'),
+        ('pcert.pem', 'pkey.pem', 'foobar', 'missing.der', 'Unable to load OCSP response'),
+    ],
+)
+def test_tls_ocsp_response_errors(cert, key, password, ocsp, error):
+    with pytest.raises(ValueError, match=error):
+        validate_tls_config(
+            str(TLS_FIXTURES / cert),
+            str(TLS_FIXTURES / key),
+            password,
+            settings=TLSSettings(ocsp_response=TLS_FIXTURES / ocsp),
+        )
+
+
+def test_tls_configuration_error():
+    server = Granian('tests.apps.asgi:app', ssl_cert=TLS_FIXTURES / 'cert.pem', ssl_key=TLS_FIXTURES / 'pkey.pem')
+    with pytest.raises(ConfigurationError):
+        server.validate_ssl_context()
@@ -0,0 +1,83 @@
+import sys
+
+import httpx
+import pytest
+
+from granian.errors import ConfigurationError
+from granian.http import RateLimitSettings
+from granian.server import Granian
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_rate_limit(rsgi_server, threading_mode):
+    settings = RateLimitSettings(rate=0.01, burst=2)
+    async with rsgi_server(threading_mode, rate_limit_settings=settings) as port:
+        for _ in range(2):
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 200
+
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 429
+        assert int(res.headers['retry-after']) >= 1
+        assert res.headers['ratelimit-limit'] == '2'
+        assert res.headers['ratelimit-remaining'] == '0'
+        assert int(res.headers['ratelimit-reset']) >= int(res.headers['retry-after'])
+
+
+@pytest.mark.asyncio
+async def test_rate_limit_header(rsgi_server):
+    settings = RateLimitSettings(rate=0.01, burst=1, key='header', header='x-api-key')
+    async with rsgi_server('runtime', rate_limit_settings=settings) as port:
+        res = httpx.get(f'http://127.0.0.1:{port}/info', headers={'x-api-key': 'foo'})
+        assert res.status_code == 200
+        res = httpx.get(f'http://127.0.0.1:{port}/info', headers={'x-api-key': 'foo'})
+        assert res.status_code == 429
+        res = httpx.get(f'http://127.0.0.1:{port}/info', headers={'x-api-key': 'bar'})
+        assert res.status_code == 200
+
+
+@pytest.mark.asyncio
+async def test_rate_limit_paths(rsgi_server):
+    settings = RateLimitSettings(rate=0.01, burst=1, paths=['/info'])
+    async with rsgi_server('runtime', rate_limit_settings=settings) as port:
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 200
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 429
+        for _ in range(2):
+            res = httpx.post(f'http://127.0.0.1:{port}/echo', content='test')
+            assert res.status_code == 200
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(sys.platform == 'win32', reason='shared memory rate limits are not supported on Windows')
+async def test_rate_limit_shared(rsgi_server):
+    settings = RateLimitSettings(rate=0.01, burst=2, shared=True)
+    async with rsgi_server('runtime', rate_limit_settings=settings, workers=2) as port:
+        statuses = [httpx.get(f'http://127.0.0.1:{port}/info').status_code for _ in range(4)]
+        assert statuses == [200, 200, 429, 429]
+
+
+def test_rate_limit_settings_normalization():
+    server = Granian('tests.apps.rsgi:app', rate_limit_settings=RateLimitSettings(rate=2.5))
+    server.validate_rate_limit_settings()
+    assert server.rate_limit_settings.burst == 3
+
+
+@pytest.mark.parametrize(
+    'settings',
+    [
+        RateLimitSettings(rate=0),
+        RateLimitSettings(rate=1, burst=0),
+        RateLimitSettings(rate=1, key='cookie'),
+        RateLimitSettings(rate=1, key='header'),
+        RateLimitSettings(rate=1, key='header', header='x api key'),
+        RateLimitSettings(rate=1, key='path'),
+        RateLimitSettings(rate=1, paths=['api']),
+    ],
+)
+def test_rate_limit_settings_errors(settings):
+    server = Granian('tests.apps.rsgi:app', rate_limit_settings=settings)
+    with pytest.raises(ConfigurationError):
+        server.validate_rate_limit_settings()
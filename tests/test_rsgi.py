@@ -1,5 +1,10 @@
+import asyncio
 import os
+import socket
 
+import h2.config
+import h2.connection
+import h2.events
 import httpx
 import pytest
 
@@ -36,6 +41,49 @@ async def test_body(rsgi_server, threading_mode):
     assert res.text == 'test'
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_expect_continue(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                f'POST /expect HTTP/1.1\r\nhost: localhost:{port}\r\nexpect: 100-continue\r\n'
+                'content-length: 4\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        interim = await asyncio.wait_for(reader.readuntil(b'\r\n\r\n'), 5)
+        writer.write(b'test')
+        await writer.drain()
+        head = await reader.readuntil(b'\r\n\r\n')
+        body = await reader.readexactly(4)
+        writer.close()
+
+    assert interim == b'HTTP/1.1 100 Continue\r\n\r\n'
+    assert head.startswith(b'HTTP/1.1 200')
+    assert body == b'test'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_expect_continue_reject(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                f'POST /expect HTTP/1.1\r\nhost: localhost:{port}\r\nexpect: 100-continue\r\n'
+                'x-reject: 1\r\ncontent-length: 4\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        res = await asyncio.wait_for(reader.read(), 5)
+        writer.close()
+
+    assert res.startswith(b'HTTP/1.1 413')
+    assert b'100 Continue' not in res
+
+
 @pytest.mark.asyncio
 @pytest.mark.skipif(not bool(os.getenv('PGO_RUN')), reason='not PGO build')
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
@@ -69,6 +117,16 @@ async def test_body_stream_res(rsgi_server, threading_mode):
     assert res.text == 'test' * 3
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_body_stream_res_large(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/stream_large')
+
+    assert res.status_code == 200
+    assert res.text == 'x' * 300_000
+
+
 @pytest.mark.asyncio
 @pytest.mark.skipif(bool(os.getenv('PGO_RUN')), reason='PGO build')
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
@@ -77,3 +135,234 @@ async def test_app_error(rsgi_server, threading_mode):
         res = httpx.get(f'http://localhost:{port}/err_app')
 
     assert res.status_code == 500
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(bool(os.getenv('PGO_RUN')), reason='PGO build')
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_response_error(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/err_response')
+        res_next = httpx.get(f'http://localhost:{port}/info')
+
+    assert res.status_code == 500
+    assert res_next.status_code == 200
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_headers_error(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/err_headers')
+
+    assert res.status_code == 200
+    assert res.text == 'ValueError,ValueError'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_client_disconnect(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        _, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(f'GET /disconnect HTTP/1.1\r\nhost: localhost:{port}\r\n\r\n'.encode('latin1'))
+        await writer.drain()
+        await asyncio.sleep(0.5)
+        writer.close()
+        await writer.wait_closed()
+        await asyncio.sleep(0.5)
+        res = httpx.get(f'http://localhost:{port}/disconnects')
+
+    assert res.status_code == 200
+    assert res.text == '1'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_client_disconnect_cancel(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode, cancel_on_disconnect=True, task_impl='asyncio') as port:
+        _, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(f'GET /cancel HTTP/1.1\r\nhost: localhost:{port}\r\n\r\n'.encode('latin1'))
+        await writer.drain()
+        await asyncio.sleep(0.5)
+        writer.close()
+        await writer.wait_closed()
+        await asyncio.sleep(0.5)
+        res = httpx.get(f'http://localhost:{port}/cancellations')
+
+    assert res.status_code == 200
+    assert res.text == '1'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_early_hints(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (f'GET /early_hints HTTP/1.1\r\nhost: localhost:{port}\r\nconnection: close\r\n\r\n').encode('latin1')
+        )
+        await writer.drain()
+        res = await reader.read()
+        writer.close()
+
+    hints, final = res.split(b'\r\n\r\n', 1)
+    assert hints == b'HTTP/1.1 103 Early Hints\r\nlink: </style.css>; rel=preload; as=style'
+    assert final.startswith(b'HTTP/1.1 200')
+    assert final.endswith(b'\r\n\r\ntest')
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_trailers(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                'POST /trailers HTTP/1.1\r\n'
+                f'host: localhost:{port}\r\n'
+                'te: trailers\r\n'
+                'trailer: x-checksum\r\n'
+                'transfer-encoding: chunked\r\n'
+                'connection: close\r\n\r\n'
+                '4\r\ntest\r\n0\r\nx-checksum: abc\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        res = await reader.read()
+        writer.close()
+
+    head, body = res.split(b'\r\n\r\n', 1)
+    assert head.startswith(b'HTTP/1.1 200')
+    assert b'transfer-encoding: chunked' in head
+    assert body == b'4\r\ntest\r\n0\r\nx-checksum: abc\r\n\r\n'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_sse(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/sse', headers={'last-event-id': '41'})
+
+    assert res.status_code == 200
+    assert res.headers['content-type'] == 'text/event-stream'
+    assert res.headers['cache-control'] == 'no-cache'
+    assert res.text.startswith('event: greet\nid: 41\ndata: hello\n\n:\n\n')
+    assert res.text.endswith('retry: 1000\ndata: multi\ndata: line\n\n')
+
+
+async def _grpc_request(port, path, messages, headers=''):
+    body = b''.join(b'\x00' + len(msg).to_bytes(4, 'big') + msg for msg in messages)
+    reader, writer = await asyncio.open_connection('127.0.0.1', port)
+    writer.write(
+        (
+            f'POST {path} HTTP/1.1\r\n'
+            f'host: localhost:{port}\r\n'
+            'content-type: application/grpc\r\n'
+            'te: trailers\r\n'
+            f'{headers}'
+            f'content-length: {len(body)}\r\n'
+            'connection: close\r\n\r\n'
+        ).encode('latin1')
+        + body
+    )
+    await writer.drain()
+    res = await reader.read()
+    writer.close()
+    return res.split(b'\r\n\r\n', 1)
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_grpc(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        head, body = await _grpc_request(port, '/grpc', [b'hello', b'world'])
+
+    assert head.startswith(b'HTTP/1.1 200')
+    assert b'content-type: application/grpc' in head
+    assert body == (
+        b'A\r\n\x00\x00\x00\x00\x05HELLO\r\n'
+        b'A\r\n\x00\x00\x00\x00\x05WORLD\r\n'
+        b'0\r\ngrpc-status: 0\r\n\r\n'
+    )
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_grpc_deadline(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        head, body = await _grpc_request(port, '/grpc_deadline', [], 'grpc-timeout: 100m\r\n')
+
+    assert head.startswith(b'HTTP/1.1 200')
+    assert body == b'0\r\ngrpc-status: 4\r\ngrpc-message: Deadline Exceeded\r\n\r\n'
+
+
+def _grpc_h2_request(port, path, messages, headers=()):
+    body = b''.join(b'\x00' + len(msg).to_bytes(4, 'big') + msg for msg in messages)
+    conn = h2.connection.H2Connection(config=h2.config.H2Configuration(client_side=True, header_encoding='utf-8'))
+    conn.initiate_connection()
+    conn.send_headers(
+        1,
+        [
+            (':method', 'POST'),
+            (':scheme', 'http'),
+            (':authority', f'localhost:{port}'),
+            (':path', path),
+            ('content-type', 'application/grpc'),
+            ('te', 'trailers'),
+            *headers,
+        ],
+    )
+    conn.send_data(1, body, end_stream=True)
+
+    res = {'headers': {}, 'data': b'', 'trailers': {}}
+    with socket.create_connection(('127.0.0.1', port), timeout=5) as sock:
+        sock.sendall(conn.data_to_send())
+        ended = False
+        while not ended:
+            data = sock.recv(65536)
+            if not data:
+                break
+            for event in conn.receive_data(data):
+                if isinstance(event, h2.events.ResponseReceived):
+                    res['headers'] = dict(event.headers)
+                elif isinstance(event, h2.events.DataReceived):
+                    res['data'] += event.data
+                    conn.acknowledge_received_data(event.flow_controlled_length, event.stream_id)
+                elif isinstance(event, h2.events.TrailersReceived):
+                    res['trailers'] = dict(event.headers)
+                elif isinstance(event, (h2.events.StreamEnded, h2.events.StreamReset)):
+                    ended = True
+            sock.sendall(conn.data_to_send())
+    return res
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_grpc_h2(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        res = _grpc_h2_request(port, '/grpc', [b'hello', b'world'])
+
+    assert res['headers'][':status'] == '200'
+    assert res['headers']['content-type'] == 'application/grpc'
+    assert res['data'] == b'\x00\x00\x00\x00\x05HELLO\x00\x00\x00\x00\x05WORLD'
+    assert res['trailers'] == {'grpc-status': '0'}
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_grpc_h2_error(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        res = _grpc_h2_request(port, '/grpc_error', [b'hello'])
+
+    assert res['headers'][':status'] == '200'
+    assert res['trailers']['grpc-status'] == '2'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_grpc_h2_deadline(rsgi_server, threading_mode):
+    async with rsgi_server(threading_mode) as port:
+        res = _grpc_h2_request(port, '/grpc_deadline', [], [('grpc-timeout', '100m')])
+
+    assert res['headers'][':status'] == '200'
+    assert res['trailers'] == {'grpc-status': '4', 'grpc-message': 'Deadline Exceeded'}
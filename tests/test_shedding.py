@@ -0,0 +1,60 @@
+import asyncio
+import socket
+
+import httpx
+import pytest
+
+from granian.errors import ConfigurationError
+from granian.http import LoadSheddingSettings
+from granian.server import Granian
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_load_shedding(rsgi_server, threading_mode):
+    settings = LoadSheddingSettings(retry_after=5)
+    async with rsgi_server(threading_mode, backpressure=1, load_shedding_settings=settings) as port:
+        # let the server release the connection used to check its readiness
+        await asyncio.sleep(0.5)
+        with socket.create_connection(('127.0.0.1', port)):
+            await asyncio.sleep(0.2)
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 503
+            assert res.headers['retry-after'] == '5'
+
+        await asyncio.sleep(0.2)
+        res = httpx.get(f'http://127.0.0.1:{port}/info')
+        assert res.status_code == 200
+
+
+@pytest.mark.asyncio
+async def test_load_shedding_queue_timeout(rsgi_server):
+    settings = LoadSheddingSettings(queue_timeout=1)
+    async with rsgi_server('runtime', backpressure=1, load_shedding_settings=settings) as port:
+        await asyncio.sleep(0.5)
+        with socket.create_connection(('127.0.0.1', port)):
+            await asyncio.sleep(0.2)
+            res = httpx.get(f'http://127.0.0.1:{port}/info')
+            assert res.status_code == 503
+
+        await asyncio.sleep(0.2)
+        held = socket.create_connection(('127.0.0.1', port))
+        await asyncio.sleep(0.2)
+        req = asyncio.create_task(asyncio.to_thread(httpx.get, f'http://127.0.0.1:{port}/info'))
+        await asyncio.sleep(0.3)
+        held.close()
+        res = await req
+        assert res.status_code == 200
+
+
+@pytest.mark.parametrize(
+    'settings',
+    [
+        LoadSheddingSettings(queue_timeout=-1),
+        LoadSheddingSettings(retry_after=-1),
+    ],
+)
+def test_load_shedding_settings_errors(settings):
+    server = Granian('tests.apps.rsgi:app', load_shedding_settings=settings)
+    with pytest.raises(ConfigurationError):
+        server.validate_load_shedding_settings()
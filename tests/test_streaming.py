@@ -0,0 +1,51 @@
+import asyncio
+import socket
+
+import httpx
+import pytest
+
+
+async def _open(port, path, rcvbuf=None):
+    sock = socket.socket(socket.AF_INET, socket.SOCK_STREAM)
+    if rcvbuf:
+        sock.setsockopt(socket.SOL_SOCKET, socket.SO_RCVBUF, rcvbuf)
+    sock.connect(('127.0.0.1', port))
+    reader, writer = await asyncio.open_connection(sock=sock)
+    writer.write(f'GET {path} HTTP/1.1\r\nhost: localhost:{port}\r\nconnection: close\r\n\r\n'.encode('latin1'))
+    await writer.drain()
+    return reader, writer
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('server', ['asgi', 'rsgi'], indirect=True)
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_slow_reader(server, threading_mode):
+    async with server(threading_mode, stream_buffer_size=65536) as port:
+        reader, writer = await _open(port, '/stream_slow', rcvbuf=8192)
+        await asyncio.sleep(1.5)
+        stalled = httpx.get(f'http://localhost:{port}/streamed').json()['sent']
+
+        received = 0
+        while data := await reader.read(65536):
+            received += len(data)
+        writer.close()
+        completed = httpx.get(f'http://localhost:{port}/streamed').json()['sent']
+
+    assert stalled < 256 * 65536 // 2
+    assert received > 256 * 65536
+    assert completed == 256 * 65536
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('server', ['asgi', 'rsgi'], indirect=True)
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_closed_client(server, threading_mode):
+    async with server(threading_mode, stream_buffer_size=65536) as port:
+        reader, writer = await _open(port, '/stream_infinite')
+        await reader.readexactly(65536)
+        writer.close()
+        await writer.wait_closed()
+        await asyncio.sleep(1)
+        res = httpx.get(f'http://localhost:{port}/streamed')
+
+    assert len(res.json()['errors']) == 1
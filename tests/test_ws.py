@@ -1,6 +1,7 @@
 import json
 import os
 
+import httpx
 import pytest
 import websockets
 
@@ -32,6 +33,52 @@ async def test_reject(server, threading_mode):
     assert exc.value.status_code == 403
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_asgi_deny(asgi_server, threading_mode):
+    async with asgi_server(threading_mode) as port:
+        res = httpx.get(
+            f'http://localhost:{port}/ws_deny',
+            headers={
+                'connection': 'upgrade',
+                'upgrade': 'websocket',
+                'sec-websocket-key': 'dGhlIHNhbXBsZSBub25jZQ==',
+                'sec-websocket-version': '13',
+            },
+        )
+
+    assert res.status_code == 401
+    assert res.headers['content-type'] == 'application/json'
+    assert res.json() == {'error': 'unauthorized'}
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('server', ['asgi', 'rsgi'], indirect=True)
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_fragments(server, threading_mode):
+    async with server(threading_mode) as port:
+        async with websockets.connect(f'ws://localhost:{port}/ws_fragments') as ws:
+            res_bytes = await ws.recv()
+            res_text = await ws.recv()
+
+    assert res_bytes == b'test' * 3
+    assert res_text == 'test' * 3
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+@pytest.mark.parametrize('message', [[b'foo', b'bar', b'baz'], ['caf', 'é', '!']])
+async def test_rsgi_fragments_receive(rsgi_server, threading_mode, message):
+    async with rsgi_server(threading_mode) as port:
+        async with websockets.connect(f'ws://localhost:{port}/ws_fragments_receive') as ws:
+            await ws.send(message)
+            res = await ws.recv()
+            res_count = await ws.recv()
+
+    assert res == message[0][:0].join(message)
+    assert res_count == str(len(message))
+
+
 @pytest.mark.asyncio
 @pytest.mark.skipif(bool(os.getenv('PGO_RUN')), reason='PGO build')
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
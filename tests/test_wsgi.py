@@ -1,3 +1,4 @@
+import asyncio
 import os
 
 import httpx
@@ -37,6 +38,49 @@ async def test_body(wsgi_server, threading_mode):
     assert res.text == 'test'
 
 
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_expect_continue(wsgi_server, threading_mode):
+    async with wsgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                f'POST /expect HTTP/1.1\r\nhost: localhost:{port}\r\nexpect: 100-continue\r\n'
+                'content-length: 4\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        interim = await asyncio.wait_for(reader.readuntil(b'\r\n\r\n'), 5)
+        writer.write(b'test')
+        await writer.drain()
+        head = await reader.readuntil(b'\r\n\r\n')
+        body = await reader.readexactly(4)
+        writer.close()
+
+    assert interim == b'HTTP/1.1 100 Continue\r\n\r\n'
+    assert head.startswith(b'HTTP/1.1 200')
+    assert body == b'test'
+
+
+@pytest.mark.asyncio
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_expect_continue_reject(wsgi_server, threading_mode):
+    async with wsgi_server(threading_mode) as port:
+        reader, writer = await asyncio.open_connection('127.0.0.1', port)
+        writer.write(
+            (
+                f'POST /expect HTTP/1.1\r\nhost: localhost:{port}\r\nexpect: 100-continue\r\n'
+                'x-reject: 1\r\ncontent-length: 4\r\n\r\n'
+            ).encode('latin1')
+        )
+        await writer.drain()
+        res = await asyncio.wait_for(reader.read(), 5)
+        writer.close()
+
+    assert res.startswith(b'HTTP/1.1 413')
+    assert b'100 Continue' not in res
+
+
 @pytest.mark.asyncio
 @pytest.mark.skipif(not bool(os.getenv('PGO_RUN')), reason='not PGO build')
 @pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
@@ -67,3 +111,15 @@ async def test_app_error(wsgi_server, threading_mode):
         res = httpx.get(f'http://localhost:{port}/err_app')
 
     assert res.status_code == 500
+
+
+@pytest.mark.asyncio
+@pytest.mark.skipif(bool(os.getenv('PGO_RUN')), reason='PGO build')
+@pytest.mark.parametrize('threading_mode', ['runtime', 'workers'])
+async def test_response_error(wsgi_server, threading_mode):
+    async with wsgi_server(threading_mode) as port:
+        res = httpx.get(f'http://localhost:{port}/err_response')
+        res_next = httpx.get(f'http://localhost:{port}/info')
+
+    assert res.status_code == 500
+    assert res_next.status_code == 200